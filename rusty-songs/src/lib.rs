@@ -1,4 +1,6 @@
+pub mod library;
 pub mod models;
+pub mod player;
 pub mod services;
 pub mod tui;
 pub mod utils;
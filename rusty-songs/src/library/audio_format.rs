@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::codecs::{
+    CodecParameters, CodecType, CODEC_TYPE_AAC, CODEC_TYPE_ALAC, CODEC_TYPE_FLAC, CODEC_TYPE_MP3,
+    CODEC_TYPE_OPUS, CODEC_TYPE_VORBIS,
+};
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::{Hint, ProbeResult};
+use symphonia::default::{get_codecs, get_probe};
+
+/// Extensions the library picks up. Playback goes through ffplay, which handles all of them.
+/// Symphonia reads the tags and headers of all of them, but cannot decode Opus, see `can_decode`.
+pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
+    "mp3", "flac", "ogg", "oga", "opus", "m4a", "mp4", "aac", "alac", "wav",
+];
+
+pub fn is_supported_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SUPPORTED_EXTENSIONS
+                .iter()
+                .any(|supported| ext.eq_ignore_ascii_case(supported))
+        })
+}
+
+/// Probes a file with Symphonia, using its extension as a hint
+pub fn probe(path: &Path) -> Option<ProbeResult> {
+    let file = File::open(path).ok()?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()
+}
+
+pub fn open_format(path: &Path) -> Option<Box<dyn FormatReader>> {
+    probe(path).map(|probed| probed.format)
+}
+
+/// Whether Symphonia has a decoder for a track. Its Ogg reader understands Opus streams, but
+/// there is no Opus decoder in Symphonia 0.5, so their duration comes from the Ogg pages and
+/// they are neither fingerprinted nor decoded by the library check.
+pub fn can_decode(params: &CodecParameters) -> bool {
+    get_codecs().get_codec(params.codec).is_some()
+}
+
+/// Short, human readable name of the codec of the default track
+pub fn codec_name(path: &Path) -> Option<String> {
+    let format = open_format(path)?;
+    let codec = format.default_track()?.codec_params.codec;
+    Some(describe_codec(codec))
+}
+
+fn describe_codec(codec: CodecType) -> String {
+    let name = match codec {
+        CODEC_TYPE_MP3 => "MP3",
+        CODEC_TYPE_FLAC => "FLAC",
+        CODEC_TYPE_VORBIS => "Vorbis",
+        CODEC_TYPE_OPUS => "Opus",
+        CODEC_TYPE_AAC => "AAC",
+        CODEC_TYPE_ALAC => "ALAC",
+        _ => {
+            // PCM variants and anything else Symphonia knows about, e.g. "pcm_s16le"
+            return get_codecs()
+                .get_codec(codec)
+                .map(|descriptor| {
+                    let name = descriptor.short_name;
+                    if name.starts_with("pcm") {
+                        "PCM".to_string()
+                    } else {
+                        name.to_uppercase()
+                    }
+                })
+                .unwrap_or_else(|| "Unknown".to_string());
+        }
+    };
+
+    name.to_string()
+}
@@ -0,0 +1,122 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Where the library lives and which parts of it to skip
+#[derive(Debug, Clone)]
+pub struct LibraryConfig {
+    pub roots: Vec<PathBuf>,
+    pub follow_symlinks: bool,
+    pub excludes: GlobSet,
+}
+
+impl LibraryConfig {
+    /// Reads the configuration from the environment:
+    /// - LIBRARY_ROOTS: library folders separated like PATH, defaults to MUSIC_DIR
+    /// - LIBRARY_FOLLOW_SYMLINKS: "true" or "1" to follow symbolic links
+    /// - LIBRARY_EXCLUDE: comma separated globs, e.g. "**/Podcasts/**,*.m3u"
+    pub fn from_env() -> Self {
+        let music_dir = env::var("MUSIC_DIR").unwrap_or_else(|_| "music".to_string());
+
+        let mut roots: Vec<PathBuf> = env::var_os("LIBRARY_ROOTS")
+            .map(|roots| env::split_paths(&roots).collect())
+            .unwrap_or_default();
+        roots.retain(|root| !root.as_os_str().is_empty());
+        if roots.is_empty() {
+            roots.push(PathBuf::from(music_dir));
+        }
+
+        let follow_symlinks = env::var("LIBRARY_FOLLOW_SYMLINKS")
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        let mut builder = GlobSetBuilder::new();
+        if let Ok(patterns) = env::var("LIBRARY_EXCLUDE") {
+            for pattern in patterns.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                match Glob::new(pattern) {
+                    Ok(glob) => {
+                        builder.add(glob);
+                    }
+                    Err(e) => eprintln!("Ignoring invalid exclude glob {}: {}", pattern, e),
+                }
+            }
+        }
+        let excludes = builder.build().unwrap_or_else(|_| GlobSet::empty());
+
+        LibraryConfig {
+            roots,
+            follow_symlinks,
+            excludes,
+        }
+    }
+
+    /// Globs are matched against the path relative to its library root, as well as the full path
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.excludes.is_empty() {
+            return false;
+        }
+
+        let relative = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok());
+
+        self.excludes.is_match(path) || relative.is_some_and(|rel| self.excludes.is_match(rel))
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(excludes: &[&str]) -> LibraryConfig {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in excludes {
+            builder.add(Glob::new(pattern).unwrap());
+        }
+        LibraryConfig {
+            roots: vec![PathBuf::from("/music"), PathBuf::from("/mnt/nas")],
+            follow_symlinks: false,
+            excludes: builder.build().unwrap(),
+        }
+    }
+
+    #[test]
+    fn excludes_match_below_any_root_or_the_full_path() {
+        let config = config(&["Podcasts/**", "/music/Live/*", "*.m3u"]);
+
+        for path in [
+            "/music/Podcasts/episode.mp3",
+            "/mnt/nas/Podcasts/episode.mp3",
+            "/music/Live/concert.flac",
+            "/music/Album/list.m3u",
+        ] {
+            assert!(config.is_excluded(Path::new(path)), "{}", path);
+        }
+        for path in [
+            "/music/Album/Podcasts/song.mp3",
+            "/mnt/nas/Live/concert.flac",
+            "/elsewhere/Podcasts/episode.mp3",
+        ] {
+            assert!(!config.is_excluded(Path::new(path)), "{}", path);
+        }
+        assert!(!LibraryConfig {
+            excludes: GlobSet::empty(),
+            ..config
+        }
+        .is_excluded(Path::new("/music/Podcasts/episode.mp3")));
+    }
+
+    #[test]
+    fn contains_only_paths_below_a_root() {
+        let config = config(&[]);
+        assert!(config.contains(Path::new("/mnt/nas/Album/song.mp3")));
+        assert!(config.contains(Path::new("/music")));
+        assert!(!config.contains(Path::new("/musicals/song.mp3")));
+        assert!(!config.contains(Path::new("song.mp3")));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::library::audio_format::probe;
+use id3::frame::PictureType;
+use id3::Tag;
+use std::fs;
+use std::path::Path;
+use symphonia::core::meta::StandardVisualKey;
+
+/// Image files next to the songs that are used as cover art for a whole folder
+const FOLDER_COVER_NAMES: [&str; 4] = ["cover", "folder", "front", "album"];
+const IMAGE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+fn id3_cover(path: &Path) -> Option<Vec<u8>> {
+    let tag = Tag::read_from_path(path).ok()?;
+    let cover = tag
+        .pictures()
+        .find(|picture| picture.picture_type == PictureType::CoverFront)
+        .or_else(|| tag.pictures().next())
+        .map(|picture| picture.data.clone());
+    cover
+}
+
+fn embedded_cover(path: &Path) -> Option<Vec<u8>> {
+    let mut probed = probe(path)?;
+
+    let mut revisions = Vec::new();
+    if let Some(revision) = probed.metadata.get().and_then(|m| m.current().cloned()) {
+        revisions.push(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current().cloned() {
+        revisions.push(revision);
+    }
+
+    let visuals: Vec<_> = revisions
+        .iter()
+        .flat_map(|revision| revision.visuals())
+        .collect();
+    visuals
+        .iter()
+        .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+        .or_else(|| visuals.first())
+        .map(|visual| visual.data.to_vec())
+}
+
+/// An image with the same name as the song, e.g. a thumbnail saved by yt-dlp, or a folder cover
+fn sidecar_cover(path: &Path) -> Option<Vec<u8>> {
+    let folder = path.parent()?;
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+
+    let cover = std::iter::once(stem.as_str())
+        .chain(FOLDER_COVER_NAMES)
+        .flat_map(|name| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .map(move |ext| folder.join(format!("{}.{}", name, ext)))
+        })
+        .find_map(|candidate| fs::read(candidate).ok());
+    cover
+}
+
+/// Cover art of a song: embedded in its tags, or else an image file next to it
+pub fn load_cover_art(path: &Path) -> Option<Vec<u8>> {
+    let is_mp3 = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"));
+    let embedded = if is_mp3 {
+        id3_cover(path)
+    } else {
+        embedded_cover(path)
+    };
+
+    embedded.or_else(|| sidecar_cover(path))
+}
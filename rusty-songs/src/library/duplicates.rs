@@ -0,0 +1,377 @@
+use crate::library::audio_format::{can_decode, open_format};
+use crate::library::index::IndexEntry;
+use crate::models::song::Song;
+use crate::utils::tag_tools::write_tags;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+
+/// Only the start of a song is fingerprinted, which is plenty to tell songs apart
+const FINGERPRINT_SECONDS: f64 = 120.0;
+
+/// Length of the windows whose loudness is compared
+const WINDOW_SECONDS: f64 = 0.1;
+
+/// How far (in windows) two fingerprints may be shifted against each other,
+/// to absorb different amounts of leading silence
+const MAX_SHIFT: usize = 10;
+
+/// Fraction of matching bits above which two songs count as the same recording
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Songs whose durations differ more than this are never considered similar
+const MAX_DURATION_DIFFERENCE: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateKind {
+    /// Byte for byte the same file
+    Identical,
+    /// The same recording, e.g. downloaded twice or encoded differently
+    Similar,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    pub songs: Vec<Song>,
+}
+
+/// SHA-256 of the whole file, hex encoded
+pub fn content_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// A compact acoustic fingerprint: one bit per window, set when the window is louder than the
+/// previous one. This survives re-encoding, different bitrates and volume changes.
+pub fn fingerprint(path: &Path) -> Option<Vec<u32>> {
+    let mut format = open_format(path)?;
+    let track = format.default_track()?;
+    if !can_decode(&track.codec_params) {
+        return None;
+    }
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate?;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &Default::default())
+        .ok()?;
+
+    let window = ((f64::from(sample_rate) * WINDOW_SECONDS) as usize).max(1);
+    let max_windows = (FINGERPRINT_SECONDS / WINDOW_SECONDS) as usize;
+
+    let mut energies = Vec::with_capacity(max_windows);
+    let mut energy = 0.0f32;
+    let mut frames_in_window = 0;
+
+    while energies.len() < max_windows {
+        let Ok(packet) = format.next_packet() else {
+            break;
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let Ok(decoded) = decoder.decode(&packet) else {
+            continue;
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        samples.copy_interleaved_ref(decoded);
+
+        for frame in samples.samples().chunks(channels) {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            energy += mono * mono;
+            frames_in_window += 1;
+
+            if frames_in_window == window {
+                energies.push(energy);
+                energy = 0.0;
+                frames_in_window = 0;
+            }
+        }
+    }
+
+    if energies.len() < 2 {
+        return None;
+    }
+
+    let mut bits = vec![0u32; energies.len().div_ceil(32)];
+    for (i, pair) in energies.windows(2).enumerate() {
+        if pair[1] > pair[0] {
+            bits[i / 32] |= 1 << (i % 32);
+        }
+    }
+
+    Some(bits)
+}
+
+fn unpack(fingerprint: &[u32]) -> Vec<bool> {
+    (0..fingerprint.len() * 32)
+        .map(|i| fingerprint[i / 32] & (1 << (i % 32)) != 0)
+        .collect()
+}
+
+/// Best fraction of matching bits over all allowed shifts
+fn similarity(a: &[bool], b: &[bool]) -> f64 {
+    let mut best = 0.0f64;
+
+    for shift in 0..=MAX_SHIFT {
+        for (first, second) in [(a, b), (b, a)] {
+            if shift >= first.len() {
+                continue;
+            }
+            let shifted = &first[shift..];
+            let len = shifted.len().min(second.len());
+            if len == 0 {
+                continue;
+            }
+
+            let matching = shifted
+                .iter()
+                .zip(second.iter())
+                .take(len)
+                .filter(|(x, y)| x == y)
+                .count();
+            best = best.max(matching as f64 / len as f64);
+        }
+    }
+
+    best
+}
+
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/// Groups indexed songs that are identical or sound the same
+pub fn find_duplicates(entries: &[&IndexEntry]) -> Vec<DuplicateGroup> {
+    let fingerprints: Vec<Option<Vec<bool>>> = entries
+        .iter()
+        .map(|entry| entry.fingerprint.as_deref().map(unpack))
+        .collect();
+
+    let mut parents: Vec<usize> = (0..entries.len()).collect();
+    let mut identical_only = vec![true; entries.len()];
+
+    for i in 0..entries.len() {
+        for j in (i + 1)..entries.len() {
+            let identical = entries[i].content_hash.is_some()
+                && entries[i].content_hash == entries[j].content_hash;
+
+            // Two downloads of the same video are the same song, even when encoded differently
+            let same_video = entries[i].song.tags.video_id.is_some()
+                && entries[i].song.tags.video_id == entries[j].song.tags.video_id;
+
+            let similar = !identical
+                && (same_video
+                    || (entries[i].song.duration - entries[j].song.duration).abs()
+                        <= MAX_DURATION_DIFFERENCE
+                        && match (&fingerprints[i], &fingerprints[j]) {
+                            (Some(a), Some(b)) => similarity(a, b) >= SIMILARITY_THRESHOLD,
+                            _ => false,
+                        });
+
+            if identical || similar {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[root_j] = root_i;
+                if similar {
+                    identical_only[i] = false;
+                    identical_only[j] = false;
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for i in 0..entries.len() {
+        let root = find_root(&mut parents, i);
+        match groups
+            .iter_mut()
+            .find(|(group_root, _)| *group_root == root)
+        {
+            Some((_, members)) => members.push(i),
+            None => groups.push((root, vec![i])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, members)| DuplicateGroup {
+            kind: if members.iter().all(|&i| identical_only[i]) {
+                DuplicateKind::Identical
+            } else {
+                DuplicateKind::Similar
+            },
+            songs: members.iter().map(|&i| entries[i].song.clone()).collect(),
+        })
+        .collect()
+}
+
+/// Copies the tags the kept song is missing from its duplicates, then deletes the duplicates
+pub fn merge_duplicates(keep: &Song, duplicates: &[Song]) -> Result<(), Box<dyn Error>> {
+    let mut tags = keep.tags.clone();
+    for duplicate in duplicates {
+        tags.merge_missing(&duplicate.tags);
+    }
+
+    if tags != keep.tags {
+        write_tags(&keep.path, &tags)?;
+    }
+
+    for duplicate in duplicates {
+        fs::remove_file(&duplicate.path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::index::FileStamp;
+    use crate::models::song::{SongStats, TrackTags};
+    use crate::utils::tag_tools::read_tags;
+    use std::env;
+    use std::path::PathBuf;
+
+    fn song(path: PathBuf, tags: TrackTags) -> Song {
+        Song {
+            title: path.file_stem().unwrap().to_string_lossy().to_string(),
+            duration: 200.0,
+            path,
+            codec: "MP3".to_string(),
+            tags,
+            stats: SongStats::default(),
+        }
+    }
+
+    fn entry(
+        name: &str,
+        hash: &str,
+        video_id: Option<&str>,
+        fingerprint: Option<Vec<u32>>,
+    ) -> IndexEntry {
+        IndexEntry {
+            stamp: FileStamp { mtime: 1, size: 1 },
+            song: song(
+                PathBuf::from(format!("{}.mp3", name)),
+                TrackTags {
+                    video_id: video_id.map(str::to_string),
+                    ..TrackTags::default()
+                },
+            ),
+            content_hash: Some(hash.to_string()),
+            fingerprint,
+        }
+    }
+
+    fn titles(group: &DuplicateGroup) -> Vec<&str> {
+        group.songs.iter().map(|song| song.title.as_str()).collect()
+    }
+
+    #[test]
+    fn similarity_absorbs_shifts_but_not_different_songs() {
+        let bits: Vec<bool> = (0..200).map(|i| (i * 7) % 5 < 2).collect();
+
+        assert_eq!(similarity(&bits, &bits), 1.0);
+        // Leading silence only shifts the windows
+        assert_eq!(similarity(&bits, &bits[3..]), 1.0);
+        assert_eq!(similarity(&bits[3..], &bits), 1.0);
+        assert_eq!(similarity(&[true; 200], &[false; 200]), 0.0);
+    }
+
+    #[test]
+    fn groups_are_joined_through_shared_members() {
+        let entries = [
+            entry("a", "first", Some("dQw4w9WgXcQ"), None),
+            // The same video as a, and byte for byte the same file as c
+            entry("b", "second", Some("dQw4w9WgXcQ"), None),
+            entry("c", "second", None, None),
+            entry("d", "third", None, None),
+        ];
+
+        let groups = find_duplicates(&entries.iter().collect::<Vec<_>>());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(titles(&groups[0]), vec!["a", "b", "c"]);
+        assert_eq!(groups[0].kind, DuplicateKind::Similar);
+    }
+
+    #[test]
+    fn undecodable_songs_never_sound_alike() {
+        let bits = vec![0x1234_5678, 0x9abc_def0];
+        let entries = [
+            entry("a", "first", None, Some(bits.clone())),
+            entry("b", "second", None, Some(bits)),
+            // Empty fingerprints mark files that could not be decoded
+            entry("c", "third", None, Some(Vec::new())),
+            entry("d", "fourth", None, Some(Vec::new())),
+        ];
+
+        let groups = find_duplicates(&entries.iter().collect::<Vec<_>>());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(titles(&groups[0]), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn merging_keeps_missing_tags_and_deletes_only_the_duplicates() {
+        let path = |name: &str| {
+            let path = env::temp_dir().join(format!(
+                "rusty_songs_duplicates_{}_{}.mp3",
+                name,
+                std::process::id()
+            ));
+            fs::write(&path, []).unwrap();
+            path
+        };
+        let keep = song(
+            path("keep"),
+            TrackTags {
+                title: Some("Song".to_string()),
+                ..TrackTags::default()
+            },
+        );
+        let duplicate = song(
+            path("duplicate"),
+            TrackTags {
+                title: Some("Other title".to_string()),
+                artist: Some("Artist".to_string()),
+                ..TrackTags::default()
+            },
+        );
+
+        merge_duplicates(&keep, std::slice::from_ref(&duplicate)).unwrap();
+
+        let tags = read_tags(&keep.path);
+        assert_eq!(tags.title.as_deref(), Some("Song"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert!(!duplicate.path.exists());
+
+        fs::remove_file(&keep.path).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use crate::library::audio_format::open_format;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use symphonia::core::codecs::CodecParameters;
+use symphonia::core::units::TimeBase;
+
+/// Returns the duration of an audio file in seconds, trying the cheapest source first:
+/// container headers, then Symphonia's header information and a packet count, and only then
+/// a full decode.
+pub fn get_duration(path: &Path) -> Option<f64> {
+    container_duration(path)
+        .or_else(|| probe_duration(path).filter(|duration| *duration > 0.0))
+        .or_else(|| decode_duration(path))
+}
+
+/// The duration the file's headers state, without reading the stream
+pub fn header_duration(path: &Path) -> Option<f64> {
+    container_duration(path).or_else(|| {
+        let format = open_format(path)?;
+        let params = &format.default_track()?.codec_params;
+        Some(to_seconds(time_base(params)?, params.n_frames?))
+    })
+}
+
+/// Reads the duration straight from FLAC STREAMINFO, the MP4 `mvhd` box or the last Ogg page
+fn container_duration(path: &Path) -> Option<f64> {
+    let mut file = File::open(path).ok()?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic).ok()?;
+    file.seek(SeekFrom::Start(0)).ok()?;
+
+    if &magic[0..4] == b"fLaC" {
+        flac_streaminfo_duration(&mut file)
+    } else if &magic[4..8] == b"ftyp" {
+        mp4_mvhd_duration(&mut file)
+    } else if &magic[0..4] == b"OggS" {
+        ogg_duration(&mut file)
+    } else {
+        None
+    }
+}
+
+fn flac_streaminfo_duration(file: &mut File) -> Option<f64> {
+    // "fLaC" marker, 4 byte metadata block header, then the 34 byte STREAMINFO block
+    let mut buf = [0u8; 42];
+    file.read_exact(&mut buf).ok()?;
+    if buf[4] & 0x7f != 0 {
+        return None; // The first block must be STREAMINFO
+    }
+
+    let info = &buf[8..];
+    let sample_rate =
+        (u32::from(info[10]) << 12) | (u32::from(info[11]) << 4) | (u32::from(info[12]) >> 4);
+    let total_samples = (u64::from(info[13] & 0x0f) << 32)
+        | u64::from(u32::from_be_bytes([info[14], info[15], info[16], info[17]]));
+
+    if sample_rate == 0 || total_samples == 0 {
+        return None;
+    }
+    Some(total_samples as f64 / f64::from(sample_rate))
+}
+
+/// Fixed part of an Ogg page header, followed by the segment table
+const OGG_PAGE_HEADER_LEN: usize = 27;
+
+/// Pages are at most this long, so the last one starts within this many bytes of the end
+const OGG_MAX_PAGE_LEN: u64 = 65_307;
+
+/// Granule position of the last page of the first logical stream, in samples, less the
+/// samples an Opus decoder skips at the start. Needs no decoder, which matters for Opus.
+fn ogg_duration<R: Read + Seek>(file: &mut R) -> Option<f64> {
+    let mut header = [0u8; OGG_PAGE_HEADER_LEN];
+    file.read_exact(&mut header).ok()?;
+    let serial = &header[14..18];
+    let mut segments = vec![0u8; usize::from(header[26])];
+    file.read_exact(&mut segments).ok()?;
+
+    // The first page holds the identification header of the codec
+    let mut first_packet = vec![0u8; segments.iter().map(|&len| usize::from(len)).sum()];
+    file.read_exact(&mut first_packet).ok()?;
+    let (sample_rate, pre_skip) = if first_packet.starts_with(b"OpusHead") {
+        // Opus granules always count at 48 kHz, whatever the input rate was
+        let pre_skip = u16::from_le_bytes(first_packet.get(10..12)?.try_into().ok()?);
+        (48_000, u64::from(pre_skip))
+    } else if first_packet.starts_with(b"\x01vorbis") {
+        let rate = u32::from_le_bytes(first_packet.get(12..16)?.try_into().ok()?);
+        (rate, 0)
+    } else {
+        return None;
+    };
+
+    let len = file.seek(SeekFrom::End(0)).ok()?;
+    let tail_start = len.saturating_sub(OGG_MAX_PAGE_LEN + OGG_PAGE_HEADER_LEN as u64);
+    file.seek(SeekFrom::Start(tail_start)).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+
+    // Pages whose last packet continues on the next page have no granule position (-1)
+    let granule = (0..tail.len().saturating_sub(OGG_PAGE_HEADER_LEN))
+        .rev()
+        .filter(|&i| {
+            &tail[i..i + 4] == b"OggS" && tail[i + 4] == 0 && &tail[i + 14..i + 18] == serial
+        })
+        .map(|i| u64::from_le_bytes(tail[i + 6..i + 14].try_into().unwrap()))
+        .find(|&granule| granule != u64::MAX)?;
+
+    if sample_rate == 0 || granule <= pre_skip {
+        return None;
+    }
+    Some((granule - pre_skip) as f64 / f64::from(sample_rate))
+}
+
+fn mp4_mvhd_duration(file: &mut File) -> Option<f64> {
+    let file_len = file.metadata().ok()?.len();
+    let moov = find_mp4_box(file, 0, file_len, b"moov")?;
+    let (mvhd_start, _) = find_mp4_box(file, moov.0, moov.1, b"mvhd")?;
+
+    file.seek(SeekFrom::Start(mvhd_start)).ok()?;
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version).ok()?;
+
+    let (timescale, duration) = if version[0] == 1 {
+        let mut buf = [0u8; 28];
+        file.read_exact(&mut buf).ok()?;
+        (
+            u32::from_be_bytes(buf[16..20].try_into().ok()?),
+            u64::from_be_bytes(buf[20..28].try_into().ok()?),
+        )
+    } else {
+        let mut buf = [0u8; 16];
+        file.read_exact(&mut buf).ok()?;
+        (
+            u32::from_be_bytes(buf[8..12].try_into().ok()?),
+            u64::from(u32::from_be_bytes(buf[12..16].try_into().ok()?)),
+        )
+    };
+
+    if timescale == 0 || duration == 0 || duration == u64::from(u32::MAX) {
+        return None;
+    }
+    Some(duration as f64 / f64::from(timescale))
+}
+
+/// Walks the boxes between `start` and `end`, returning the payload range of the first match.
+/// Sizes come straight from the file, a box that claims to reach past its parent ends the walk.
+fn find_mp4_box<R: Read + Seek>(
+    file: &mut R,
+    start: u64,
+    end: u64,
+    name: &[u8; 4],
+) -> Option<(u64, u64)> {
+    let mut offset = start;
+
+    while end.checked_sub(offset)? >= 8 {
+        let remaining = end - offset;
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).ok()?;
+
+        let mut size = u64::from(u32::from_be_bytes(header[0..4].try_into().ok()?));
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large).ok()?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        } else if size == 0 {
+            size = remaining; // The box extends to the end of its parent
+        }
+
+        if size < header_len || size > remaining {
+            return None;
+        }
+        if &header[4..8] == name {
+            return Some((offset + header_len, offset + size));
+        }
+        offset += size;
+    }
+
+    None
+}
+
+fn time_base(params: &CodecParameters) -> Option<TimeBase> {
+    params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)))
+}
+
+fn to_seconds(time_base: TimeBase, ts: u64) -> f64 {
+    let time = time_base.calc_time(ts);
+    time.seconds as f64 + time.frac
+}
+
+/// Uses the frame count Symphonia derives from headers (Xing/Info/VBRI for MP3),
+/// otherwise sums packet durations without decoding them
+fn probe_duration(path: &Path) -> Option<f64> {
+    let mut format = open_format(path)?;
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let time_base = time_base(&track.codec_params)?;
+
+    if let Some(n_frames) = track.codec_params.n_frames {
+        return Some(to_seconds(time_base, n_frames));
+    }
+
+    let mut total = 0;
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() == track_id {
+            total += packet.dur;
+        }
+    }
+
+    Some(to_seconds(time_base, total))
+}
+
+/// Last resort: decode every packet and count the frames
+fn decode_duration(path: &Path) -> Option<f64> {
+    let mut format = open_format(path)?;
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &Default::default())
+        .ok()?;
+
+    let mut total_duration = 0.0;
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        if let Ok(decoded) = decoder.decode(&packet) {
+            total_duration += decoded.frames() as f64 / sample_rate as f64;
+        }
+    }
+
+    Some(total_duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = (8 + payload.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(name);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn finds_nested_boxes() {
+        let mvhd = mp4_box(b"mvhd", &[0; 4]);
+        let mut data = mp4_box(b"ftyp", b"M4A ");
+        data.extend(mp4_box(b"moov", &[mp4_box(b"free", &[]), mvhd].concat()));
+        let end = data.len() as u64;
+        let mut file = Cursor::new(data);
+
+        let moov = find_mp4_box(&mut file, 0, end, b"moov").unwrap();
+        assert_eq!(moov, (20, end));
+        assert_eq!(
+            find_mp4_box(&mut file, moov.0, moov.1, b"mvhd"),
+            Some((36, 40))
+        );
+        assert_eq!(find_mp4_box(&mut file, 0, end, b"mdat"), None);
+    }
+
+    #[test]
+    fn rejects_boxes_larger_than_their_parent() {
+        // A 64 bit size that would overflow the offset
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"free");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        data.extend(mp4_box(b"moov", &[]));
+        let end = data.len() as u64;
+
+        assert_eq!(find_mp4_box(&mut Cursor::new(data), 0, end, b"moov"), None);
+    }
+
+    #[test]
+    fn stops_at_truncated_boxes() {
+        let mut data = mp4_box(b"ftyp", b"M4A ");
+        data.extend_from_slice(&100u32.to_be_bytes());
+        data.extend_from_slice(b"moov");
+        let end = data.len() as u64;
+
+        assert_eq!(find_mp4_box(&mut Cursor::new(data), 0, end, b"moov"), None);
+        assert_eq!(find_mp4_box(&mut Cursor::new(vec![]), 8, 4, b"moov"), None);
+    }
+
+    fn ogg_page(granule: u64, serial: u32, packet: &[u8]) -> Vec<u8> {
+        let mut lacing = vec![255u8; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+
+        let mut page = b"OggS\0\0".to_vec();
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&serial.to_le_bytes());
+        page.extend_from_slice(&[0; 8]); // Sequence number and checksum
+        page.push(lacing.len() as u8);
+        page.extend(lacing);
+        page.extend_from_slice(packet);
+        page
+    }
+
+    fn opus_head(pre_skip: u16) -> Vec<u8> {
+        let mut head = b"OpusHead\x01\x02".to_vec();
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&44_100u32.to_le_bytes());
+        head.extend_from_slice(&[0; 3]);
+        head
+    }
+
+    #[test]
+    fn reads_opus_duration_from_the_last_granule() {
+        let mut data = ogg_page(0, 7, &opus_head(312));
+        data.extend(ogg_page(0, 7, b"OpusTags"));
+        data.extend(ogg_page(48_000, 7, &[1; 300]));
+        data.extend(ogg_page(3 * 48_000 + 312, 7, &[2; 100]));
+        // A packet continuing past the end of the page, and another stream
+        data.extend(ogg_page(u64::MAX, 7, &[3; 10]));
+        data.extend(ogg_page(10 * 48_000, 8, &[4; 10]));
+
+        assert_eq!(ogg_duration(&mut Cursor::new(data)), Some(3.0));
+    }
+
+    #[test]
+    fn reads_vorbis_duration_at_its_sample_rate() {
+        let mut head = b"\x01vorbis".to_vec();
+        head.extend_from_slice(&0u32.to_le_bytes());
+        head.push(2);
+        head.extend_from_slice(&44_100u32.to_le_bytes());
+        let mut data = ogg_page(0, 1, &head);
+        data.extend(ogg_page(88_200, 1, &[0; 20]));
+
+        assert_eq!(ogg_duration(&mut Cursor::new(data)), Some(2.0));
+    }
+
+    #[test]
+    fn ignores_ogg_streams_without_a_known_codec_or_length() {
+        let mut data = ogg_page(0, 1, b"\x7fFLAC");
+        data.extend(ogg_page(88_200, 1, &[0; 20]));
+        assert_eq!(ogg_duration(&mut Cursor::new(data)), None);
+
+        let data = ogg_page(0, 1, &opus_head(312));
+        assert_eq!(ogg_duration(&mut Cursor::new(data)), None);
+    }
+}
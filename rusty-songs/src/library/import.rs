@@ -0,0 +1,123 @@
+use crate::library::config::LibraryConfig;
+use crate::library::duplicates::content_hash;
+use crate::library::index::{is_audio_file, walk_audio_files};
+use crate::library::naming::NamingTemplate;
+use crate::utils::file_names::unique_path;
+use crate::utils::tag_tools::read_tags;
+use globset::GlobSet;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether imported files stay where they were
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    Copy,
+    Move,
+}
+
+/// One file of an import and where it ends up
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// Why the file is left alone, e.g. because the destination is taken
+    pub skip: Option<String>,
+}
+
+/// Works out the destination of every audio file in `inputs`, without touching anything.
+/// Folders are imported recursively, and the library excludes only apply to folders inside
+/// the library.
+pub fn plan_import(
+    inputs: &[PathBuf],
+    template: &NamingTemplate,
+    music_dir: &Path,
+) -> Vec<ImportItem> {
+    let config = LibraryConfig::from_env();
+    // Exclude globs like `*/Downloads/*` describe the library, not where songs come from
+    let outside = LibraryConfig {
+        excludes: GlobSet::empty(),
+        ..config.clone()
+    };
+    let mut sources = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let walk_config = if config.contains(input) {
+                &config
+            } else {
+                &outside
+            };
+            let mut files: Vec<PathBuf> = walk_audio_files(walk_config, input).collect();
+            files.sort();
+            sources.extend(files);
+        } else if is_audio_file(input) {
+            sources.push(input.clone());
+        } else {
+            eprintln!("Skipping {}, not an audio file", input.display());
+        }
+    }
+
+    let mut taken = HashSet::new();
+    sources
+        .into_iter()
+        .map(|source| {
+            let mut tags = read_tags(&source);
+            if tags.title.is_none() {
+                tags.title = source
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string());
+            }
+            let extension = source
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let destination = music_dir.join(template.render(&tags, &extension));
+
+            // The same file imported twice is skipped, a different one of the same name
+            // gets a suffix
+            let skip = if destination == source {
+                Some("already in place".to_string())
+            } else if destination.exists() && same_content(&source, &destination) {
+                Some("already imported".to_string())
+            } else {
+                None
+            };
+            let destination = if skip.is_none() {
+                let free = unique_path(&destination, |candidate| {
+                    candidate.exists() || taken.contains(candidate)
+                });
+                taken.insert(free.clone());
+                free
+            } else {
+                destination
+            };
+
+            ImportItem {
+                source,
+                destination,
+                skip,
+            }
+        })
+        .collect()
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    matches!((content_hash(a), content_hash(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Copies or moves one planned file, creating its folders
+pub fn import_file(item: &ImportItem, mode: ImportMode) -> io::Result<()> {
+    if let Some(parent) = item.destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match mode {
+        ImportMode::Copy => fs::copy(&item.source, &item.destination).map(|_| ()),
+        // Renaming fails across file systems, e.g. from an external drive
+        ImportMode::Move => fs::rename(&item.source, &item.destination).or_else(|_| {
+            fs::copy(&item.source, &item.destination)?;
+            fs::remove_file(&item.source)
+        }),
+    }
+}
@@ -0,0 +1,719 @@
+use crate::library::audio_format::{codec_name, is_supported_extension};
+use crate::library::config::LibraryConfig;
+use crate::library::duplicates::content_hash;
+use crate::library::duration::get_duration;
+use crate::models::song::{Song, SongStats};
+use crate::utils::tag_tools::read_tags;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Modification time and size of a file, used to tell whether it changed since it was indexed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub mtime: u64,
+    pub size: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+
+        Some(FileStamp {
+            mtime,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Suffixes of files that are still being produced by yt-dlp or `process_video_to_audio`
+const PARTIAL_SUFFIXES: [&str; 4] = [".part", ".ytdl", ".temp.mp3", ".tmp"];
+
+pub fn is_partial_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+
+    name.starts_with('.')
+        || name.contains(".part-Frag")
+        || PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    is_supported_extension(path) && !is_partial_file(path)
+}
+
+/// Recursively lists the audio files below `dir`, honouring the exclude globs and symlink setting
+pub fn walk_audio_files<'a>(
+    config: &'a LibraryConfig,
+    dir: &Path,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    WalkDir::new(dir)
+        .follow_links(config.follow_symlinks)
+        .into_iter()
+        .filter_entry(move |entry| !config.is_excluded(entry.path()))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file() && is_audio_file(entry.path()))
+        .map(|entry| entry.into_path())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub stamp: FileStamp,
+    pub song: Song,
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    // Filled in lazily by the duplicate finder, and dropped with the entry when the file changes
+    #[serde(default)]
+    pub fingerprint: Option<Vec<u32>>,
+}
+
+impl IndexEntry {
+    /// Key the song's statistics are stored under. It does not depend on the file name,
+    /// so statistics survive renames and re-downloads.
+    pub fn identity(&self) -> Option<String> {
+        match (&self.song.tags.video_id, &self.content_hash) {
+            (Some(video_id), _) => Some(format!("youtube:{}", video_id)),
+            (None, Some(hash)) => Some(format!("sha256:{}", hash)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Files that need attention after comparing the music directory against the index
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub changed: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl ScanResult {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Modification times and sizes of the files in the index, including those that failed to
+/// index. This is all a scan compares against, and cheap to take out of the index.
+#[derive(Debug, Clone, Default)]
+pub struct KnownFiles {
+    stamps: HashMap<PathBuf, FileStamp>,
+}
+
+impl KnownFiles {
+    fn is_up_to_date(&self, path: &Path, stamp: Option<FileStamp>) -> bool {
+        self.stamps
+            .get(path)
+            .is_some_and(|known| Some(*known) == stamp)
+    }
+
+    fn is_known(&self, path: &Path) -> bool {
+        self.stamps.contains_key(path)
+    }
+
+    /// Compares the library roots against the index without decoding anything
+    pub fn scan(&self, config: &LibraryConfig) -> ScanResult {
+        let mut result = ScanResult::default();
+        let mut seen = HashSet::new();
+
+        for root in &config.roots {
+            for path in walk_audio_files(config, root) {
+                if !seen.insert(path.clone()) {
+                    continue; // Reachable through more than one root or symlink
+                }
+                if !self.is_up_to_date(&path, FileStamp::of(&path)) {
+                    result.changed.push(path);
+                }
+            }
+        }
+
+        result.removed = self
+            .stamps
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+
+        result
+    }
+
+    /// Sorts paths reported by the watcher into files to (re)index and files that disappeared.
+    /// Directories are expanded, so moving a whole album in or out is picked up as well.
+    pub fn classify(&self, config: &LibraryConfig, paths: Vec<PathBuf>) -> ScanResult {
+        let mut result = ScanResult::default();
+
+        for path in paths {
+            if path.is_dir() {
+                if !config.is_excluded(&path) {
+                    for file in walk_audio_files(config, &path) {
+                        if !self.is_up_to_date(&file, FileStamp::of(&file)) {
+                            result.changed.push(file);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let stamp = FileStamp::of(&path);
+            let is_song = stamp.is_some() && is_audio_file(&path) && !config.is_excluded(&path);
+
+            if is_song {
+                if !self.is_up_to_date(&path, stamp) {
+                    result.changed.push(path);
+                }
+            } else if self.is_known(&path) {
+                result.removed.push(path);
+            } else if !path.exists() {
+                // A directory that went away takes everything below it along
+                result.removed.extend(
+                    self.stamps
+                        .keys()
+                        .filter(|known| known.starts_with(&path))
+                        .cloned(),
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// Bumped whenever the shape or meaning of indexed entries changes, forcing a full rescan
+const INDEX_VERSION: u32 = 5;
+
+/// A stretch of listening to one song, for the listening time statistics
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Listen {
+    /// When the song stopped, in seconds since the epoch
+    pub at: u64,
+    /// How long it was listened to
+    pub seconds: u64,
+}
+
+/// Persistent index of the library, keyed by path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    #[serde(default)]
+    version: u32,
+    entries: HashMap<PathBuf, IndexEntry>,
+    /// Statistics by song identity, see `IndexEntry::identity`
+    #[serde(default)]
+    stats: HashMap<String, SongStats>,
+    /// Every listen, oldest first
+    #[serde(default)]
+    history: Vec<Listen>,
+    // Files that could not be read, so they are not retried until they change on disk
+    #[serde(skip)]
+    failed: HashMap<PathBuf, FileStamp>,
+    /// Whether anything changed since the index was loaded or last handed out for saving
+    #[serde(skip)]
+    dirty: bool,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+impl LibraryIndex {
+    /// Location of the index file, configurable through LIBRARY_INDEX
+    pub fn index_path() -> PathBuf {
+        PathBuf::from(env::var("LIBRARY_INDEX").unwrap_or_else(|_| "library.json".to_string()))
+    }
+
+    pub fn music_dir() -> PathBuf {
+        PathBuf::from(env::var("MUSIC_DIR").unwrap_or_else(|_| "music".to_string()))
+    }
+
+    /// Loads the index from disk, starting empty if it is missing or unreadable
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::index_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<LibraryIndex>(&content).ok())
+        {
+            Some(index) if index.version == INDEX_VERSION => index,
+            // Statistics and history are the user's own data, they are kept even when the entries are rebuilt
+            Some(index) => LibraryIndex {
+                version: INDEX_VERSION,
+                stats: index.stats,
+                history: index.history,
+                ..Default::default()
+            },
+            None => LibraryIndex {
+                version: INDEX_VERSION,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Writes the index to a temporary file first so a crash never leaves it half written
+    pub fn save(&self) -> io::Result<()> {
+        // Saves share the temporary file, one must not rename it while another writes it
+        static SAVING: Mutex<()> = Mutex::new(());
+        let _saving = SAVING.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let path = Self::index_path();
+        let temp_path = path.with_extension("json.tmp");
+
+        let content = serde_json::to_string(self)?;
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &path)
+    }
+
+    /// A copy of the index to save if it changed since the last call. Saving takes a while
+    /// for large libraries, the copy can be written without holding on to the index.
+    pub fn take_unsaved(&mut self) -> Option<LibraryIndex> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        Some(self.clone())
+    }
+
+    /// Hands the index out again by the next `take_unsaved`, e.g. after saving it failed
+    pub fn mark_unsaved(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Stamps of every file the index knows about, to scan the library against
+    /// without holding on to the index
+    pub fn known_files(&self) -> KnownFiles {
+        let mut stamps = self.failed.clone();
+        stamps.extend(
+            self.entries
+                .iter()
+                .map(|(path, entry)| (path.clone(), entry.stamp)),
+        );
+        KnownFiles { stamps }
+    }
+
+    /// Reads a single file from disk. This is the expensive part and should run off the UI thread.
+    pub fn index_file(path: &Path) -> Option<IndexEntry> {
+        let stamp = FileStamp::of(path)?;
+        let song = Self::read_song(path)?;
+
+        Some(IndexEntry {
+            stamp,
+            song,
+            content_hash: content_hash(path).ok(),
+            fingerprint: None,
+        })
+    }
+
+    /// Reads the duration and tags of a file without hashing it, also for files
+    /// outside the library that are played without being indexed
+    pub fn read_song(path: &Path) -> Option<Song> {
+        let duration = get_duration(path)?;
+        let tags = read_tags(path);
+        let title = tags.title.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+        let codec = codec_name(path).unwrap_or_else(|| {
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_uppercase())
+                .unwrap_or_default()
+        });
+
+        Some(Song {
+            title,
+            duration,
+            path: path.to_path_buf(),
+            codec,
+            tags,
+            stats: SongStats::default(),
+        })
+    }
+
+    pub fn insert(&mut self, path: PathBuf, mut entry: IndexEntry) {
+        self.dirty = true;
+        self.failed.remove(&path);
+
+        let previous = self.entries.get(&path);
+        let previous_identity = previous.and_then(IndexEntry::identity);
+        let identity = entry.identity();
+
+        // Editing tags changes the content hash, the statistics stay with the file
+        if let (Some(old), Some(new)) = (&previous_identity, &identity) {
+            if old != new && !self.stats.contains_key(new) {
+                if let Some(stats) = self.stats.remove(old) {
+                    self.stats.insert(new.clone(), stats);
+                }
+            }
+        }
+
+        // A video ID recovered from the tags of an older download takes over the statistics
+        // that were kept by content until then
+        if let (Some(new), Some(hash)) = (&identity, &entry.content_hash) {
+            let by_content = format!("sha256:{}", hash);
+            if *new != by_content && !self.stats.contains_key(new) {
+                if let Some(stats) = self.stats.remove(&by_content) {
+                    self.stats.insert(new.clone(), stats);
+                }
+            }
+        }
+
+        entry.song.stats = match identity {
+            Some(identity) => self
+                .stats
+                .entry(identity)
+                .or_insert_with(|| SongStats {
+                    added_at: now(),
+                    ..Default::default()
+                })
+                .clone(),
+            None => SongStats {
+                // A file that changed on disk has not been newly added
+                added_at: previous.map_or_else(now, |previous| previous.song.stats.added_at),
+                ..Default::default()
+            },
+        };
+
+        self.entries.insert(path, entry);
+    }
+
+    /// Updates the statistics of the song at `path`, returning them afterwards
+    pub fn update_stats(
+        &mut self,
+        path: &Path,
+        update: impl FnOnce(&mut SongStats),
+    ) -> Option<SongStats> {
+        let entry = self.entries.get_mut(path)?;
+        self.dirty = true;
+        let Some(identity) = entry.identity() else {
+            update(&mut entry.song.stats);
+            return Some(entry.song.stats.clone());
+        };
+
+        let stats = self.stats.entry(identity.clone()).or_default();
+        update(stats);
+        let stats = stats.clone();
+
+        // Identical copies share their statistics
+        for entry in self.entries.values_mut() {
+            if entry.identity().as_ref() == Some(&identity) {
+                entry.song.stats = stats.clone();
+            }
+        }
+
+        Some(stats)
+    }
+
+    pub fn record_listen(&mut self, seconds: f64) {
+        if seconds >= 1.0 {
+            self.dirty = true;
+            self.history.push(Listen {
+                at: now(),
+                seconds: seconds as u64,
+            });
+        }
+    }
+
+    pub fn history(&self) -> &[Listen] {
+        &self.history
+    }
+
+    /// Adds the statistics of the duplicates to the song that is kept
+    pub fn merge_stats(&mut self, keep: &Path, duplicates: &[PathBuf]) {
+        let Some(keep_identity) = self.entries.get(keep).and_then(IndexEntry::identity) else {
+            return;
+        };
+
+        // Copies sharing an identity already share their statistics
+        let mut seen = vec![keep_identity];
+        let others: Vec<SongStats> = duplicates
+            .iter()
+            .filter_map(|path| self.entries.get(path)?.identity())
+            .filter(|identity| {
+                let new = !seen.contains(identity);
+                seen.push(identity.clone());
+                new
+            })
+            .filter_map(|identity| self.stats.get(&identity).cloned())
+            .collect();
+
+        self.update_stats(keep, |stats| {
+            for other in &others {
+                stats.merge(other);
+            }
+        });
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.failed.remove(path);
+        if self.entries.remove(path).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Remembers that a file could not be indexed in its current state
+    pub fn mark_failed(&mut self, path: PathBuf) {
+        if self.entries.remove(&path).is_some() {
+            self.dirty = true;
+        }
+        if let Some(stamp) = FileStamp::of(&path) {
+            self.failed.insert(path, stamp);
+        }
+    }
+
+    /// Files that could not be indexed, e.g. broken downloads
+    pub fn failed_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.failed.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
+    }
+
+    /// Paths of songs whose content hash or fingerprint has not been computed yet
+    pub fn missing_fingerprints(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.content_hash.is_none() || entry.fingerprint.is_none())
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    pub fn set_fingerprint(&mut self, path: &Path, content_hash: String, fingerprint: Vec<u32>) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            self.dirty = true;
+            entry.content_hash = Some(content_hash);
+            entry.fingerprint = Some(fingerprint);
+        }
+    }
+
+    pub fn song(&self, path: &Path) -> Option<&Song> {
+        self.entries.get(path).map(|entry| &entry.song)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// The song downloaded from a YouTube video, found by the video ID in its tags
+    pub fn song_for_video(&self, video_id: &str) -> Option<&Song> {
+        self.entries()
+            .map(|entry| &entry.song)
+            .find(|song| song.tags.video_id.as_deref() == Some(video_id))
+    }
+
+    /// All indexed songs, ordered by path
+    pub fn songs(&self) -> Vec<Song> {
+        let mut songs: Vec<Song> = self
+            .entries
+            .values()
+            .map(|entry| entry.song.clone())
+            .collect();
+        songs.sort_by(|a, b| a.path.cmp(&b.path));
+        songs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::song::TrackTags;
+    use globset::{Glob, GlobSet, GlobSetBuilder};
+
+    /// A fresh, empty directory below the system's temporary directory
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            env::temp_dir().join(format!("rusty_songs_index_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(root: &Path) -> LibraryConfig {
+        LibraryConfig {
+            roots: vec![root.to_path_buf()],
+            follow_symlinks: false,
+            excludes: GlobSet::empty(),
+        }
+    }
+
+    fn sorted(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths.sort();
+        paths
+    }
+
+    fn entry(path: &Path, content_hash: &str, video_id: Option<&str>) -> IndexEntry {
+        IndexEntry {
+            stamp: FileStamp { mtime: 1, size: 1 },
+            song: Song {
+                title: "Song".to_string(),
+                duration: 1.0,
+                path: path.to_path_buf(),
+                codec: "MP3".to_string(),
+                tags: TrackTags {
+                    video_id: video_id.map(str::to_string),
+                    ..TrackTags::default()
+                },
+                stats: SongStats::default(),
+            },
+            content_hash: Some(content_hash.to_string()),
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn downloads_in_progress_are_not_audio_files() {
+        for name in [
+            "song.mp3.part",
+            "song.f251.webm.part-Frag3",
+            "song.temp.mp3",
+            ".hidden.mp3",
+            "notes.txt",
+        ] {
+            assert!(!is_audio_file(Path::new(name)), "{}", name);
+        }
+        assert!(is_audio_file(Path::new("music/part.mp3")));
+        assert!(is_audio_file(Path::new("song.tmp.flac")));
+    }
+
+    #[test]
+    fn scan_reports_new_changed_and_removed_files() {
+        let dir = temp_dir("scan");
+        let song = dir.join("a.mp3");
+        let other = dir.join("album").join("b.flac");
+        fs::create_dir_all(other.parent().unwrap()).unwrap();
+        fs::write(&song, b"one").unwrap();
+        fs::write(&other, b"two").unwrap();
+        fs::write(dir.join("notes.txt"), b"not audio").unwrap();
+        fs::write(dir.join("c.mp3.part"), b"still downloading").unwrap();
+
+        let mut index = LibraryIndex::default();
+        let scan = index.known_files().scan(&config(&dir));
+        assert_eq!(sorted(scan.changed), vec![song.clone(), other.clone()]);
+        assert!(scan.removed.is_empty());
+
+        // Known in their current state, whether they could be read or not
+        index.mark_failed(song.clone());
+        index.mark_failed(other.clone());
+        assert!(index.known_files().scan(&config(&dir)).is_empty());
+
+        // A different size is a change even within the same second
+        fs::write(&song, b"one, tagged").unwrap();
+        fs::remove_file(&other).unwrap();
+        let scan = index.known_files().scan(&config(&dir));
+        assert_eq!(scan.changed, vec![song]);
+        assert_eq!(scan.removed, vec![other]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_skips_excluded_files() {
+        let dir = temp_dir("exclude");
+        fs::create_dir_all(dir.join("Podcasts")).unwrap();
+        fs::write(dir.join("Podcasts").join("episode.mp3"), b"talk").unwrap();
+        fs::write(dir.join("song.mp3"), b"music").unwrap();
+
+        let mut config = config(&dir);
+        let mut excludes = GlobSetBuilder::new();
+        excludes.add(Glob::new("Podcasts").unwrap());
+        config.excludes = excludes.build().unwrap();
+
+        let scan = LibraryIndex::default().known_files().scan(&config);
+        assert_eq!(scan.changed, vec![dir.join("song.mp3")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn classify_expands_directories_and_removes_what_went_away() {
+        let dir = temp_dir("classify");
+        let album = dir.join("album");
+        fs::create_dir_all(&album).unwrap();
+        let first = album.join("1.mp3");
+        let second = album.join("2.mp3");
+        fs::write(&first, b"one").unwrap();
+        fs::write(&second, b"two").unwrap();
+
+        let mut index = LibraryIndex::default();
+        let scan = index.known_files().classify(
+            &config(&dir),
+            vec![album.clone(), dir.join("unrelated.txt")],
+        );
+        assert_eq!(sorted(scan.changed), vec![first.clone(), second.clone()]);
+        assert!(scan.removed.is_empty());
+
+        index.mark_failed(first.clone());
+        index.mark_failed(second.clone());
+        fs::remove_dir_all(&album).unwrap();
+        let scan = index.known_files().classify(&config(&dir), vec![album]);
+        assert!(scan.changed.is_empty());
+        assert_eq!(sorted(scan.removed), vec![first, second]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn statistics_follow_the_song_to_its_video_id() {
+        let path = PathBuf::from("music/song.mp3");
+        let mut index = LibraryIndex::default();
+        index.insert(path.clone(), entry(&path, "abc", None));
+        index.update_stats(&path, |stats| stats.play_count = 3);
+
+        // The video ID shows up in the tags, e.g. after the tag editor wrote it
+        index.insert(path.clone(), entry(&path, "def", Some("dQw4w9WgXcQ")));
+        assert_eq!(index.song(&path).unwrap().stats.play_count, 3);
+        assert!(index.stats.contains_key("youtube:dQw4w9WgXcQ"));
+        assert!(!index.stats.contains_key("sha256:abc"));
+
+        // An identical copy shares the statistics
+        let copy = PathBuf::from("music/copy.mp3");
+        index.insert(copy.clone(), entry(&copy, "def", Some("dQw4w9WgXcQ")));
+        index.update_stats(&copy, |stats| stats.play_count += 1);
+        assert_eq!(index.song(&path).unwrap().stats.play_count, 4);
+    }
+
+    #[test]
+    fn finds_downloads_by_video_id() {
+        let path = PathBuf::from("music/renamed.mp3");
+        let mut index = LibraryIndex::default();
+        index.insert(path.clone(), entry(&path, "abc", Some("dQw4w9WgXcQ")));
+
+        assert_eq!(index.song_for_video("dQw4w9WgXcQ").unwrap().path, path);
+        assert!(index.song_for_video("abcdefghijk").is_none());
+    }
+
+    #[test]
+    fn changes_are_handed_out_for_saving_once() {
+        let path = PathBuf::from("music/song.mp3");
+        let mut index = LibraryIndex::default();
+        assert!(index.take_unsaved().is_none());
+
+        index.insert(path.clone(), entry(&path, "abc", None));
+        assert!(index.take_unsaved().is_some());
+        assert!(index.take_unsaved().is_none());
+
+        // Statistics of songs that are not indexed change nothing
+        assert!(index
+            .update_stats(Path::new("elsewhere.mp3"), |_| {})
+            .is_none());
+        assert!(index.take_unsaved().is_none());
+
+        index.record_listen(30.0);
+        index.mark_unsaved();
+        assert!(index.take_unsaved().is_some());
+    }
+}
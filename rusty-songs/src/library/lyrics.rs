@@ -0,0 +1,277 @@
+use crate::library::audio_format::probe;
+use id3::frame::TimestampFormat;
+use id3::Tag;
+use std::fs;
+use std::path::{Path, PathBuf};
+use symphonia::core::meta::StandardTagKey;
+
+/// Samples per MPEG audio frame, for SYLT frames timed in MPEG frames
+const SAMPLES_PER_MPEG_FRAME: f64 = 1152.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    /// Seconds into the song, None for unsynced lyrics
+    pub time: Option<f64>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+    /// Offset from the LRC `[offset:]` tag in milliseconds, positive shows lines earlier
+    pub offset_ms: i64,
+}
+
+impl Lyrics {
+    pub fn is_synced(&self) -> bool {
+        self.lines.iter().any(|line| line.time.is_some())
+    }
+
+    /// Index of the line being sung at `position`, with `extra_offset_ms` on top of the file's own offset
+    pub fn current_line(&self, position: f64, extra_offset_ms: i64) -> Option<usize> {
+        let position = position + (self.offset_ms + extra_offset_ms) as f64 / 1000.0;
+        self.lines
+            .iter()
+            .rposition(|line| line.time.is_some_and(|time| time <= position))
+    }
+}
+
+/// Where the `.lrc` file of a song lives: next to it, with the same name
+pub fn lyrics_path(audio_path: &Path) -> PathBuf {
+    audio_path.with_extension("lrc")
+}
+
+/// Parses `mm:ss`, `mm:ss.xx` or `mm:ss:xx` into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let (minutes, rest) = value.split_once(':')?;
+    let rest = rest.replacen(':', ".", 1);
+    Some(minutes.trim().parse::<f64>().ok()? * 60.0 + rest.trim().parse::<f64>().ok()?)
+}
+
+/// Removes the word timings of enhanced LRC, e.g. `<00:12.34>`
+fn strip_word_timings(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) if parse_timestamp(&rest[start + 1..start + end]).is_some() => {
+                result.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            }
+            _ => {
+                result.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result.trim().to_string()
+}
+
+/// Parses LRC text. Text without any timestamps is kept as unsynced lyrics.
+pub fn parse_lrc(content: &str) -> Lyrics {
+    let mut lyrics = Lyrics::default();
+    let mut plain = Vec::new();
+
+    for raw_line in content.lines() {
+        let mut rest = raw_line.trim();
+        let mut times = Vec::new();
+        let mut is_tag = false;
+
+        while let Some(inner_end) = rest.strip_prefix('[').and_then(|inner| inner.find(']')) {
+            let inner = &rest[1..=inner_end];
+            match parse_timestamp(inner) {
+                Some(time) => times.push(time),
+                // Not a tag, e.g. "[Chorus]" in plain lyrics
+                None if !inner.contains(':') => break,
+                None => {
+                    // ID tags like [ar:Artist]; only the offset matters for display
+                    if let Some(offset) = inner.strip_prefix("offset:") {
+                        lyrics.offset_ms = offset.trim().parse().unwrap_or(0);
+                    }
+                    is_tag = true;
+                }
+            }
+            rest = rest[inner_end + 2..].trim_start();
+        }
+
+        let text = strip_word_timings(rest);
+        if times.is_empty() {
+            if !is_tag {
+                plain.push(text);
+            }
+            continue;
+        }
+        for time in times {
+            lyrics.lines.push(LyricLine {
+                time: Some(time),
+                text: text.clone(),
+            });
+        }
+    }
+
+    if lyrics.lines.is_empty() {
+        // Trim the blank lines around unsynced lyrics but keep the ones between verses
+        let start = plain.iter().position(|line| !line.is_empty()).unwrap_or(0);
+        let end = plain
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |end| end + 1);
+        lyrics.lines = plain
+            .get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|text| LyricLine {
+                time: None,
+                text: text.clone(),
+            })
+            .collect();
+    } else {
+        lyrics.lines.sort_by(|a, b| {
+            a.time
+                .unwrap_or_default()
+                .total_cmp(&b.time.unwrap_or_default())
+        });
+    }
+
+    lyrics
+}
+
+fn id3_lyrics(path: &Path) -> Option<Lyrics> {
+    let tag = Tag::read_from_path(path).ok()?;
+
+    if let Some(synced) = tag
+        .synchronised_lyrics()
+        .find(|lyrics| !lyrics.content.is_empty())
+    {
+        let seconds_per_unit = match synced.timestamp_format {
+            TimestampFormat::Ms => 0.001,
+            TimestampFormat::Mpeg => {
+                let sample_rate = probe(path)
+                    .and_then(|probed| probed.format.default_track()?.codec_params.sample_rate)
+                    .unwrap_or(44100);
+                SAMPLES_PER_MPEG_FRAME / f64::from(sample_rate)
+            }
+        };
+
+        return Some(Lyrics {
+            lines: synced
+                .content
+                .iter()
+                .map(|(time, text)| LyricLine {
+                    time: Some(f64::from(*time) * seconds_per_unit),
+                    text: text.trim().to_string(),
+                })
+                .collect(),
+            offset_ms: 0,
+        });
+    }
+
+    let unsynced = tag
+        .lyrics()
+        .map(|lyrics| parse_lrc(&lyrics.text))
+        .find(|lyrics| !lyrics.lines.is_empty());
+    unsynced
+}
+
+fn embedded_lyrics(path: &Path) -> Option<Lyrics> {
+    let mut probed = probe(path)?;
+
+    let mut revisions = Vec::new();
+    if let Some(revision) = probed.metadata.get().and_then(|m| m.current().cloned()) {
+        revisions.push(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current().cloned() {
+        revisions.push(revision);
+    }
+
+    revisions
+        .iter()
+        .flat_map(|revision| revision.tags())
+        .filter(|tag| {
+            tag.std_key == Some(StandardTagKey::Lyrics)
+                || tag.key.eq_ignore_ascii_case("UNSYNCEDLYRICS")
+        })
+        .map(|tag| parse_lrc(&tag.value.to_string()))
+        .find(|lyrics| !lyrics.lines.is_empty())
+}
+
+/// Lyrics of a song from its `.lrc` sidecar, or else from the lyrics embedded in its tags
+pub fn load_lyrics(audio_path: &Path) -> Option<Lyrics> {
+    if let Ok(content) = fs::read_to_string(lyrics_path(audio_path)) {
+        let lyrics = parse_lrc(&content);
+        if !lyrics.lines.is_empty() {
+            return Some(lyrics);
+        }
+    }
+
+    let is_mp3 = audio_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"));
+    if is_mp3 {
+        id3_lyrics(audio_path)
+    } else {
+        embedded_lyrics(audio_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(time: Option<f64>, text: &str) -> LyricLine {
+        LyricLine {
+            time,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_synced_lyrics_in_order() {
+        let lyrics = parse_lrc(
+            "[ar:Artist]\n[offset:+250]\n\n[00:12.50][01:02.00]Chorus\n[00:05:10] <00:05.10>First <00:06.00>line\n[00:20.00]",
+        );
+
+        assert_eq!(lyrics.offset_ms, 250);
+        assert!(lyrics.is_synced());
+        assert_eq!(
+            lyrics.lines,
+            vec![
+                line(Some(5.1), "First line"),
+                line(Some(12.5), "Chorus"),
+                line(Some(20.0), ""),
+                line(Some(62.0), "Chorus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_plain_lyrics_with_their_verses() {
+        let lyrics = parse_lrc("\n[Verse 1]\nFirst line\n\nSecond <verse>\n\n");
+
+        assert!(!lyrics.is_synced());
+        assert_eq!(
+            lyrics.lines,
+            vec![
+                line(None, "[Verse 1]"),
+                line(None, "First line"),
+                line(None, ""),
+                line(None, "Second <verse>"),
+            ]
+        );
+        assert_eq!(lyrics.current_line(10.0, 0), None);
+    }
+
+    #[test]
+    fn finds_the_current_line_with_offsets() {
+        let lyrics = Lyrics {
+            lines: vec![line(Some(5.0), "one"), line(Some(10.0), "two")],
+            offset_ms: 500,
+        };
+
+        assert_eq!(lyrics.current_line(4.0, 0), None);
+        assert_eq!(lyrics.current_line(4.5, 0), Some(0));
+        assert_eq!(lyrics.current_line(9.0, 500), Some(1));
+        assert_eq!(lyrics.current_line(9.0, -1000), Some(0));
+    }
+}
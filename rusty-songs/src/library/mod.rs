@@ -0,0 +1,14 @@
+pub mod audio_format;
+pub mod config;
+pub mod cover_art;
+pub mod duplicates;
+pub mod duration;
+pub mod import;
+pub mod index;
+pub mod lyrics;
+pub mod naming;
+pub mod smart_playlist;
+pub mod sort;
+pub mod stats;
+pub mod verify;
+pub mod watcher;
@@ -0,0 +1,389 @@
+use crate::models::song::TrackTags;
+use crate::utils::file_names::{sanitize_file_name, truncate_file_name, MAX_FILE_NAME_BYTES};
+use std::env;
+use std::path::PathBuf;
+
+/// Keeps the flat layout downloads always had
+pub const DEFAULT_TEMPLATE: &str = "{title}.{ext}";
+
+const FIELDS: [&str; 7] = ["title", "artist", "album", "track", "year", "genre", "ext"];
+
+/// The fields a width zero pads, padding text would turn `Song` into `000000Song`
+const NUMBER_FIELDS: [&str; 2] = ["track", "year"];
+
+/// Characters joining placeholders, like the ` - ` in `{track:02} - {title}`
+const SEPARATORS: [char; 8] = [' ', '-', '_', '.', ',', ';', '~', '|'];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    /// A tag, numbers optionally zero padded to a width like `{track:02}`
+    Field {
+        name: String,
+        width: usize,
+    },
+}
+
+/// A part of a template filled in with the tags of one song
+enum Piece {
+    Literal(String),
+    Value(String),
+    /// A number the song has no tag for
+    Missing,
+}
+
+/// Where a song goes below the music directory, e.g. `{artist}/{album}/{track:02} - {title}.{ext}`.
+/// Placeholders are {title}, {artist}, {album}, {track}, {year}, {genre} and {ext}, and only
+/// the numbers {track} and {year} take a width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamingTemplate {
+    parts: Vec<Part>,
+}
+
+impl Default for NamingTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+impl NamingTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Unclosed {{ in naming template {}", template))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+
+            let placeholder = &rest[start + 1..end];
+            let (name, width) = match placeholder.split_once(':') {
+                Some((name, width)) => (
+                    name,
+                    width
+                        .parse()
+                        .map_err(|_| format!("Invalid width in {{{}}}", placeholder))?,
+                ),
+                None => (placeholder, 0),
+            };
+            let name = name.trim().to_lowercase();
+            if !FIELDS.contains(&name.as_str()) {
+                return Err(format!("Unknown placeholder {{{}}}", placeholder));
+            }
+            if width > 0 && !NUMBER_FIELDS.contains(&name.as_str()) {
+                return Err(format!(
+                    "Only {{track}} and {{year}} can be padded, not {{{}}}",
+                    placeholder
+                ));
+            }
+            parts.push(Part::Field { name, width });
+
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        if !parts
+            .iter()
+            .any(|part| matches!(part, Part::Field { name, .. } if name == "title"))
+        {
+            return Err("The naming template needs a {title}".to_string());
+        }
+
+        Ok(NamingTemplate { parts })
+    }
+
+    /// Reads NAMING_TEMPLATE, falling back to the flat `{title}.{ext}` layout
+    pub fn from_env() -> Self {
+        match env::var("NAMING_TEMPLATE") {
+            Ok(template) => Self::parse(&template).unwrap_or_else(|e| {
+                eprintln!("{}, using {}", e, DEFAULT_TEMPLATE);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn value(name: &str, tags: &TrackTags, extension: &str) -> String {
+        let text = |value: &Option<String>, missing: &str| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .unwrap_or(missing)
+                .to_string()
+        };
+
+        match name {
+            "title" => text(&tags.title, "Untitled"),
+            "artist" => text(&tags.artist, "Unknown Artist"),
+            "album" => text(&tags.album, "Unknown Album"),
+            "genre" => text(&tags.genre, "Unknown Genre"),
+            "track" => tags
+                .track
+                .map(|track| track.to_string())
+                .unwrap_or_default(),
+            "year" => tags.year.map(|year| year.to_string()).unwrap_or_default(),
+            _ => extension.to_lowercase(),
+        }
+    }
+
+    /// The path relative to the music directory. Tag values are made safe for file names,
+    /// so they never add folders or leave the music directory. Missing numbers leave no
+    /// dangling separators behind, and overly long names are shortened.
+    pub fn render(&self, tags: &TrackTags, extension: &str) -> PathBuf {
+        let mut pieces: Vec<Piece> = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => Piece::Literal(text.clone()),
+                Part::Field { name, width } => {
+                    let value = Self::value(name, tags, extension);
+                    if value.is_empty() {
+                        Piece::Missing
+                    } else {
+                        let value = sanitize_file_name(&value);
+                        Piece::Value(format!("{:0>width$}", value, width = *width))
+                    }
+                }
+            })
+            .collect();
+
+        for i in 0..pieces.len() {
+            if matches!(pieces[i], Piece::Missing) {
+                remove_dangling_separators(&mut pieces, i);
+            }
+        }
+
+        let rendered: String = pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) | Piece::Value(text) => text.as_str(),
+                Piece::Missing => "",
+            })
+            .collect();
+
+        rendered
+            .split(['/', '\\'])
+            .map(|component| match component.trim() {
+                "" | "." | ".." => "_".to_string(),
+                _ => truncate_file_name(component, MAX_FILE_NAME_BYTES),
+            })
+            .collect()
+    }
+}
+
+fn is_folder_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn literal_mut(pieces: &mut [Piece], i: usize) -> Option<&mut String> {
+    match pieces.get_mut(i) {
+        Some(Piece::Literal(text)) => Some(text),
+        _ => None,
+    }
+}
+
+/// Removes the template text around the missing value at `i` that only made sense with the
+/// value: brackets around it, and the separator joining it to a neighbouring value. Tag values
+/// themselves are left alone.
+fn remove_dangling_separators(pieces: &mut [Piece], i: usize) {
+    let opening = i
+        .checked_sub(1)
+        .and_then(|before| literal_mut(pieces, before))
+        .and_then(|text| text.chars().last());
+    let closing = literal_mut(pieces, i + 1).and_then(|text| text.chars().next());
+    if matches!(
+        (opening, closing),
+        (Some('('), Some(')')) | (Some('['), Some(']'))
+    ) {
+        if let Some(text) = literal_mut(pieces, i - 1) {
+            text.pop();
+        }
+        if let Some(text) = literal_mut(pieces, i + 1) {
+            text.remove(0);
+        }
+    }
+
+    // Whether a value comes before the missing one within the same folder or file name
+    let mut value_before = false;
+    for piece in pieces[..i].iter().rev() {
+        match piece {
+            Piece::Literal(text) if text.contains(is_folder_separator) => break,
+            Piece::Value(_) => {
+                value_before = true;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    if value_before {
+        // "{artist} - {year}" drops the separator before the missing year
+        if let Some(text) = i
+            .checked_sub(1)
+            .and_then(|before| literal_mut(pieces, before))
+        {
+            text.truncate(text.trim_end_matches(SEPARATORS).len());
+        }
+    } else if let Some(text) = literal_mut(pieces, i + 1) {
+        // "{track:02} - {title}" drops the one after a missing track at the start of a name
+        let end = text.find(is_folder_separator).unwrap_or(text.len());
+        let trimmed = text[..end].trim_start_matches(SEPARATORS).len();
+        text.replace_range(..end - trimmed, "");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(title: &str, track: Option<u32>, year: Option<i32>) -> TrackTags {
+        TrackTags {
+            title: Some(title.to_string()),
+            artist: Some("Artist".to_string()),
+            album: Some("Album".to_string()),
+            track,
+            year,
+            ..TrackTags::default()
+        }
+    }
+
+    fn render(template: &str, tags: &TrackTags) -> String {
+        NamingTemplate::parse(template)
+            .unwrap()
+            .render(tags, "MP3")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn parses_fields_and_widths() {
+        let template = NamingTemplate::parse("{Artist}/{track:02} - {title}.{ext}").unwrap();
+        assert_eq!(
+            template.parts,
+            vec![
+                Part::Field {
+                    name: "artist".to_string(),
+                    width: 0
+                },
+                Part::Literal("/".to_string()),
+                Part::Field {
+                    name: "track".to_string(),
+                    width: 2
+                },
+                Part::Literal(" - ".to_string()),
+                Part::Field {
+                    name: "title".to_string(),
+                    width: 0
+                },
+                Part::Literal(".".to_string()),
+                Part::Field {
+                    name: "ext".to_string(),
+                    width: 0
+                },
+            ]
+        );
+        assert_eq!(NamingTemplate::default().parts.len(), 3);
+    }
+
+    #[test]
+    fn rejects_broken_templates() {
+        assert!(NamingTemplate::parse("{title").is_err());
+        assert!(NamingTemplate::parse("{title}{label}").is_err());
+        assert!(NamingTemplate::parse("{title}{track:two}").is_err());
+        assert!(NamingTemplate::parse("{artist}.{ext}").is_err());
+        assert_eq!(
+            NamingTemplate::parse("{title:10}.{ext}"),
+            Err("Only {track} and {year} can be padded, not {title:10}".to_string())
+        );
+    }
+
+    #[test]
+    fn renders_tags_into_folders_and_names() {
+        let template = "{artist}/{album}/{track:02} - {title}.{ext}";
+        assert_eq!(
+            render(template, &tags("Song", Some(3), None)),
+            "Artist/Album/03 - Song.mp3"
+        );
+        assert_eq!(
+            render(template, &TrackTags::default()),
+            "Unknown Artist/Unknown Album/Untitled.mp3"
+        );
+        // Values never add folders or climb out of the music directory
+        assert_eq!(
+            render(template, &tags("AC/DC", None, None)),
+            "Artist/Album/AC_DC.mp3"
+        );
+        assert_eq!(render("{title}/{ext}", &tags("..", None, None)), "_/mp3");
+    }
+
+    #[test]
+    fn pads_numbers_to_their_width() {
+        assert_eq!(
+            render(
+                "{year:6} {track:3} {title}.{ext}",
+                &tags("Song", Some(7), Some(99))
+            ),
+            "000099 007 Song.mp3"
+        );
+        // A width narrower than the number leaves it whole
+        assert_eq!(
+            render("{track:1} {title}.{ext}", &tags("Song", Some(12), None)),
+            "12 Song.mp3"
+        );
+    }
+
+    #[test]
+    fn drops_separators_left_by_missing_numbers() {
+        assert_eq!(
+            render("{track:02} - {title}.{ext}", &tags("Song", None, None)),
+            "Song.mp3"
+        );
+        assert_eq!(
+            render("{title} ({year}).{ext}", &tags("Song", None, None)),
+            "Song.mp3"
+        );
+        assert_eq!(
+            render("{title} [{year}].{ext}", &tags("Song", None, Some(1999))),
+            "Song [1999].mp3"
+        );
+        assert_eq!(
+            render(
+                "{artist} - {year} - {title}.{ext}",
+                &tags("Song", None, None)
+            ),
+            "Artist - Song.mp3"
+        );
+        assert_eq!(
+            render(
+                "{artist}/{year}/{track} {title}.{ext}",
+                &tags("Song", None, None)
+            ),
+            "Artist/_/Song.mp3"
+        );
+    }
+
+    #[test]
+    fn leaves_titles_alone() {
+        let template = "{track:02} - {title}.{ext}";
+        assert_eq!(render(template, &tags("-ism", None, None)), "-ism.mp3");
+        assert_eq!(
+            render(template, &tags("Song (-)", None, None)),
+            "Song (-).mp3"
+        );
+        assert_eq!(
+            render(template, &tags("[] and ()", Some(1), None)),
+            "01 - [] and ().mp3"
+        );
+        assert_eq!(
+            render("{title} - {year}.{ext}", &tags("Song -", None, None)),
+            "Song -.mp3"
+        );
+    }
+}
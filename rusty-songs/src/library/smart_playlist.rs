@@ -0,0 +1,592 @@
+use crate::models::song::Song;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A named query, stored in the settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartPlaylist {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for QueryError {}
+
+fn error<T>(message: impl Into<String>) -> Result<T, QueryError> {
+    Err(QueryError(message.into()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Codec,
+    /// Title, artist or album
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberField {
+    Duration,
+    Year,
+    Track,
+    Plays,
+    Skips,
+    Rating,
+    /// 1 for favourites, 0 otherwise
+    Favourite,
+    /// Age in seconds
+    Added,
+    /// Seconds since the song was last played
+    Played,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Contains,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Text(TextField, Op, String),
+    Number(NumberField, Op, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Condition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Option<String>, Op, String),
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, ':' | '=' | '!' | '<' | '>')
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+fn read_value(chars: &mut Peekable<Chars>) -> Result<String, QueryError> {
+    let mut value = String::new();
+
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        loop {
+            match chars.next() {
+                Some('"') => return Ok(value),
+                Some(c) => value.push(c),
+                None => return error("missing closing quote"),
+            }
+        }
+    }
+
+    while let Some(&c) = chars.peek() {
+        if is_separator(c) {
+            break;
+        }
+        value.push(c);
+        chars.next();
+    }
+    Ok(value)
+}
+
+fn read_operator(chars: &mut Peekable<Chars>) -> Result<Op, QueryError> {
+    let mut operator = String::new();
+    while let Some(&c) = chars.peek() {
+        if !is_operator(c) {
+            break;
+        }
+        operator.push(c);
+        chars.next();
+    }
+
+    // `added:<30d` reads the same as `added<30d`
+    let operator = match operator.strip_prefix(':') {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => operator.as_str(),
+    };
+
+    match operator {
+        ":" => Ok(Op::Contains),
+        "=" => Ok(Op::Eq),
+        "!=" => Ok(Op::Ne),
+        "<" => Ok(Op::Lt),
+        "<=" => Ok(Op::Le),
+        ">" => Ok(Op::Gt),
+        ">=" => Ok(Op::Ge),
+        other => error(format!("unknown operator '{}'", other)),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push(Token::Open);
+        } else if c == ')' {
+            chars.next();
+            tokens.push(Token::Close);
+        } else if c == '"' {
+            tokens.push(Token::Term(None, Op::Contains, read_value(&mut chars)?));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if is_separator(c) || is_operator(c) || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+
+            if chars.peek().is_some_and(|&c| is_operator(c)) {
+                let op = read_operator(&mut chars)?;
+                tokens.push(Token::Term(Some(word), op, read_value(&mut chars)?));
+                continue;
+            }
+
+            tokens.push(match word.as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                _ if word.is_empty() => return error(format!("unexpected '{}'", c)),
+                _ => Token::Term(None, Op::Contains, word),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses durations like `300`, `5m`, `1h` or `3:20` into seconds
+fn parse_duration(value: &str) -> Option<f64> {
+    if let Some((minutes, seconds)) = value.split_once(':') {
+        return Some(minutes.parse::<f64>().ok()? * 60.0 + seconds.parse::<f64>().ok()?);
+    }
+
+    let (number, unit) = split_unit(value);
+    let scale = match unit {
+        "" | "s" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        _ => return None,
+    };
+    Some(number.parse::<f64>().ok()? * scale)
+}
+
+/// Parses ages like `12h`, `30d`, `2w`, `6mo` or `1y` into seconds, plain numbers are days
+fn parse_age(value: &str) -> Option<f64> {
+    const DAY: f64 = 86400.0;
+
+    let (number, unit) = split_unit(value);
+    let scale = match unit {
+        "h" => 3600.0,
+        "" | "d" => DAY,
+        "w" => 7.0 * DAY,
+        "mo" => 30.0 * DAY,
+        "y" => 365.0 * DAY,
+        _ => return None,
+    };
+    Some(number.parse::<f64>().ok()? * scale)
+}
+
+fn parse_flag(value: &str) -> Option<f64> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" | "1" => Some(1.0),
+        "no" | "false" | "0" => Some(0.0),
+        _ => None,
+    }
+}
+
+fn split_unit(value: &str) -> (&str, &str) {
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    value.split_at(split)
+}
+
+fn condition(field: Option<String>, op: Op, value: String) -> Result<Condition, QueryError> {
+    let Some(field) = field else {
+        return Ok(Condition::Text(TextField::Any, op, value.to_lowercase()));
+    };
+
+    let text_field = match field.to_lowercase().as_str() {
+        "title" => Some(TextField::Title),
+        "artist" => Some(TextField::Artist),
+        "album" => Some(TextField::Album),
+        "genre" => Some(TextField::Genre),
+        "codec" => Some(TextField::Codec),
+        _ => None,
+    };
+    if let Some(text_field) = text_field {
+        if !matches!(op, Op::Contains | Op::Eq | Op::Ne) {
+            return error(format!("{} can only be compared with :, = or !=", field));
+        }
+        return Ok(Condition::Text(text_field, op, value.to_lowercase()));
+    }
+
+    let (number_field, number) = match field.to_lowercase().as_str() {
+        "duration" => (NumberField::Duration, parse_duration(&value)),
+        "year" => (NumberField::Year, value.parse().ok()),
+        "track" => (NumberField::Track, value.parse().ok()),
+        "plays" => (NumberField::Plays, value.parse().ok()),
+        "skips" => (NumberField::Skips, value.parse().ok()),
+        "rating" => (NumberField::Rating, value.parse().ok()),
+        "favourite" | "fav" => (NumberField::Favourite, parse_flag(&value)),
+        "added" => (NumberField::Added, parse_age(&value)),
+        "played" => (NumberField::Played, parse_age(&value)),
+        _ => return error(format!("unknown field '{}'", field)),
+    };
+    // `m` is minutes in durations, so ages spell months out instead of guessing
+    if matches!(number_field, NumberField::Added | NumberField::Played)
+        && split_unit(&value).1 == "m"
+    {
+        return error(format!(
+            "invalid age '{}' for {}, use mo for months",
+            value, field
+        ));
+    }
+    let Some(number) = number else {
+        return error(format!("invalid value '{}' for {}", value, field));
+    };
+
+    let op = match (number_field, op) {
+        (NumberField::Added | NumberField::Played, Op::Contains) => Op::Le,
+        (_, Op::Contains) => Op::Eq,
+        (_, op) => op,
+    };
+    Ok(Condition::Number(number_field, op, number))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Term(..)) => {}
+                _ => return Ok(left),
+            }
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => error("missing closing parenthesis"),
+                }
+            }
+            Some(Token::Term(field, op, value)) => Ok(Expr::Term(condition(field, op, value)?)),
+            Some(Token::Close) => error("unexpected ')'"),
+            Some(_) => error("expected a term before AND/OR"),
+            None => error("query ends too early"),
+        }
+    }
+}
+
+/// A parsed smart playlist query, e.g. `artist:"Daft Punk" AND duration>300 AND plays<3`.
+///
+/// Terms are `field op value` with `op` one of `:`, `=`, `!=`, `<`, `<=`, `>`, `>=`. On text
+/// fields `:` means "contains", on numbers equality and on `added`/`played` "within", so
+/// `added:<30d` and `added:30d` both select songs added in the last 30 days. Terms combine with `AND`, `OR`,
+/// `NOT` and parentheses, adjacent terms are ANDed and a bare word matches title, artist or album.
+///
+/// Durations take `s`, `m`/`min` or `h` (plain numbers are seconds) or `m:ss`. Ages take `h`, `d`,
+/// `w`, `mo` or `y` (plain numbers are days), a bare `m` is rejected as it could mean minutes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        if parser.tokens.is_empty() {
+            return error("empty query");
+        }
+
+        let expr = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return error("unexpected ')'");
+        }
+
+        Ok(Query { expr })
+    }
+
+    pub fn matches(&self, song: &Song) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        evaluate(&self.expr, song, now)
+    }
+}
+
+fn evaluate(expr: &Expr, song: &Song, now: u64) -> bool {
+    match expr {
+        Expr::And(left, right) => evaluate(left, song, now) && evaluate(right, song, now),
+        Expr::Or(left, right) => evaluate(left, song, now) || evaluate(right, song, now),
+        Expr::Not(inner) => !evaluate(inner, song, now),
+        Expr::Term(Condition::Text(field, op, value)) => {
+            let tags = &song.tags;
+            let candidates: Vec<&str> = match field {
+                TextField::Title => vec![song.title.as_str()],
+                TextField::Artist => vec![tags.artist.as_deref().unwrap_or_default()],
+                TextField::Album => vec![tags.album.as_deref().unwrap_or_default()],
+                TextField::Genre => vec![tags.genre.as_deref().unwrap_or_default()],
+                TextField::Codec => vec![song.codec.as_str()],
+                TextField::Any => vec![
+                    song.title.as_str(),
+                    tags.artist.as_deref().unwrap_or_default(),
+                    tags.album.as_deref().unwrap_or_default(),
+                ],
+            };
+
+            let mut candidates = candidates.into_iter().map(str::to_lowercase);
+            match op {
+                Op::Contains => candidates.any(|text| text.contains(value.as_str())),
+                Op::Ne => candidates.all(|text| text != *value),
+                _ => candidates.any(|text| text == *value),
+            }
+        }
+        Expr::Term(Condition::Number(field, op, value)) => {
+            let actual = match field {
+                NumberField::Duration => Some(song.duration),
+                NumberField::Year => song.tags.year.map(f64::from),
+                NumberField::Track => song.tags.track.map(f64::from),
+                NumberField::Plays => Some(f64::from(song.stats.play_count)),
+                NumberField::Skips => Some(f64::from(song.stats.skip_count)),
+                NumberField::Rating => Some(f64::from(song.stats.rating)),
+                NumberField::Favourite => Some(f64::from(u8::from(song.stats.favourite))),
+                NumberField::Added => Some(now.saturating_sub(song.stats.added_at) as f64),
+                NumberField::Played => song
+                    .stats
+                    .last_played
+                    .map(|played| now.saturating_sub(played) as f64),
+            };
+            let Some(actual) = actual else {
+                return *op == Op::Ne;
+            };
+
+            match op {
+                Op::Lt => actual < *value,
+                Op::Le => actual <= *value,
+                Op::Gt => actual > *value,
+                Op::Ge => actual >= *value,
+                Op::Ne => actual != *value,
+                Op::Eq | Op::Contains => actual == *value,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::song::{SongStats, TrackTags};
+    use std::path::PathBuf;
+
+    const NOW: u64 = 1_000 * 86400;
+
+    fn song(title: &str, artist: &str, duration: f64, plays: u32, added_days_ago: u64) -> Song {
+        Song {
+            title: title.to_string(),
+            duration,
+            path: PathBuf::from(format!("{}.mp3", title)),
+            codec: "MP3".to_string(),
+            tags: TrackTags {
+                artist: Some(artist.to_string()),
+                ..TrackTags::default()
+            },
+            stats: SongStats {
+                play_count: plays,
+                added_at: NOW - added_days_ago * 86400,
+                ..SongStats::default()
+            },
+        }
+    }
+
+    fn matching(query: &str, songs: &[Song]) -> Vec<String> {
+        let query = Query::parse(query).unwrap();
+        songs
+            .iter()
+            .filter(|song| evaluate(&query.expr, song, NOW))
+            .map(|song| song.title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parses_terms_operators_and_precedence() {
+        let query = Query::parse(r#"artist:"Daft Punk" duration>5m OR NOT plays>=3"#).unwrap();
+        assert_eq!(
+            query.expr,
+            Expr::Or(
+                Box::new(Expr::And(
+                    Box::new(Expr::Term(Condition::Text(
+                        TextField::Artist,
+                        Op::Contains,
+                        "daft punk".to_string()
+                    ))),
+                    Box::new(Expr::Term(Condition::Number(
+                        NumberField::Duration,
+                        Op::Gt,
+                        300.0
+                    ))),
+                )),
+                Box::new(Expr::Not(Box::new(Expr::Term(Condition::Number(
+                    NumberField::Plays,
+                    Op::Ge,
+                    3.0
+                ))))),
+            )
+        );
+
+        // `added:30d` and `added:<30d` both mean "within the last 30 days"
+        assert_eq!(
+            Query::parse("added:30d").unwrap(),
+            Query::parse("added:<=30d").unwrap()
+        );
+        assert_eq!(
+            Query::parse("duration>5min").unwrap(),
+            Query::parse("duration>300").unwrap()
+        );
+        assert_eq!(
+            Query::parse("played:6mo").unwrap().expr,
+            Expr::Term(Condition::Number(
+                NumberField::Played,
+                Op::Le,
+                180.0 * 86400.0
+            ))
+        );
+        assert_eq!(
+            Query::parse("duration=3:20").unwrap().expr,
+            Expr::Term(Condition::Number(NumberField::Duration, Op::Eq, 200.0))
+        );
+    }
+
+    #[test]
+    fn reports_malformed_queries() {
+        for (input, message) in [
+            ("", "empty query"),
+            ("title:\"open", "missing closing quote"),
+            ("(rock", "missing closing parenthesis"),
+            ("rock)", "unexpected ')'"),
+            ("rock AND", "query ends too early"),
+            ("OR rock", "expected a term before AND/OR"),
+            ("mood:happy", "unknown field 'mood'"),
+            ("title>a", "title can only be compared with :, = or !="),
+            ("plays>many", "invalid value 'many' for plays"),
+            ("plays=>3", "unknown operator '=>'"),
+            ("added:6m", "invalid age '6m' for added, use mo for months"),
+        ] {
+            assert_eq!(
+                Query::parse(input).unwrap_err().to_string(),
+                message,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn selects_matching_songs() {
+        let songs = [
+            song("Around the World", "Daft Punk", 429.0, 5, 400),
+            song("One More Time", "Daft Punk", 320.0, 0, 3),
+            song("Time", "Pink Floyd", 413.0, 2, 10),
+        ];
+
+        assert_eq!(
+            matching("time", &songs),
+            vec!["One More Time".to_string(), "Time".to_string()]
+        );
+        assert_eq!(
+            matching(r#"artist="daft punk" plays<3"#, &songs),
+            vec!["One More Time".to_string()]
+        );
+        assert_eq!(
+            matching("added<30d AND NOT (artist:floyd OR duration>7m)", &songs),
+            vec!["One More Time".to_string()]
+        );
+        // Songs without the tag only match `!=`
+        assert_eq!(matching("year=1975", &songs), Vec::<String>::new());
+        assert_eq!(matching("year!=1975", &songs).len(), 3);
+    }
+}
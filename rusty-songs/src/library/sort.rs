@@ -0,0 +1,196 @@
+use crate::models::song::Song;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SortKey {
+    #[default]
+    Title,
+    Artist,
+    Album,
+    Duration,
+    DateAdded,
+    PlayCount,
+    Rating,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::Title,
+        SortKey::Artist,
+        SortKey::Album,
+        SortKey::Duration,
+        SortKey::DateAdded,
+        SortKey::PlayCount,
+        SortKey::Rating,
+    ];
+
+    pub fn next(self) -> Self {
+        let position = Self::ALL.iter().position(|key| *key == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Title => "title",
+            SortKey::Artist => "artist",
+            SortKey::Album => "album",
+            SortKey::Duration => "duration",
+            SortKey::DateAdded => "date added",
+            SortKey::PlayCount => "play count",
+            SortKey::Rating => "rating",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GroupBy {
+    #[default]
+    None,
+    Artist,
+    Album,
+}
+
+impl GroupBy {
+    pub fn next(self) -> Self {
+        match self {
+            GroupBy::None => GroupBy::Artist,
+            GroupBy::Artist => GroupBy::Album,
+            GroupBy::Album => GroupBy::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::None => "none",
+            GroupBy::Artist => "artist",
+            GroupBy::Album => "album",
+        }
+    }
+
+    /// Name of the group a song belongs to, or None when not grouping
+    pub fn group_name(self, song: &Song) -> Option<String> {
+        let name = match self {
+            GroupBy::None => return None,
+            GroupBy::Artist => song.tags.artist.clone(),
+            GroupBy::Album => song.tags.album.clone(),
+        };
+        Some(name.unwrap_or_else(|| "Unknown".to_string()))
+    }
+}
+
+fn compare_text(a: &Option<String>, b: &Option<String>) -> Ordering {
+    // Songs without the tag go last
+    match (a, b) {
+        (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub fn compare(a: &Song, b: &Song, key: SortKey) -> Ordering {
+    let ordering = match key {
+        SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        SortKey::Artist => compare_text(&a.tags.artist, &b.tags.artist),
+        SortKey::Album => {
+            compare_text(&a.tags.album, &b.tags.album).then_with(|| a.tags.track.cmp(&b.tags.track))
+        }
+        SortKey::Duration => a.duration.total_cmp(&b.duration),
+        SortKey::DateAdded => a.stats.added_at.cmp(&b.stats.added_at),
+        SortKey::PlayCount => a.stats.play_count.cmp(&b.stats.play_count),
+        SortKey::Rating => a.stats.rating.cmp(&b.stats.rating),
+    };
+
+    // Keep the order stable and predictable for equal keys
+    ordering.then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+}
+
+/// Sorts songs by group first (when grouping), then by the chosen key
+pub fn sort_songs(songs: &mut [Song], key: SortKey, descending: bool, group_by: GroupBy) {
+    songs.sort_by(|a, b| {
+        let group = compare_text(&group_by.group_name(a), &group_by.group_name(b));
+        let ordering = compare(a, b, key);
+        group.then(if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::song::{SongStats, TrackTags};
+    use std::path::PathBuf;
+
+    fn song(title: &str, artist: Option<&str>, album: Option<&str>, track: Option<u32>) -> Song {
+        Song {
+            title: title.to_string(),
+            duration: 1.0,
+            path: PathBuf::from(format!("{}.mp3", title)),
+            codec: "MP3".to_string(),
+            tags: TrackTags {
+                artist: artist.map(str::to_string),
+                album: album.map(str::to_string),
+                track,
+                ..TrackTags::default()
+            },
+            stats: SongStats::default(),
+        }
+    }
+
+    fn titles(songs: &[Song]) -> Vec<&str> {
+        songs.iter().map(|song| song.title.as_str()).collect()
+    }
+
+    #[test]
+    fn keys_and_groupings_cycle_back_to_the_start() {
+        let mut key = SortKey::default();
+        for _ in 0..SortKey::ALL.len() {
+            key = key.next();
+        }
+        assert_eq!(key, SortKey::Title);
+        assert_eq!(GroupBy::None.next().next().next(), GroupBy::None);
+    }
+
+    #[test]
+    fn songs_without_the_tag_go_last() {
+        let mut songs = vec![
+            song("c", None, None, None),
+            song("b", Some("beta"), None, None),
+            song("a", Some("Alpha"), None, None),
+        ];
+
+        sort_songs(&mut songs, SortKey::Artist, false, GroupBy::None);
+        assert_eq!(titles(&songs), vec!["a", "b", "c"]);
+
+        // Descending flips the tags, equal keys still fall back to the title
+        sort_songs(&mut songs, SortKey::Artist, true, GroupBy::None);
+        assert_eq!(titles(&songs), vec!["c", "b", "a"]);
+        sort_songs(&mut songs, SortKey::Duration, true, GroupBy::None);
+        assert_eq!(titles(&songs), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn albums_play_in_track_order_within_their_group() {
+        let mut songs = vec![
+            song("Intro", Some("B"), Some("Second"), Some(1)),
+            song("Outro", Some("A"), Some("First"), Some(2)),
+            song("Opener", Some("A"), Some("First"), Some(1)),
+            song("Single", None, None, None),
+        ];
+
+        sort_songs(&mut songs, SortKey::Album, false, GroupBy::Artist);
+        assert_eq!(titles(&songs), vec!["Opener", "Outro", "Intro", "Single"]);
+        assert_eq!(
+            GroupBy::Artist.group_name(&songs[3]),
+            Some("Unknown".to_string())
+        );
+
+        // Descending reverses the songs inside each group, not the groups
+        sort_songs(&mut songs, SortKey::Album, true, GroupBy::Artist);
+        assert_eq!(titles(&songs), vec!["Outro", "Opener", "Intro", "Single"]);
+    }
+}
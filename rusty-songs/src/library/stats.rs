@@ -0,0 +1,266 @@
+use crate::library::index::LibraryIndex;
+use crate::models::song::Song;
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: u64 = 86400;
+/// Days shown in the daily listening chart
+pub const DAYS: usize = 30;
+/// Weeks shown in the weekly listening chart
+pub const WEEKS: usize = 8;
+/// Entries in the top lists
+const TOP: usize = 10;
+/// Upper bounds (in kbit/s) of the bitrate buckets, the last one is open ended
+const BITRATE_BUCKETS: [(u64, &str); 5] = [
+    (96, "<96k"),
+    (160, "96-160k"),
+    (224, "160-224k"),
+    (320, "224-320k"),
+    (u64::MAX, "320k+"),
+];
+
+/// Numbers for the Stats view, computed from the library index and the play history
+#[derive(Clone, Debug, Default)]
+pub struct LibraryStats {
+    pub total_tracks: usize,
+    /// Seconds of music in the library
+    pub total_duration: f64,
+    pub total_size: u64,
+    /// Bytes per format, largest first
+    pub size_by_format: Vec<(String, u64)>,
+    /// Bytes per average bitrate bucket, lowest bitrate first
+    pub size_by_bitrate: Vec<(String, u64)>,
+    /// Artists of tagged songs, with plays and songs, most played first
+    pub top_artists: Vec<(String, u32, usize)>,
+    /// Channels of YouTube downloads, with plays and songs, most played first
+    pub top_channels: Vec<(String, u32, usize)>,
+    pub most_played: Vec<Song>,
+    pub never_played: Vec<Song>,
+    /// Seconds listened per day, oldest first, today last
+    pub daily_listening: Vec<u64>,
+    /// Seconds listened per week of seven days, oldest first, this week last
+    pub weekly_listening: Vec<u64>,
+    pub total_listening: u64,
+}
+
+/// Plays and songs per name, most played first
+fn top(counts: HashMap<String, (u32, usize)>) -> Vec<(String, u32, usize)> {
+    let mut top: Vec<(String, u32, usize)> = counts
+        .into_iter()
+        .map(|(name, (plays, songs))| (name, plays, songs))
+        .collect();
+    top.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then(b.2.cmp(&a.2))
+            .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+    });
+    top.truncate(TOP);
+    top
+}
+
+impl LibraryStats {
+    pub fn compute(index: &LibraryIndex, now: u64) -> Self {
+        let mut stats = LibraryStats::default();
+        let mut size_by_format: HashMap<String, u64> = HashMap::new();
+        let mut size_by_bitrate = vec![0u64; BITRATE_BUCKETS.len()];
+        let mut artists: HashMap<String, (u32, usize)> = HashMap::new();
+        let mut channels: HashMap<String, (u32, usize)> = HashMap::new();
+        let mut songs = Vec::new();
+
+        for entry in index.entries() {
+            let song = &entry.song;
+            let size = entry.stamp.size;
+            stats.total_tracks += 1;
+            stats.total_duration += song.duration.max(0.0);
+            stats.total_size += size;
+            *size_by_format.entry(song.codec.clone()).or_default() += size;
+
+            if song.duration > 0.0 {
+                let kbps = (size as f64 * 8.0 / song.duration / 1000.0) as u64;
+                let bucket = BITRATE_BUCKETS
+                    .iter()
+                    .position(|(limit, _)| kbps < *limit)
+                    .unwrap_or(BITRATE_BUCKETS.len() - 1);
+                size_by_bitrate[bucket] += size;
+            }
+
+            // Downloads start out with the channel as their artist, which is no artist yet
+            if let Some(artist) = song
+                .tags
+                .artist
+                .as_ref()
+                .filter(|&artist| song.tags.channel.as_ref() != Some(artist))
+            {
+                let (plays, count) = artists.entry(artist.clone()).or_default();
+                *plays += song.stats.play_count;
+                *count += 1;
+            }
+            if let Some(channel) = &song.tags.channel {
+                let (plays, count) = channels.entry(channel.clone()).or_default();
+                *plays += song.stats.play_count;
+                *count += 1;
+            }
+
+            songs.push(song.clone());
+        }
+
+        stats.size_by_format = size_by_format.into_iter().collect();
+        stats
+            .size_by_format
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.size_by_bitrate = BITRATE_BUCKETS
+            .iter()
+            .zip(size_by_bitrate)
+            .map(|((_, label), size)| (label.to_string(), size))
+            .collect();
+        stats.top_artists = top(artists);
+        stats.top_channels = top(channels);
+
+        songs.sort_by(|a, b| {
+            b.stats
+                .play_count
+                .cmp(&a.stats.play_count)
+                .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+        });
+        stats.never_played = songs
+            .iter()
+            .filter(|song| song.stats.play_count == 0)
+            .cloned()
+            .collect();
+        stats.most_played = songs
+            .into_iter()
+            .filter(|song| song.stats.play_count > 0)
+            .take(TOP)
+            .collect();
+
+        stats.daily_listening = vec![0; DAYS];
+        stats.weekly_listening = vec![0; WEEKS];
+        let today = now / SECONDS_PER_DAY;
+        for listen in index.history() {
+            stats.total_listening += listen.seconds;
+
+            let days_ago = today.saturating_sub(listen.at / SECONDS_PER_DAY) as usize;
+            if days_ago < DAYS {
+                stats.daily_listening[DAYS - 1 - days_ago] += listen.seconds;
+            }
+            if days_ago / 7 < WEEKS {
+                stats.weekly_listening[WEEKS - 1 - days_ago / 7] += listen.seconds;
+            }
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::index::{FileStamp, IndexEntry};
+    use crate::models::song::{SongStats, TrackTags};
+    use std::path::PathBuf;
+
+    fn song(name: &str, artist: &str, channel: Option<&str>) -> IndexEntry {
+        IndexEntry {
+            stamp: FileStamp { mtime: 1, size: 1 },
+            song: Song {
+                title: name.to_string(),
+                duration: 1.0,
+                path: PathBuf::from(format!("{}.mp3", name)),
+                codec: "MP3".to_string(),
+                tags: TrackTags {
+                    artist: Some(artist.to_string()),
+                    video_id: channel.map(|_| format!("{:_<11}", name)),
+                    channel: channel.map(str::to_string),
+                    ..TrackTags::default()
+                },
+                stats: SongStats::default(),
+            },
+            content_hash: Some(name.to_string()),
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn groups_downloads_by_channel_and_identified_songs_by_artist() {
+        let mut index = LibraryIndex::default();
+        for (entry, plays) in [
+            // Identified after downloading, the artist is no longer the channel
+            (song("a", "Artist", Some("Label Uploads")), 3),
+            // Not identified yet, its artist is only the channel
+            (song("b", "Label Uploads", Some("Label Uploads")), 1),
+            (song("c", "Artist", None), 2),
+        ] {
+            let path = entry.song.path.clone();
+            index.insert(path.clone(), entry);
+            index.update_stats(&path, |stats| stats.play_count = plays);
+        }
+
+        let stats = LibraryStats::compute(&index, 0);
+        assert_eq!(stats.top_artists, vec![("Artist".to_string(), 5, 2)]);
+        assert_eq!(
+            stats.top_channels,
+            vec![("Label Uploads".to_string(), 4, 2)]
+        );
+    }
+
+    #[test]
+    fn buckets_sizes_by_format_and_bitrate() {
+        let mut index = LibraryIndex::default();
+        for (name, codec, size, duration) in [
+            ("low", "MP3", 1_000_000, 100.0),
+            // Bucket bounds are exclusive, so 160k goes with the higher bitrates
+            ("edge", "MP3", 2_000_000, 100.0),
+            ("high", "FLAC", 5_000_000, 100.0),
+            ("broken", "FLAC", 500, 0.0),
+        ] {
+            let mut entry = song(name, "Artist", None);
+            entry.stamp.size = size;
+            entry.song.codec = codec.to_string();
+            entry.song.duration = duration;
+            index.insert(entry.song.path.clone(), entry);
+        }
+
+        let stats = LibraryStats::compute(&index, 0);
+        assert_eq!(stats.total_tracks, 4);
+        assert_eq!(stats.total_duration, 300.0);
+        assert_eq!(
+            stats.size_by_format,
+            vec![
+                ("FLAC".to_string(), 5_000_500),
+                ("MP3".to_string(), 3_000_000)
+            ]
+        );
+        // Files without a duration have no bitrate
+        assert_eq!(
+            stats
+                .size_by_bitrate
+                .iter()
+                .map(|(_, size)| *size)
+                .collect::<Vec<_>>(),
+            vec![1_000_000, 0, 2_000_000, 0, 5_000_000]
+        );
+    }
+
+    #[test]
+    fn listening_time_lands_in_its_day_and_week() {
+        let mut index = LibraryIndex::default();
+        index.record_listen(60.0);
+        index.record_listen(30.0);
+        // Too short to count as listening
+        index.record_listen(0.5);
+        let at = index.history()[0].at;
+
+        let stats = LibraryStats::compute(&index, at);
+        assert_eq!(stats.daily_listening[DAYS - 1], 90);
+        assert_eq!(stats.weekly_listening[WEEKS - 1], 90);
+
+        let stats = LibraryStats::compute(&index, at + 7 * SECONDS_PER_DAY);
+        assert_eq!(stats.daily_listening[DAYS - 8], 90);
+        assert_eq!(stats.weekly_listening[WEEKS - 2], 90);
+
+        // Beyond both charts, but still part of the total
+        let stats = LibraryStats::compute(&index, at + 100 * SECONDS_PER_DAY);
+        assert!(stats.daily_listening.iter().all(|&seconds| seconds == 0));
+        assert!(stats.weekly_listening.iter().all(|&seconds| seconds == 0));
+        assert_eq!(stats.total_listening, 90);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::library::audio_format::{can_decode, open_format};
+use crate::library::duration::header_duration;
+use crate::models::song::Song;
+use crate::utils::format::format_duration;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::default::get_codecs;
+
+/// Decoded and stated durations may differ this much (in seconds) without being a problem
+const DURATION_TOLERANCE: f64 = 2.0;
+/// Or this fraction of the stated duration, for long files
+const DURATION_TOLERANCE_RATIO: f64 = 0.02;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// The file cannot be opened or has no playable audio track
+    Unreadable(String),
+    /// Packets that failed to decode, with the first error
+    DecodeErrors {
+        count: usize,
+        first: String,
+    },
+    /// The audio stops well before the duration its headers state, e.g. an interrupted download
+    Truncated {
+        decoded: f64,
+        expected: f64,
+    },
+    ZeroDuration,
+    /// The audio runs longer than its headers state
+    DurationMismatch {
+        decoded: f64,
+        expected: f64,
+    },
+}
+
+impl Problem {
+    pub fn describe(&self) -> String {
+        match self {
+            Problem::Unreadable(reason) => format!("unreadable: {}", reason),
+            Problem::DecodeErrors { count, first } => {
+                format!("{} decode errors, first: {}", count, first)
+            }
+            Problem::Truncated { decoded, expected } => format!(
+                "truncated: plays {} of {}",
+                format_duration(*decoded),
+                format_duration(*expected)
+            ),
+            Problem::ZeroDuration => "no audio".to_string(),
+            Problem::DurationMismatch { decoded, expected } => format!(
+                "headers say {}, decodes to {}",
+                format_duration(*expected),
+                format_duration(*decoded)
+            ),
+        }
+    }
+}
+
+/// A file with at least one problem. Files that could never be indexed have no song.
+#[derive(Debug, Clone)]
+pub struct VerifyIssue {
+    pub path: PathBuf,
+    pub song: Option<Song>,
+    /// Set for YouTube downloads, which can be downloaded again
+    pub video_id: Option<String>,
+    pub problems: Vec<Problem>,
+}
+
+impl VerifyIssue {
+    pub fn title(&self) -> String {
+        match &self.song {
+            Some(song) => song.title.clone(),
+            None => self
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+}
+
+/// Decodes the whole file and reports everything that is wrong with it. This is slow.
+pub fn verify_file(path: &Path) -> Vec<Problem> {
+    let Some(mut format) = open_format(path) else {
+        return vec![Problem::Unreadable(
+            "not a supported audio file".to_string(),
+        )];
+    };
+    let Some(track) = format.default_track() else {
+        return vec![Problem::Unreadable("no audio track".to_string())];
+    };
+    if !can_decode(&track.codec_params) {
+        // Only the headers can be checked, e.g. for Opus
+        return match header_duration(path) {
+            Some(duration) if duration > 0.0 => Vec::new(),
+            _ => vec![Problem::ZeroDuration],
+        };
+    }
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
+    let mut decoder = match get_codecs().make(&track.codec_params, &Default::default()) {
+        Ok(decoder) => decoder,
+        Err(e) => return vec![Problem::Unreadable(e.to_string())],
+    };
+
+    let mut frames = 0u64;
+    let mut errors = 0;
+    let mut first_error = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // The normal end of the stream
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => {
+                errors += 1;
+                first_error.get_or_insert(e.to_string());
+                break;
+            }
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => frames += decoded.frames() as u64,
+            Err(SymphoniaError::DecodeError(e)) => {
+                errors += 1;
+                first_error.get_or_insert(e.to_string());
+            }
+            Err(e) => {
+                errors += 1;
+                first_error.get_or_insert(e.to_string());
+                break;
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+    if let Some(first) = first_error {
+        problems.push(Problem::DecodeErrors {
+            count: errors,
+            first,
+        });
+    }
+
+    let decoded = frames as f64 / f64::from(sample_rate);
+    if decoded <= 0.0 {
+        problems.push(Problem::ZeroDuration);
+        return problems;
+    }
+
+    if let Some(expected) = header_duration(path).filter(|expected| *expected > 0.0) {
+        problems.extend(duration_problem(decoded, expected));
+    }
+
+    problems
+}
+
+/// Compares the decoded duration against the one the headers state
+fn duration_problem(decoded: f64, expected: f64) -> Option<Problem> {
+    let tolerance = DURATION_TOLERANCE.max(expected * DURATION_TOLERANCE_RATIO);
+    if decoded < expected - tolerance {
+        Some(Problem::Truncated { decoded, expected })
+    } else if decoded > expected + tolerance {
+        Some(Problem::DurationMismatch { decoded, expected })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    /// A mono 16 bit WAV file whose header states `stated_frames`, holding `frames` of silence
+    fn wav(name: &str, stated_frames: u32, frames: u32) -> PathBuf {
+        const SAMPLE_RATE: u32 = 8000;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"RIFF");
+        data.extend_from_slice(&(36 + stated_frames * 2).to_le_bytes());
+        data.extend_from_slice(b"WAVEfmt ");
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        data.extend_from_slice(&1u16.to_le_bytes()); // mono
+        data.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        data.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&16u16.to_le_bytes());
+        data.extend_from_slice(b"data");
+        data.extend_from_slice(&(stated_frames * 2).to_le_bytes());
+        data.resize(data.len() + frames as usize * 2, 0);
+
+        let path = env::temp_dir().join(format!(
+            "rusty_songs_verify_{}_{}.wav",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn small_duration_differences_are_tolerated() {
+        // Two seconds either way for short songs
+        assert_eq!(duration_problem(58.5, 60.0), None);
+        assert_eq!(duration_problem(61.9, 60.0), None);
+        assert_eq!(
+            duration_problem(57.0, 60.0),
+            Some(Problem::Truncated {
+                decoded: 57.0,
+                expected: 60.0
+            })
+        );
+        assert_eq!(
+            duration_problem(63.0, 60.0),
+            Some(Problem::DurationMismatch {
+                decoded: 63.0,
+                expected: 60.0
+            })
+        );
+
+        // Two percent for long ones, e.g. an hour long mix
+        assert_eq!(duration_problem(3540.0, 3600.0), None);
+        assert!(matches!(
+            duration_problem(3500.0, 3600.0),
+            Some(Problem::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_interrupted_and_unreadable_files() {
+        let complete = wav("complete", 24000, 24000);
+        assert_eq!(verify_file(&complete), Vec::new());
+
+        // The header promises 10 seconds, the download stopped after 3
+        let truncated = wav("truncated", 80000, 24000);
+        assert!(matches!(
+            verify_file(&truncated)[..],
+            [Problem::Truncated { expected, .. }] if expected == 10.0
+        ));
+
+        let text = env::temp_dir().join(format!("rusty_songs_verify_{}.mp3", std::process::id()));
+        fs::write(&text, "not audio").unwrap();
+        assert!(matches!(verify_file(&text)[..], [Problem::Unreadable(_)]));
+
+        for path in [complete, truncated, text] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::library::config::LibraryConfig;
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+/// How long a path has to stay quiet before it is considered fully written
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Watches the library roots through inotify and reports paths that changed
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl LibraryWatcher {
+    pub fn new(config: &LibraryConfig) -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = RecommendedWatcher::new(
+            sender,
+            Config::default().with_follow_symlinks(config.follow_symlinks),
+        )?;
+        for root in &config.roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+        }
+
+        Ok(LibraryWatcher {
+            _watcher: watcher,
+            events,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Drains the queued filesystem events and returns the paths that have settled.
+    /// Files still being written keep producing events, so they are held back until they stop.
+    pub fn settled_paths(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => {
+                    for path in event.paths {
+                        self.pending.insert(path, now);
+                    }
+                }
+                Err(e) => eprintln!("Library watcher error: {}", e),
+            }
+        }
+
+        let settled: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, last_event)| now.duration_since(**last_event) >= SETTLE_DELAY)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &settled {
+            self.pending.remove(path);
+        }
+
+        settled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use globset::GlobSet;
+    use std::{env, fs, thread};
+
+    #[test]
+    fn reports_files_once_they_stop_changing() {
+        let dir = env::temp_dir().join(format!("rusty_songs_watcher_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let config = LibraryConfig {
+            roots: vec![dir.clone()],
+            follow_symlinks: false,
+            excludes: GlobSet::empty(),
+        };
+        let mut watcher = LibraryWatcher::new(&config).unwrap();
+
+        let song = dir.join("song.mp3");
+        fs::write(&song, b"first half").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert!(watcher.settled_paths().is_empty());
+
+        thread::sleep(SETTLE_DELAY);
+        assert!(watcher.settled_paths().contains(&song));
+        assert!(watcher.settled_paths().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
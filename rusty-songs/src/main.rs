@@ -1,17 +1,125 @@
 extern crate dotenv;
 
 use dotenv::dotenv;
+use rusty_songs::library::import::{import_file, plan_import, ImportMode};
+use rusty_songs::library::index::LibraryIndex;
+use rusty_songs::library::naming::NamingTemplate;
 use rusty_songs::tui::app;
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+const IMPORT_USAGE: &str =
+    "Usage: rusty_songs import [--move] [--dry-run] [--template TEMPLATE] PATH...";
+
+/// Copies or moves files from anywhere into MUSIC_DIR, named after their tags.
+/// The running app picks them up through its library watcher, or on its next start.
+fn import(args: &[String]) -> ExitCode {
+    let mut mode = ImportMode::Copy;
+    let mut dry_run = false;
+    let mut template = NamingTemplate::from_env();
+    let mut inputs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--move" => mode = ImportMode::Move,
+            "--dry-run" | "-n" => dry_run = true,
+            "--template" => {
+                let parsed = args
+                    .next()
+                    .ok_or_else(|| "--template needs a value".to_string())
+                    .and_then(|value| NamingTemplate::parse(value));
+                match parsed {
+                    Ok(parsed) => template = parsed,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            "--help" | "-h" => {
+                println!("{}", IMPORT_USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if inputs.is_empty() {
+        eprintln!("{}", IMPORT_USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let plan = plan_import(&inputs, &template, &LibraryIndex::music_dir());
+    let mut imported = 0;
+    let mut skipped = 0;
+    let mut failed = 0;
+    for item in &plan {
+        if let Some(reason) = &item.skip {
+            println!(
+                "skip  {} -> {} ({})",
+                item.source.display(),
+                item.destination.display(),
+                reason
+            );
+            skipped += 1;
+            continue;
+        }
+
+        println!(
+            "{}  {} -> {}",
+            if mode == ImportMode::Move {
+                "move"
+            } else {
+                "copy"
+            },
+            item.source.display(),
+            item.destination.display()
+        );
+        if dry_run {
+            imported += 1;
+        } else if let Err(e) = import_file(item, mode) {
+            eprintln!("Failed to import {}: {}", item.source.display(), e);
+            failed += 1;
+        } else {
+            imported += 1;
+        }
+    }
+
+    println!(
+        "{} {} files, skipped {}{}",
+        if dry_run { "Would import" } else { "Imported" },
+        imported,
+        skipped,
+        if failed > 0 {
+            format!(", {} failed", failed)
+        } else {
+            String::new()
+        }
+    );
+
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     dotenv().ok();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("import") {
+        return import(&args[1..]);
+    }
+
     let app = Arc::new(Mutex::new(app::App::new()));
 
     if let Err(e) = app::App::run(app.clone()).await {
         eprintln!("Application error: {}", e);
     }
+    ExitCode::SUCCESS
 }
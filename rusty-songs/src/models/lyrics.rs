@@ -0,0 +1,25 @@
+use serde::Deserialize;
+
+/// A track as returned by an LRCLIB-compatible lyrics API
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsRecord {
+    pub track_name: Option<String>,
+    pub artist_name: Option<String>,
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub instrumental: bool,
+    pub plain_lyrics: Option<String>,
+    pub synced_lyrics: Option<String>,
+}
+
+impl LyricsRecord {
+    /// Synced lyrics in LRC format when available, plain text otherwise
+    pub fn best_lyrics(&self) -> Option<&str> {
+        self.synced_lyrics
+            .as_deref()
+            .filter(|lyrics| !lyrics.trim().is_empty())
+            .or(self.plain_lyrics.as_deref())
+            .filter(|lyrics| !lyrics.trim().is_empty())
+    }
+}
@@ -1,2 +1,4 @@
+pub mod lyrics;
+pub mod musicbrainz;
 pub mod song;
 pub mod video;
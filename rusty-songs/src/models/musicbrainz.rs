@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+/// Response of a MusicBrainz-compatible `/recording` search
+#[derive(Debug, Deserialize)]
+pub struct RecordingSearch {
+    #[serde(default)]
+    pub recordings: Vec<Recording>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Recording {
+    pub id: String,
+    /// How well the recording matches the query, from 0 to 100
+    #[serde(default)]
+    pub score: u8,
+    pub title: String,
+    /// Length in milliseconds
+    pub length: Option<u64>,
+    #[serde(rename = "artist-credit", default)]
+    pub artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    pub releases: Vec<Release>,
+    #[serde(rename = "first-release-date")]
+    pub first_release_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ArtistCredit {
+    pub name: String,
+    /// Text joining this artist to the next one, e.g. " feat. "
+    #[serde(default)]
+    pub joinphrase: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Release {
+    pub id: String,
+    pub title: String,
+    pub date: Option<String>,
+    #[serde(default)]
+    pub media: Vec<Medium>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Medium {
+    /// The tracks of this medium that are the searched recording
+    #[serde(default)]
+    pub track: Vec<ReleaseTrack>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReleaseTrack {
+    pub number: Option<String>,
+    pub position: Option<u32>,
+}
+
+impl Release {
+    /// Position of the searched recording on this release
+    pub fn track_number(&self) -> Option<u32> {
+        let track = self.media.iter().flat_map(|medium| &medium.track).next()?;
+        track
+            .number
+            .as_deref()
+            .and_then(|number| number.parse().ok())
+            .or(track.position)
+    }
+}
+
+impl Recording {
+    /// All credited artists as one name, e.g. "Artist feat. Other"
+    pub fn artist(&self) -> Option<String> {
+        let artist: String = self
+            .artist_credit
+            .iter()
+            .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+            .collect();
+        let artist = artist.trim();
+        (!artist.is_empty()).then(|| artist.to_string())
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> Option<f64> {
+        self.length.map(|length| length as f64 / 1000.0)
+    }
+
+    /// Year of the earliest release, e.g. 2004 from "2004-05-17"
+    pub fn year(&self, release: Option<&Release>) -> Option<i32> {
+        self.first_release_date
+            .as_deref()
+            .or_else(|| release.and_then(|release| release.date.as_deref()))
+            .and_then(|date| date.get(0..4))
+            .and_then(|year| year.parse().ok())
+    }
+}
@@ -1,5 +1,106 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Song {
     pub title: String,
     pub duration: f64,
+    pub path: PathBuf,
+    pub codec: String,
+    #[serde(default)]
+    pub tags: TrackTags,
+    #[serde(default)]
+    pub stats: SongStats,
+}
+
+/// Listening statistics and user ratings of a song, kept by the library index
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SongStats {
+    #[serde(default)]
+    pub play_count: u32,
+    #[serde(default)]
+    pub skip_count: u32,
+    /// Unix timestamp of the last time the song was played
+    #[serde(default)]
+    pub last_played: Option<u64>,
+    /// Unix timestamp of when the song first showed up in the library
+    #[serde(default)]
+    pub added_at: u64,
+    /// 0 to 5 stars
+    #[serde(default)]
+    pub rating: u8,
+    #[serde(default)]
+    pub favourite: bool,
+}
+
+pub const MAX_RATING: u8 = 5;
+
+impl SongStats {
+    /// Combines the statistics of two copies of the same song
+    pub fn merge(&mut self, other: &SongStats) {
+        self.play_count += other.play_count;
+        self.skip_count += other.skip_count;
+        self.last_played = self.last_played.max(other.last_played);
+        self.added_at = match (self.added_at, other.added_at) {
+            (0, other) => other,
+            (own, 0) => own,
+            (own, other) => own.min(other),
+        };
+        self.rating = self.rating.max(other.rating);
+        self.favourite |= other.favourite;
+    }
+}
+
+/// Tags read from the audio file itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<u32>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub video_id: Option<String>,
+    /// YouTube channel a download came from. The artist starts out as the channel too,
+    /// but no longer matches it once the song is identified or edited.
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+impl TrackTags {
+    /// Fills every field that is missing here with the value from `other`
+    pub fn merge_missing(&mut self, other: &TrackTags) {
+        fn fill<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+            if field.is_none() {
+                field.clone_from(other);
+            }
+        }
+
+        fill(&mut self.title, &other.title);
+        fill(&mut self.artist, &other.artist);
+        fill(&mut self.album, &other.album);
+        fill(&mut self.track, &other.track);
+        fill(&mut self.year, &other.year);
+        fill(&mut self.genre, &other.genre);
+        fill(&mut self.video_id, &other.video_id);
+        fill(&mut self.channel, &other.channel);
+    }
+
+    /// Overwrites every field that is set in `other`
+    pub fn overwrite_with(&mut self, other: &TrackTags) {
+        fn set<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+            if other.is_some() {
+                field.clone_from(other);
+            }
+        }
+
+        set(&mut self.title, &other.title);
+        set(&mut self.artist, &other.artist);
+        set(&mut self.album, &other.album);
+        set(&mut self.track, &other.track);
+        set(&mut self.year, &other.year);
+        set(&mut self.genre, &other.genre);
+        set(&mut self.video_id, &other.video_id);
+        set(&mut self.channel, &other.channel);
+    }
 }
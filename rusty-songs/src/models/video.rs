@@ -1,14 +1,43 @@
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize)]
+/// One page of search results
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct SearchResponse {
     pub items: Vec<Video>,
+    /// Passed back to fetch the following page, missing on the last one
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    #[serde(rename = "pageInfo")]
+    pub page_info: Option<PageInfo>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PageInfo {
+    /// YouTube's rough estimate of how many videos match
+    #[serde(rename = "totalResults")]
+    pub total_results: u64,
+    #[serde(rename = "resultsPerPage")]
+    pub results_per_page: u32,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Video {
     pub id: VideoId,
     pub snippet: Snippet,
+    /// Filled in by a follow-up videos.list call, search results do not carry it
+    #[serde(skip)]
+    pub details: Option<VideoDetails>,
+}
+
+impl Video {
+    /// Length in seconds, unknown for live streams and premieres
+    pub fn duration(&self) -> Option<f64> {
+        self.details.as_ref()?.duration()
+    }
+
+    pub fn view_count(&self) -> Option<u64> {
+        self.details.as_ref()?.view_count()
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -23,4 +52,178 @@ pub struct Snippet {
     pub description: String,
     #[serde(rename = "channelTitle")]
     pub channel_title: String,
+    #[serde(rename = "publishedAt")]
+    pub published_at: Option<String>,
+    pub thumbnails: Option<Thumbnails>,
+    /// "live", "upcoming" or "none"
+    #[serde(rename = "liveBroadcastContent")]
+    pub live_broadcast_content: Option<String>,
+}
+
+/// Whether a video is a live stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiveStatus {
+    NotLive,
+    Live,
+    Upcoming,
+}
+
+impl Snippet {
+    pub fn live_status(&self) -> LiveStatus {
+        match self.live_broadcast_content.as_deref() {
+            Some("live") => LiveStatus::Live,
+            Some("upcoming") => LiveStatus::Upcoming,
+            _ => LiveStatus::NotLive,
+        }
+    }
+
+    /// The upload date, e.g. "2021-03-04" from publishedAt "2021-03-04T05:06:07Z"
+    pub fn published_date(&self) -> Option<&str> {
+        self.published_at.as_deref()?.get(..10)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Thumbnails {
+    pub default: Option<Thumbnail>,
+    pub medium: Option<Thumbnail>,
+    pub high: Option<Thumbnail>,
+}
+
+impl Thumbnails {
+    /// Returns the highest resolution thumbnail available
+    pub fn best(&self) -> Option<&Thumbnail> {
+        self.high
+            .as_ref()
+            .or(self.medium.as_ref())
+            .or(self.default.as_ref())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Thumbnail {
+    pub url: String,
+}
+
+/// Body of a failed API request, e.g. when the quota is used up or a filter is invalid
+#[derive(Debug, Deserialize, Clone)]
+pub struct ApiErrorResponse {
+    pub error: ApiError,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ApiError {
+    pub message: String,
+}
+
+/// Response of videos.list
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct VideoListResponse {
+    #[serde(default)]
+    pub items: Vec<VideoDetails>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct VideoDetails {
+    pub id: String,
+    #[serde(rename = "contentDetails")]
+    pub content_details: Option<ContentDetails>,
+    pub statistics: Option<Statistics>,
+}
+
+impl VideoDetails {
+    pub fn duration(&self) -> Option<f64> {
+        parse_iso8601_duration(&self.content_details.as_ref()?.duration)
+            // Live streams report "P0D"
+            .filter(|duration| *duration > 0.0)
+    }
+
+    pub fn view_count(&self) -> Option<u64> {
+        self.statistics
+            .as_ref()?
+            .view_count
+            .as_deref()?
+            .parse()
+            .ok()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ContentDetails {
+    /// ISO 8601, e.g. "PT4M13S"
+    pub duration: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Statistics {
+    /// Counts come as strings, and are missing when the uploader hides them
+    #[serde(rename = "viewCount")]
+    pub view_count: Option<String>,
+}
+
+/// Seconds of an ISO 8601 duration like "PT1H2M3S", "P1DT2H" or "PT4.5S"
+pub fn parse_iso8601_duration(text: &str) -> Option<f64> {
+    let rest = text.strip_prefix('P')?;
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    let mut in_time = false;
+
+    for c in rest.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'T' if number.is_empty() => in_time = true,
+            _ => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (c, in_time) {
+                        ('W', false) => 7.0 * 86400.0,
+                        ('D', false) => 86400.0,
+                        ('H', true) => 3600.0,
+                        ('M', true) => 60.0,
+                        ('S', true) => 1.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+
+    number.is_empty().then_some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso8601_durations() {
+        assert_eq!(parse_iso8601_duration("PT3M20S"), Some(200.0));
+        assert_eq!(parse_iso8601_duration("PT1H2M3S"), Some(3723.0));
+        assert_eq!(parse_iso8601_duration("PT45.5S"), Some(45.5));
+        assert_eq!(parse_iso8601_duration("P1DT1H"), Some(90_000.0));
+        assert_eq!(parse_iso8601_duration("P1W"), Some(604_800.0));
+        assert_eq!(parse_iso8601_duration("P0D"), Some(0.0));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        for invalid in ["", "3M20S", "PT3M20", "P3M", "PT1D", "PTXS", "PT1H1T"] {
+            assert_eq!(parse_iso8601_duration(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reads_the_token_of_the_next_page() {
+        let page: SearchResponse = serde_json::from_str(
+            r#"{"items": [], "nextPageToken": "CAoQAA", "pageInfo": {"totalResults": 1000000, "resultsPerPage": 10}}"#,
+        )
+        .unwrap();
+        assert_eq!(page.next_page_token.as_deref(), Some("CAoQAA"));
+        assert_eq!(page.page_info.unwrap().total_results, 1_000_000);
+
+        // The last page has no token
+        let page: SearchResponse = serde_json::from_str(r#"{"items": []}"#).unwrap();
+        assert_eq!(page.next_page_token, None);
+        assert!(page.page_info.is_none());
+    }
 }
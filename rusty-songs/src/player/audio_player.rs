@@ -0,0 +1,179 @@
+use crate::models::song::Song;
+#[cfg(unix)]
+use nix::sys::signal::{kill, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
+use std::io;
+use std::process::{Child, Command, Stdio};
+use std::time::Instant;
+
+/// Plays songs through ffplay, which decodes every format the library accepts.
+/// The playback position is tracked locally since ffplay does not report it.
+pub struct AudioPlayer {
+    process: Option<Child>,
+    song: Option<Song>,
+    started_at: Option<Instant>,
+    // Seconds played before the last pause
+    elapsed_before_pause: f64,
+}
+
+impl Default for AudioPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioPlayer {
+    pub fn new() -> Self {
+        AudioPlayer {
+            process: None,
+            song: None,
+            started_at: None,
+            elapsed_before_pause: 0.0,
+        }
+    }
+
+    pub fn play(&mut self, song: Song) -> io::Result<()> {
+        self.stop();
+
+        let process = Command::new("ffplay")
+            .args(["-nodisp", "-autoexit", "-loglevel", "quiet"])
+            .arg(&song.path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        self.process = Some(process);
+        self.song = Some(song);
+        self.started_at = Some(Instant::now());
+        self.elapsed_before_pause = 0.0;
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            // A paused process has to be resumed before it can exit, one that already
+            // exited cannot be signalled
+            let _ = Self::resume(&process);
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        self.song = None;
+        self.started_at = None;
+        self.elapsed_before_pause = 0.0;
+    }
+
+    /// Pauses or resumes ffplay. The position only stops or starts counting once ffplay
+    /// got the signal, so a failed attempt leaves it in step with what is heard.
+    pub fn toggle_pause(&mut self) -> io::Result<()> {
+        let Some(process) = &self.process else {
+            return Ok(());
+        };
+
+        match self.started_at {
+            Some(started_at) => {
+                Self::pause(process)?;
+                self.elapsed_before_pause += started_at.elapsed().as_secs_f64();
+                self.started_at = None;
+            }
+            None => {
+                Self::resume(process)?;
+                self.started_at = Some(Instant::now());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn pause(process: &Child) -> io::Result<()> {
+        Self::signal(process, Signal::SIGSTOP)
+    }
+
+    #[cfg(unix)]
+    fn resume(process: &Child) -> io::Result<()> {
+        Self::signal(process, Signal::SIGCONT)
+    }
+
+    #[cfg(unix)]
+    fn signal(process: &Child, signal: Signal) -> io::Result<()> {
+        let pid = i32::try_from(process.id()).map_err(io::Error::other)?;
+        kill(Pid::from_raw(pid), signal).map_err(io::Error::from)
+    }
+
+    // ffplay has no way to be paused from outside on other platforms
+    #[cfg(not(unix))]
+    fn pause(_process: &Child) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pausing is only supported on Unix",
+        ))
+    }
+
+    #[cfg(not(unix))]
+    fn resume(_process: &Child) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn current_song(&self) -> Option<&Song> {
+        self.song.as_ref()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.process.is_some() && self.started_at.is_none()
+    }
+
+    /// Playback position in seconds
+    pub fn position(&self) -> f64 {
+        let running = self
+            .started_at
+            .map(|started_at| started_at.elapsed().as_secs_f64())
+            .unwrap_or(0.0);
+        let position = self.elapsed_before_pause + running;
+
+        match &self.song {
+            Some(song) if song.duration > 0.0 => position.min(song.duration),
+            _ => position,
+        }
+    }
+
+    /// Returns the current song once, when it has played to the end
+    pub fn take_finished(&mut self) -> Option<Song> {
+        let finished = self
+            .process
+            .as_mut()
+            .is_some_and(|process| matches!(process.try_wait(), Ok(Some(_))));
+
+        if !finished {
+            return None;
+        }
+
+        self.process = None;
+        self.started_at = None;
+        self.elapsed_before_pause = 0.0;
+        self.song.take()
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_and_resumes_a_running_process() {
+        let mut process = Command::new("sleep").arg("10").spawn().unwrap();
+        assert!(AudioPlayer::pause(&process).is_ok());
+        assert!(AudioPlayer::resume(&process).is_ok());
+
+        process.kill().unwrap();
+        process.wait().unwrap();
+        assert!(AudioPlayer::pause(&process).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use reqwest::Client;
+use std::env;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+
+/// Public LRCLIB instance, used unless LYRICS_API_URL points somewhere else
+const DEFAULT_LYRICS_API_URL: &str = "https://lrclib.net/api";
+
+pub struct LyricsClient {
+    pub base_url: String,
+    pub client: Client,
+}
+
+impl LyricsClient {
+    fn new(base_url: String) -> Self {
+        // LRCLIB asks clients to identify themselves
+        let client = Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                " ",
+                env!("CARGO_PKG_VERSION"),
+                " (",
+                env!("CARGO_PKG_REPOSITORY"),
+                ")"
+            ))
+            .build()
+            .unwrap_or_default();
+
+        LyricsClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+        }
+    }
+
+    pub fn get_instance() -> Arc<Mutex<LyricsClient>> {
+        static SINGLETON: OnceLock<Arc<Mutex<LyricsClient>>> = OnceLock::new();
+
+        SINGLETON
+            .get_or_init(|| {
+                let base_url = env::var("LYRICS_API_URL")
+                    .unwrap_or_else(|_| DEFAULT_LYRICS_API_URL.to_string());
+                Arc::new(Mutex::new(LyricsClient::new(base_url)))
+            })
+            .clone()
+    }
+}
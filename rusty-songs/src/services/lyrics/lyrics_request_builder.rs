@@ -0,0 +1,132 @@
+use crate::models::lyrics::LyricsRecord;
+use crate::services::lyrics::lyrics_client::LyricsClient;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use std::error::Error;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Looks up the lyrics of one track, through the exact `/get` or the fuzzier `/search` endpoint
+pub struct LyricsRequestBuilder {
+    artist: String,
+    title: String,
+    album: Option<String>,
+    duration: Option<f64>,
+    api_client: Arc<Mutex<LyricsClient>>,
+}
+
+impl LyricsRequestBuilder {
+    pub fn new(artist: String, title: String) -> Self {
+        LyricsRequestBuilder {
+            artist,
+            title,
+            album: None,
+            duration: None,
+            api_client: LyricsClient::get_instance(),
+        }
+    }
+
+    pub fn album(mut self, album: Option<String>) -> Self {
+        self.album = album;
+        self
+    }
+
+    pub fn duration(mut self, duration: f64) -> Self {
+        self.duration = (duration > 0.0).then_some(duration);
+        self
+    }
+
+    async fn client(&self) -> (String, Client) {
+        // Clone the client so lookups running in parallel do not wait on each other
+        let api_client = self.api_client.lock().await;
+        (api_client.base_url.clone(), api_client.client.clone())
+    }
+
+    fn get_request(&self, client: &Client, base_url: &str) -> RequestBuilder {
+        let mut params = vec![
+            ("artist_name", self.artist.clone()),
+            ("track_name", self.title.clone()),
+        ];
+        if let Some(album) = self.album.clone() {
+            params.push(("album_name", album));
+        }
+        if let Some(duration) = self.duration {
+            params.push(("duration", format!("{}", duration.round())));
+        }
+
+        client.get(format!("{}/get", base_url)).query(&params)
+    }
+
+    fn search_request(&self, client: &Client, base_url: &str) -> RequestBuilder {
+        client
+            .get(format!("{}/search", base_url))
+            .query(&[("artist_name", &self.artist), ("track_name", &self.title)])
+    }
+
+    /// Returns None when the provider does not know the track
+    pub async fn send(self) -> Result<Option<LyricsRecord>, Box<dyn Error>> {
+        let (base_url, client) = self.client().await;
+        let response = self.get_request(&client, &base_url).send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let record: LyricsRecord = response.error_for_status()?.json().await?;
+
+        Ok(Some(record))
+    }
+
+    /// All tracks matching the artist and title, ignoring album and duration
+    pub async fn search(self) -> Result<Vec<LyricsRecord>, Box<dyn Error>> {
+        let (base_url, client) = self.client().await;
+        let records = self
+            .search_request(&client, &base_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://lyrics.example/api";
+
+    fn url(request: RequestBuilder) -> String {
+        request.build().unwrap().url().to_string()
+    }
+
+    #[test]
+    fn exact_lookups_send_every_known_detail() {
+        let builder = LyricsRequestBuilder::new("Daft Punk".to_string(), "One & Only".to_string())
+            .album(Some("Discovery".to_string()))
+            .duration(320.6);
+        assert_eq!(
+            url(builder.get_request(&Client::new(), BASE_URL)),
+            "https://lyrics.example/api/get?artist_name=Daft+Punk&track_name=One+%26+Only&album_name=Discovery&duration=321"
+        );
+
+        // Unknown albums and durations are left out rather than sent empty
+        let builder =
+            LyricsRequestBuilder::new("Artist".to_string(), "Song".to_string()).duration(0.0);
+        assert_eq!(
+            url(builder.get_request(&Client::new(), BASE_URL)),
+            "https://lyrics.example/api/get?artist_name=Artist&track_name=Song"
+        );
+    }
+
+    #[test]
+    fn searches_only_send_artist_and_title() {
+        let builder = LyricsRequestBuilder::new("Artist".to_string(), "Song".to_string())
+            .album(Some("Album".to_string()))
+            .duration(200.0);
+        assert_eq!(
+            url(builder.search_request(&Client::new(), BASE_URL)),
+            "https://lyrics.example/api/search?artist_name=Artist&track_name=Song"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::library::lyrics::lyrics_path;
+use crate::models::song::Song;
+use crate::services::lyrics::lyrics_request_builder::LyricsRequestBuilder;
+use crate::utils::tag_tools::track_guesses;
+use std::error::Error;
+use std::fs;
+
+/// Search results whose length differs more than this (in seconds) are a different recording
+const MAX_DURATION_DIFFERENCE: f64 = 3.0;
+
+#[derive(Clone, Default)]
+pub struct LyricsService;
+
+impl LyricsService {
+    pub fn new() -> Self {
+        LyricsService
+    }
+
+    /// Artist and title pairs worth asking for, the provider needs both
+    fn candidates(song: &Song) -> Vec<(String, String)> {
+        track_guesses(song)
+            .into_iter()
+            .filter_map(|(artist, title)| Some((artist?, title)))
+            .collect()
+    }
+
+    /// Fetches the lyrics of a song in LRC format, or as plain text when they are not synced
+    pub async fn fetch_lyrics(&self, song: &Song) -> Result<Option<String>, Box<dyn Error>> {
+        let candidates = Self::candidates(song);
+
+        for (artist, title) in &candidates {
+            let record = LyricsRequestBuilder::new(artist.clone(), title.clone())
+                .album(song.tags.album.clone())
+                .duration(song.duration)
+                .send()
+                .await?;
+
+            if let Some(lyrics) = record.as_ref().and_then(|record| record.best_lyrics()) {
+                return Ok(Some(lyrics.to_string()));
+            }
+        }
+
+        // The exact lookup wants album and duration to match, searching is more forgiving
+        for (artist, title) in candidates {
+            let records = LyricsRequestBuilder::new(artist, title).search().await?;
+            let best = records
+                .iter()
+                .filter(|record| record.best_lyrics().is_some())
+                .filter(|record| {
+                    record.duration.is_none_or(|duration| {
+                        song.duration <= 0.0
+                            || (duration - song.duration).abs() <= MAX_DURATION_DIFFERENCE
+                    })
+                })
+                // Prefer synced lyrics
+                .min_by_key(|record| record.synced_lyrics.is_none());
+
+            if let Some(lyrics) = best.and_then(|record| record.best_lyrics()) {
+                return Ok(Some(lyrics.to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Fetches the lyrics of a song and saves them as its `.lrc` sidecar.
+    /// Returns false when no lyrics were found.
+    pub async fn save_lyrics(&self, song: &Song) -> Result<bool, Box<dyn Error>> {
+        let Some(lyrics) = self.fetch_lyrics(song).await? else {
+            return Ok(false);
+        };
+
+        fs::write(lyrics_path(&song.path), lyrics)?;
+        Ok(true)
+    }
+}
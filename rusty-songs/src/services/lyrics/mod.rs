@@ -0,0 +1,3 @@
+pub mod lyrics_client;
+pub mod lyrics_request_builder;
+pub mod lyrics_service;
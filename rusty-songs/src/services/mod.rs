@@ -1 +1,3 @@
+pub mod lyrics;
+pub mod musicbrainz;
 pub mod youtube;
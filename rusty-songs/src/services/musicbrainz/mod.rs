@@ -0,0 +1,3 @@
+pub mod musicbrainz_client;
+pub mod musicbrainz_request_builder;
+pub mod musicbrainz_service;
@@ -0,0 +1,79 @@
+use reqwest::Client;
+use std::env;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// Public MusicBrainz web service, used unless MUSICBRAINZ_API_URL points somewhere else
+const DEFAULT_MUSICBRAINZ_API_URL: &str = "https://musicbrainz.org/ws/2";
+/// Cover Art Archive, used unless COVER_ART_API_URL points somewhere else
+const DEFAULT_COVER_ART_API_URL: &str = "https://coverartarchive.org";
+/// MusicBrainz allows one request per second, MUSICBRAINZ_RATE_LIMIT_MS can relax this for a mirror
+const DEFAULT_RATE_LIMIT_MS: u64 = 1000;
+
+pub struct MusicBrainzClient {
+    pub base_url: String,
+    pub cover_art_url: String,
+    pub client: Client,
+    /// Minimum time between two requests
+    pub rate_limit: Duration,
+    last_request: Option<Instant>,
+}
+
+impl MusicBrainzClient {
+    fn new(base_url: String, cover_art_url: String, rate_limit: Duration) -> Self {
+        // MusicBrainz blocks clients that don't identify themselves
+        let client = Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION"),
+                " (",
+                env!("CARGO_PKG_REPOSITORY"),
+                ")"
+            ))
+            .build()
+            .unwrap_or_default();
+
+        MusicBrainzClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            cover_art_url: cover_art_url.trim_end_matches('/').to_string(),
+            client,
+            rate_limit,
+            last_request: None,
+        }
+    }
+
+    /// Waits until the rate limit allows the next request.
+    /// Callers hold the lock while waiting, so requests go out one at a time.
+    pub async fn throttle(&mut self) {
+        if let Some(last_request) = self.last_request {
+            sleep_until(last_request + self.rate_limit).await;
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    pub fn get_instance() -> Arc<Mutex<MusicBrainzClient>> {
+        static SINGLETON: OnceLock<Arc<Mutex<MusicBrainzClient>>> = OnceLock::new();
+
+        SINGLETON
+            .get_or_init(|| {
+                let base_url = env::var("MUSICBRAINZ_API_URL")
+                    .unwrap_or_else(|_| DEFAULT_MUSICBRAINZ_API_URL.to_string());
+                let cover_art_url = env::var("COVER_ART_API_URL")
+                    .unwrap_or_else(|_| DEFAULT_COVER_ART_API_URL.to_string());
+                let rate_limit = env::var("MUSICBRAINZ_RATE_LIMIT_MS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_RATE_LIMIT_MS);
+
+                Arc::new(Mutex::new(MusicBrainzClient::new(
+                    base_url,
+                    cover_art_url,
+                    Duration::from_millis(rate_limit),
+                )))
+            })
+            .clone()
+    }
+}
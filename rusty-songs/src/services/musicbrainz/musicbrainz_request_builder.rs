@@ -0,0 +1,68 @@
+use crate::models::musicbrainz::{Recording, RecordingSearch};
+use crate::services::musicbrainz::musicbrainz_client::MusicBrainzClient;
+use std::error::Error;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Searches recordings by title, and optionally by artist
+pub struct MusicBrainzRequestBuilder {
+    title: String,
+    artist: Option<String>,
+    limit: u8,
+    api_client: Arc<Mutex<MusicBrainzClient>>,
+}
+
+/// Quotes a value as a Lucene phrase, the query syntax of the search endpoint
+fn phrase(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl MusicBrainzRequestBuilder {
+    pub fn new(title: String) -> Self {
+        MusicBrainzRequestBuilder {
+            title,
+            artist: None,
+            limit: 5,
+            api_client: MusicBrainzClient::get_instance(),
+        }
+    }
+
+    pub fn artist(mut self, artist: Option<String>) -> Self {
+        self.artist = artist;
+        self
+    }
+
+    pub fn limit(mut self, limit: u8) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Best matches first
+    pub async fn send(self) -> Result<Vec<Recording>, Box<dyn Error>> {
+        let mut query = format!("recording:{}", phrase(&self.title));
+        if let Some(artist) = &self.artist {
+            query.push_str(&format!(" AND artist:{}", phrase(artist)));
+        }
+
+        let (base_url, client) = {
+            let mut api_client = self.api_client.lock().await;
+            api_client.throttle().await;
+            (api_client.base_url.clone(), api_client.client.clone())
+        };
+
+        let search: RecordingSearch = client
+            .get(format!("{}/recording", base_url))
+            .query(&[
+                ("query", query),
+                ("fmt", "json".to_string()),
+                ("limit", self.limit.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(search.recordings)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::models::musicbrainz::Recording;
+use crate::models::song::{Song, TrackTags};
+use crate::services::musicbrainz::musicbrainz_client::MusicBrainzClient;
+use crate::services::musicbrainz::musicbrainz_request_builder::MusicBrainzRequestBuilder;
+use crate::utils::tag_tools::track_guesses;
+use std::error::Error;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Matches scoring lower than this are too uncertain to propose
+const MIN_SCORE: u8 = 80;
+/// Recordings whose length differs more than this (in seconds) are a different version
+const MAX_DURATION_DIFFERENCE: f64 = 5.0;
+
+/// Canonical tags and cover art proposed for a song, for the user to review
+#[derive(Clone)]
+pub struct MetadataProposal {
+    pub song: Song,
+    pub tags: TrackTags,
+    pub cover: Option<Vec<u8>>,
+    /// How sure the service is about the match, from 0 to 100
+    pub score: u8,
+}
+
+impl MetadataProposal {
+    pub fn has_changes(&self) -> bool {
+        self.tags != self.song.tags || self.cover.is_some()
+    }
+}
+
+#[derive(Clone)]
+pub struct MusicBrainzService {
+    api_client: Arc<Mutex<MusicBrainzClient>>,
+}
+
+impl Default for MusicBrainzService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MusicBrainzService {
+    pub fn new() -> Self {
+        MusicBrainzService {
+            api_client: MusicBrainzClient::get_instance(),
+        }
+    }
+
+    /// Looks a song up by its title, artist and length.
+    /// Returns None when nothing matches well enough.
+    pub async fn lookup(&self, song: &Song) -> Result<Option<MetadataProposal>, Box<dyn Error>> {
+        for (artist, title) in track_guesses(song) {
+            let recordings = MusicBrainzRequestBuilder::new(title)
+                .artist(artist)
+                .send()
+                .await?;
+
+            let best = recordings
+                .into_iter()
+                .filter(|recording| recording.score >= MIN_SCORE)
+                .find(|recording| {
+                    recording.duration().is_none_or(|duration| {
+                        song.duration <= 0.0
+                            || (duration - song.duration).abs() <= MAX_DURATION_DIFFERENCE
+                    })
+                });
+
+            if let Some(recording) = best {
+                return Ok(Some(self.propose(song, &recording).await));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn propose(&self, song: &Song, recording: &Recording) -> MetadataProposal {
+        let release = recording.releases.first();
+
+        // Keep what the service doesn't know about, like the genre and the video ID
+        let mut tags = song.tags.clone();
+        tags.title = Some(recording.title.clone());
+        tags.artist = recording.artist().or(tags.artist);
+        if let Some(release) = release {
+            tags.album = Some(release.title.clone());
+            tags.track = release.track_number().or(tags.track);
+        }
+        tags.year = recording.year(release).or(tags.year);
+
+        let cover = match release {
+            Some(release) => self.fetch_cover(&release.id).await,
+            None => None,
+        };
+
+        MetadataProposal {
+            song: song.clone(),
+            tags,
+            cover,
+            score: recording.score,
+        }
+    }
+
+    /// Downloads the front cover of a release, if the cover archive has one
+    pub async fn fetch_cover(&self, release_id: &str) -> Option<Vec<u8>> {
+        let (cover_art_url, client) = {
+            let mut api_client = self.api_client.lock().await;
+            api_client.throttle().await;
+            (api_client.cover_art_url.clone(), api_client.client.clone())
+        };
+
+        let response = client
+            .get(format!(
+                "{}/release/{}/front-500",
+                cover_art_url, release_id
+            ))
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+
+        response.bytes().await.ok().map(|bytes| bytes.to_vec())
+    }
+}
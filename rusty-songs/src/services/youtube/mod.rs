@@ -1,3 +1,4 @@
+pub mod search_filters;
 pub mod youtube_client;
 pub mod youtube_request_builder;
 pub mod youtube_service;
@@ -0,0 +1,374 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// YouTube's category of music videos
+pub const MUSIC_CATEGORY_ID: &str = "10";
+
+/// `videoDuration` of the search API
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoDuration {
+    /// Under four minutes
+    Short,
+    /// Four to twenty minutes
+    Medium,
+    /// Over twenty minutes
+    Long,
+}
+
+impl VideoDuration {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            VideoDuration::Short => "short",
+            VideoDuration::Medium => "medium",
+            VideoDuration::Long => "long",
+        }
+    }
+}
+
+/// `order` of the search API
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchOrder {
+    Relevance,
+    Date,
+    ViewCount,
+    Rating,
+}
+
+impl SearchOrder {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            SearchOrder::Relevance => "relevance",
+            SearchOrder::Date => "date",
+            SearchOrder::ViewCount => "viewCount",
+            SearchOrder::Rating => "rating",
+        }
+    }
+}
+
+/// `safeSearch` of the search API
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SafeSearch {
+    None,
+    Moderate,
+    Strict,
+}
+
+impl SafeSearch {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            SafeSearch::None => "none",
+            SafeSearch::Moderate => "moderate",
+            SafeSearch::Strict => "strict",
+        }
+    }
+}
+
+/// Optional narrowing of a YouTube search, left to the API's defaults when unset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilters {
+    pub video_duration: Option<VideoDuration>,
+    pub order: Option<SearchOrder>,
+    /// RFC 3339 timestamp, e.g. `2024-01-01T00:00:00Z`
+    pub published_after: Option<String>,
+    pub channel_id: Option<String>,
+    pub video_category_id: Option<String>,
+    /// ISO 3166-1 alpha-2 country code, e.g. `US`
+    pub region_code: Option<String>,
+    pub safe_search: Option<SafeSearch>,
+}
+
+impl SearchFilters {
+    pub fn is_empty(&self) -> bool {
+        *self == SearchFilters::default()
+    }
+}
+
+/// Writes the filters back in the inline syntax of `SearchQuery::parse`
+impl fmt::Display for SearchFilters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(duration) = self.video_duration {
+            parts.push(format!("dur:{}", duration.as_param()));
+        }
+        if let Some(order) = self.order {
+            let order = match order {
+                SearchOrder::ViewCount => "views",
+                order => order.as_param(),
+            };
+            parts.push(format!("order:{}", order));
+        }
+        if let Some(published_after) = &self.published_after {
+            parts.push(format!(
+                "after:{}",
+                published_after.get(..10).unwrap_or(published_after)
+            ));
+        }
+        if let Some(channel_id) = &self.channel_id {
+            parts.push(format!("channel:{}", channel_id));
+        }
+        if let Some(category_id) = &self.video_category_id {
+            if category_id == MUSIC_CATEGORY_ID {
+                parts.push("cat:music".to_string());
+            } else {
+                parts.push(format!("cat:{}", category_id));
+            }
+        }
+        if let Some(region_code) = &self.region_code {
+            parts.push(format!("region:{}", region_code));
+        }
+        if let Some(safe_search) = self.safe_search {
+            parts.push(format!("safe:{}", safe_search.as_param()));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// What was typed into the search bar, split into search terms and filters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub filters: SearchFilters,
+}
+
+impl SearchQuery {
+    /// Picks `key:value` filters out of the input, the rest is searched for as typed:
+    ///
+    /// - `dur:short|medium|long`
+    /// - `order:relevance|date|views|rating`
+    /// - `after:2024`, `after:2024-06`, `after:2024-06-01`, or relative as in `after:30d`,
+    ///   `after:2w`, `after:6m` and `after:1y`
+    /// - `channel:UC...`, a channel ID
+    /// - `cat:music` or a category ID like `cat:10`
+    /// - `region:US`
+    /// - `safe:none|moderate|strict`
+    ///
+    /// Words with a colon but another key, e.g. `re:zero`, are search terms.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut filters = SearchFilters::default();
+        let mut words = Vec::new();
+
+        for word in input.split_whitespace() {
+            let Some((key, value)) = word.split_once(':') else {
+                words.push(word);
+                continue;
+            };
+            let invalid = || format!("Invalid search filter {}", word);
+
+            match key.to_lowercase().as_str() {
+                "dur" | "duration" => {
+                    filters.video_duration = Some(match value.to_lowercase().as_str() {
+                        "short" => VideoDuration::Short,
+                        "medium" => VideoDuration::Medium,
+                        "long" => VideoDuration::Long,
+                        _ => return Err(format!("{}, use short, medium or long", invalid())),
+                    })
+                }
+                "order" | "sort" => {
+                    filters.order = Some(match value.to_lowercase().as_str() {
+                        "relevance" => SearchOrder::Relevance,
+                        "date" | "new" | "newest" => SearchOrder::Date,
+                        "views" | "viewcount" => SearchOrder::ViewCount,
+                        "rating" => SearchOrder::Rating,
+                        _ => {
+                            return Err(format!(
+                                "{}, use relevance, date, views or rating",
+                                invalid()
+                            ))
+                        }
+                    })
+                }
+                "after" => {
+                    filters.published_after = Some(
+                        parse_published_after(value)
+                            .ok_or_else(|| format!("{}, use e.g. 2024-06-01 or 30d", invalid()))?,
+                    )
+                }
+                "channel" if !value.is_empty() => filters.channel_id = Some(value.to_string()),
+                "cat" | "category" => {
+                    filters.video_category_id = match value.to_lowercase().as_str() {
+                        "music" => Some(MUSIC_CATEGORY_ID.to_string()),
+                        id if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) => {
+                            Some(id.to_string())
+                        }
+                        _ => return Err(format!("{}, use music or a category ID", invalid())),
+                    }
+                }
+                "region" => {
+                    if value.len() != 2 || !value.chars().all(|c| c.is_ascii_alphabetic()) {
+                        return Err(format!("{}, use a country code like US", invalid()));
+                    }
+                    filters.region_code = Some(value.to_uppercase());
+                }
+                "safe" => {
+                    filters.safe_search = Some(match value.to_lowercase().as_str() {
+                        "none" | "off" => SafeSearch::None,
+                        "moderate" => SafeSearch::Moderate,
+                        "strict" => SafeSearch::Strict,
+                        _ => return Err(format!("{}, use none, moderate or strict", invalid())),
+                    })
+                }
+                _ => words.push(word),
+            }
+        }
+
+        Ok(SearchQuery {
+            text: words.join(" "),
+            filters,
+        })
+    }
+}
+
+/// Turns `2024`, `2024-06`, `2024-06-01` or a period back from today like `30d`, `2w`,
+/// `6m` or `1y` into the RFC 3339 timestamp `publishedAfter` expects
+fn parse_published_after(value: &str) -> Option<String> {
+    if let Some(days) = relative_days(value) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() / 86_400;
+        let (year, month, day) = civil_from_days(now.checked_sub(days)? as i64);
+        return Some(format!("{:04}-{:02}-{:02}T00:00:00Z", year, month, day));
+    }
+
+    let mut parts = value.split('-');
+    let year: u32 = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    let month: u32 = parts.next().map_or(Some(1), |month| month.parse().ok())?;
+    let day: u32 = parts.next().map_or(Some(1), |day| day.parse().ok())?;
+    if parts.next().is_some()
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}T00:00:00Z", year, month, day))
+}
+
+fn relative_days(value: &str) -> Option<u64> {
+    let unit = value.chars().last()?;
+    let count: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let days = match unit.to_ascii_lowercase() {
+        'd' => 1,
+        'w' => 7,
+        'm' => 30,
+        'y' => 365,
+        _ => return None,
+    };
+    count.checked_mul(days)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Year, month and day of a count of days since 1970-01-01, after Howard Hinnant's algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_filters_out_of_the_search_terms() {
+        let query =
+            SearchQuery::parse("re:zero  OST dur:LONG order:views cat:music region:jp safe:off")
+                .unwrap();
+        assert_eq!(query.text, "re:zero OST");
+        assert_eq!(
+            query.filters,
+            SearchFilters {
+                video_duration: Some(VideoDuration::Long),
+                order: Some(SearchOrder::ViewCount),
+                video_category_id: Some(MUSIC_CATEGORY_ID.to_string()),
+                region_code: Some("JP".to_string()),
+                safe_search: Some(SafeSearch::None),
+                ..SearchFilters::default()
+            }
+        );
+        assert_eq!(
+            query.filters.to_string(),
+            "dur:long order:views cat:music region:JP safe:none"
+        );
+
+        let query = SearchQuery::parse("live after:2024-06 channel:UC123").unwrap();
+        assert_eq!(query.text, "live");
+        assert_eq!(
+            query.filters.published_after.as_deref(),
+            Some("2024-06-01T00:00:00Z")
+        );
+        assert_eq!(query.filters.channel_id.as_deref(), Some("UC123"));
+        assert!(SearchQuery::parse("just words").unwrap().filters.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_filters() {
+        for input in [
+            "dur:forever",
+            "order:random",
+            "after:yesterday",
+            "cat:films",
+            "region:USA",
+            "safe:maybe",
+        ] {
+            assert!(SearchQuery::parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parses_dates_of_uploads() {
+        assert_eq!(
+            parse_published_after("2024").as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            parse_published_after("2024-02-29").as_deref(),
+            Some("2024-02-29T00:00:00Z")
+        );
+        for invalid in [
+            "24",
+            "2023-02-29",
+            "2024-02-30",
+            "2024-04-31",
+            "2024-13",
+            "2024-1-1-1",
+        ] {
+            assert_eq!(parse_published_after(invalid), None, "{}", invalid);
+        }
+        assert_eq!(parse_published_after("1900-02-29"), None);
+        assert!(parse_published_after("2000-02-29").is_some());
+    }
+
+    #[test]
+    fn parses_periods_back_from_today() {
+        assert_eq!(relative_days("30d"), Some(30));
+        assert_eq!(relative_days("2W"), Some(14));
+        assert_eq!(relative_days("6m"), Some(180));
+        assert_eq!(relative_days("1y"), Some(365));
+        assert_eq!(relative_days("5x"), None);
+        assert_eq!(relative_days("99999999999999999y"), None);
+        assert_eq!(parse_published_after("99999999999999999d"), None);
+        assert!(parse_published_after("1w").is_some());
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(20_089), (2025, 1, 1));
+    }
+}
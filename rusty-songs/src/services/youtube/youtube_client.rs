@@ -1,6 +1,6 @@
 use reqwest::Client;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::Mutex;
 
 pub struct YoutubeClient {
@@ -17,15 +17,13 @@ impl YoutubeClient {
     }
 
     pub fn get_instance() -> Arc<Mutex<YoutubeClient>> {
-        static mut SINGLETON: Option<Arc<Mutex<YoutubeClient>>> = None;
+        static SINGLETON: OnceLock<Arc<Mutex<YoutubeClient>>> = OnceLock::new();
 
-        unsafe {
-            SINGLETON
-                .get_or_insert_with(|| {
-                    let api_key = env::var("YOUTUBE_API_KEY").expect("YOUTUBE_API_KEY must be set");
-                    Arc::new(Mutex::new(YoutubeClient::new(api_key)))
-                })
-                .clone()
-        }
+        SINGLETON
+            .get_or_init(|| {
+                let api_key = env::var("YOUTUBE_API_KEY").expect("YOUTUBE_API_KEY must be set");
+                Arc::new(Mutex::new(YoutubeClient::new(api_key)))
+            })
+            .clone()
     }
 }
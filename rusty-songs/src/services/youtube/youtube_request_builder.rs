@@ -1,13 +1,45 @@
-use crate::models::video::{SearchResponse, Video};
+use crate::models::video::{ApiErrorResponse, SearchResponse, VideoDetails, VideoListResponse};
+use crate::services::youtube::search_filters::{
+    SafeSearch, SearchFilters, SearchOrder, VideoDuration,
+};
 use crate::services::youtube::youtube_client::YoutubeClient;
+use crate::utils::file_names::decode_entities;
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
 use std::env;
 use std::error::Error;
+use std::io;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// What went wrong according to the API, instead of a failure to parse its error as a result
+fn api_error(status: StatusCode, body: &str) -> String {
+    match serde_json::from_str::<ApiErrorResponse>(body) {
+        Ok(response) => format!(
+            "YouTube API error {}: {}",
+            status.as_u16(),
+            response.error.message
+        ),
+        Err(_) => format!("YouTube API error {}", status),
+    }
+}
+
+/// Deserializes the body of a successful response, or returns the API's error
+async fn read_response<T: DeserializeOwned>(response: Response) -> Result<T, Box<dyn Error>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(Box::new(io::Error::other(api_error(status, &body))))
+}
+
 pub struct YoutubeRequestBuilder {
     query: String,
     max_results: u8,
+    page_token: Option<String>,
+    filters: SearchFilters,
     api_client: Arc<Mutex<YoutubeClient>>,
 }
 
@@ -16,6 +48,8 @@ impl YoutubeRequestBuilder {
         YoutubeRequestBuilder {
             query,
             max_results: 10,
+            page_token: None,
+            filters: SearchFilters::default(),
             api_client: YoutubeClient::get_instance(),
         }
     }
@@ -25,18 +59,175 @@ impl YoutubeRequestBuilder {
         self
     }
 
-    pub async fn send(self) -> Result<Vec<Video>, Box<dyn Error>> {
-        let url = format!(
-            "{}?part=snippet&q={}&maxResults={}&type=video&key={}",
-            env::var("YOUTUBE_API_URL").expect("YOUTUBE_API_URL must be set"),
-            self.query,
-            self.max_results,
-            self.api_client.lock().await.api_key
-        );
+    /// Continues a search from the `next_page_token` of a previous response
+    pub fn page_token(mut self, page_token: Option<String>) -> Self {
+        self.page_token = page_token;
+        self
+    }
 
-        let client = &self.api_client.lock().await.client;
-        let response: SearchResponse = client.get(&url).send().await?.json().await?;
+    /// Sets all filters at once, e.g. those typed into the search bar
+    pub fn filters(mut self, filters: SearchFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    pub fn video_duration(mut self, video_duration: VideoDuration) -> Self {
+        self.filters.video_duration = Some(video_duration);
+        self
+    }
+
+    pub fn order(mut self, order: SearchOrder) -> Self {
+        self.filters.order = Some(order);
+        self
+    }
+
+    /// Only videos uploaded after an RFC 3339 timestamp, e.g. `2024-01-01T00:00:00Z`
+    pub fn published_after(mut self, published_after: String) -> Self {
+        self.filters.published_after = Some(published_after);
+        self
+    }
+
+    pub fn channel_id(mut self, channel_id: String) -> Self {
+        self.filters.channel_id = Some(channel_id);
+        self
+    }
+
+    /// Only videos of a category, e.g. `MUSIC_CATEGORY_ID`
+    pub fn video_category_id(mut self, video_category_id: String) -> Self {
+        self.filters.video_category_id = Some(video_category_id);
+        self
+    }
+
+    pub fn region_code(mut self, region_code: String) -> Self {
+        self.filters.region_code = Some(region_code);
+        self
+    }
+
+    pub fn safe_search(mut self, safe_search: SafeSearch) -> Self {
+        self.filters.safe_search = Some(safe_search);
+        self
+    }
+
+    pub async fn send(self) -> Result<SearchResponse, Box<dyn Error>> {
+        let url = env::var("YOUTUBE_API_URL").expect("YOUTUBE_API_URL must be set");
+        let (client, api_key) = {
+            let api_client = self.api_client.lock().await;
+            (api_client.client.clone(), api_client.api_key.clone())
+        };
+
+        let mut params = vec![
+            ("part", "snippet".to_string()),
+            ("q", self.query.clone()),
+            ("maxResults", self.max_results.to_string()),
+            ("type", "video".to_string()),
+            ("key", api_key),
+        ];
+        if let Some(page_token) = self.page_token {
+            params.push(("pageToken", page_token));
+        }
+
+        let filters = self.filters;
+        if let Some(video_duration) = filters.video_duration {
+            params.push(("videoDuration", video_duration.as_param().to_string()));
+        }
+        if let Some(order) = filters.order {
+            params.push(("order", order.as_param().to_string()));
+        }
+        if let Some(published_after) = filters.published_after {
+            params.push(("publishedAfter", published_after));
+        }
+        if let Some(channel_id) = filters.channel_id {
+            params.push(("channelId", channel_id));
+        }
+        if let Some(video_category_id) = filters.video_category_id {
+            params.push(("videoCategoryId", video_category_id));
+        }
+        if let Some(region_code) = filters.region_code {
+            params.push(("regionCode", region_code));
+        }
+        if let Some(safe_search) = filters.safe_search {
+            params.push(("safeSearch", safe_search.as_param().to_string()));
+        }
+
+        let response = client.get(&url).query(&params).send().await?;
+        let mut response: SearchResponse = read_response(response).await?;
+
+        // Titles come HTML escaped, e.g. "Simon &amp; Garfunkel"
+        for video in response.items.iter_mut() {
+            video.snippet.title = decode_entities(&video.snippet.title);
+            video.snippet.channel_title = decode_entities(&video.snippet.channel_title);
+            video.snippet.description = decode_entities(&video.snippet.description);
+        }
+
+        Ok(response)
+    }
+}
+
+/// Looks up duration, view count and the like for videos found by a search
+pub struct VideoDetailsRequestBuilder {
+    video_ids: Vec<String>,
+    api_client: Arc<Mutex<YoutubeClient>>,
+}
+
+impl VideoDetailsRequestBuilder {
+    /// videos.list takes at most 50 IDs at a time, a search page never has more
+    pub fn new(video_ids: Vec<String>) -> Self {
+        VideoDetailsRequestBuilder {
+            video_ids,
+            api_client: YoutubeClient::get_instance(),
+        }
+    }
+
+    /// YOUTUBE_VIDEOS_API_URL, by default the videos endpoint next to the search endpoint
+    fn url() -> String {
+        env::var("YOUTUBE_VIDEOS_API_URL").unwrap_or_else(|_| {
+            let search_url = env::var("YOUTUBE_API_URL").expect("YOUTUBE_API_URL must be set");
+            match search_url.trim_end_matches('/').rsplit_once('/') {
+                Some((base, _)) => format!("{}/videos", base),
+                None => search_url,
+            }
+        })
+    }
+
+    pub async fn send(self) -> Result<Vec<VideoDetails>, Box<dyn Error>> {
+        if self.video_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (client, api_key) = {
+            let api_client = self.api_client.lock().await;
+            (api_client.client.clone(), api_client.api_key.clone())
+        };
+
+        let response = client
+            .get(Self::url())
+            .query(&[
+                ("part", "contentDetails,statistics".to_string()),
+                ("id", self.video_ids.join(",")),
+                ("key", api_key),
+            ])
+            .send()
+            .await?;
+        let response: VideoListResponse = read_response(response).await?;
 
         Ok(response.items)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_the_message_of_api_errors() {
+        let body = r#"{"error": {"code": 403, "message": "The request cannot be completed because you have exceeded your quota.", "errors": []}}"#;
+        assert_eq!(
+            api_error(StatusCode::FORBIDDEN, body),
+            "YouTube API error 403: The request cannot be completed because you have exceeded your quota."
+        );
+        assert_eq!(
+            api_error(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>"),
+            "YouTube API error 502 Bad Gateway"
+        );
+    }
+}
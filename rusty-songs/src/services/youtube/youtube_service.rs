@@ -1,11 +1,18 @@
-use crate::models::video::Video;
+use crate::library::index::LibraryIndex;
+use crate::library::naming::NamingTemplate;
+use crate::models::song::{Song, TrackTags};
+use crate::models::video::{SearchResponse, Snippet, Thumbnail, Thumbnails, Video};
+use crate::services::youtube::search_filters::SearchQuery;
 use crate::services::youtube::youtube_client::YoutubeClient;
-use crate::services::youtube::youtube_request_builder::YoutubeRequestBuilder;
+use crate::services::youtube::youtube_request_builder::{
+    VideoDetailsRequestBuilder, YoutubeRequestBuilder,
+};
+use crate::utils::file_names::unique_path;
+use crate::utils::tag_tools::write_youtube_tags;
 use crate::utils::video_tools::{compress_mp3, download_video_as_mp3};
-use std::env;
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -14,6 +21,12 @@ pub struct YoutubeService {
     api_client: Arc<Mutex<YoutubeClient>>,
 }
 
+impl Default for YoutubeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl YoutubeService {
     pub fn new() -> Self {
         YoutubeService {
@@ -21,49 +34,178 @@ impl YoutubeService {
         }
     }
 
+    /// Fetches one page of results, the first one without a `page_token`
     pub async fn search_videos(
         &self,
-        query: &str,
+        query: &SearchQuery,
         max_results: u8,
-    ) -> Result<Vec<Video>, Box<dyn Error>> {
-        let builder = YoutubeRequestBuilder::new(query.to_string()).max_results(max_results);
+        page_token: Option<String>,
+    ) -> Result<SearchResponse, Box<dyn Error>> {
+        let builder = YoutubeRequestBuilder::new(query.text.clone())
+            .max_results(max_results)
+            .page_token(page_token)
+            .filters(query.filters.clone());
+
+        let mut page = builder.send().await?;
+        self.add_details(&mut page.items).await;
+        Ok(page)
+    }
+
+    /// Attaches duration and view counts through videos.list. Search results are still
+    /// useful without them, so a failed lookup leaves them out.
+    async fn add_details(&self, videos: &mut [Video]) {
+        let video_ids = videos
+            .iter()
+            .filter_map(|video| video.id.video_id.clone())
+            .collect();
+        let Ok(details) = VideoDetailsRequestBuilder::new(video_ids).send().await else {
+            return;
+        };
+
+        for video in videos.iter_mut() {
+            video.details = details
+                .iter()
+                .find(|details| Some(&details.id) == video.id.video_id.as_ref())
+                .cloned();
+        }
+    }
 
-        let videos = builder.send().await?;
-        Ok(videos)
+    /// Where the naming template puts a video below MUSIC_DIR
+    fn template_path(video_id: &str, snippet: &Snippet) -> PathBuf {
+        let tags = TrackTags {
+            title: Some(snippet.title.clone()),
+            artist: Some(snippet.channel_title.clone()),
+            year: snippet
+                .published_at
+                .as_deref()
+                .and_then(|date| date.get(..4))
+                .and_then(|year| year.parse().ok()),
+            video_id: Some(video_id.to_string()),
+            ..TrackTags::default()
+        };
+        LibraryIndex::music_dir().join(NamingTemplate::from_env().render(&tags, "mp3"))
+    }
+
+    /// Where a video ends up below MUSIC_DIR, following the naming template.
+    /// An existing file of the same name is never overwritten, the new one gets a suffix.
+    pub fn download_path(video_id: &str, snippet: &Snippet) -> PathBuf {
+        unique_path(&Self::template_path(video_id, snippet), |candidate| {
+            candidate.exists()
+        })
+    }
+
+    /// The library song downloaded from a video, found by its video ID. Downloads from before
+    /// the ID was tagged are recognised by their file name, see `untagged_download`.
+    pub fn downloaded_song<'a>(
+        library: &'a LibraryIndex,
+        video_id: &str,
+        snippet: &Snippet,
+    ) -> Option<&'a Song> {
+        library
+            .song_for_video(video_id)
+            .or_else(|| Self::untagged_download(library, video_id, snippet))
+    }
+
+    /// A song without a video ID at the path the video would be downloaded to
+    pub fn untagged_download<'a>(
+        library: &'a LibraryIndex,
+        video_id: &str,
+        snippet: &Snippet,
+    ) -> Option<&'a Song> {
+        library
+            .song(&Self::template_path(video_id, snippet))
+            .filter(|song| song.tags.video_id.is_none())
     }
 
+    /// Downloads, compresses and tags a video, returning the path of the new file
     pub async fn process_video_to_audio(
         &self,
         video_id: &str,
-        video_title: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        let music_dir = env::var("MUSIC_DIR").unwrap_or_else(|_| "music".to_string());
+        snippet: &Snippet,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let path = Self::download_path(video_id, snippet);
+        self.download_to(video_id, snippet, &path).await?;
+        Ok(path)
+    }
 
+    async fn download_to(
+        &self,
+        video_id: &str,
+        snippet: &Snippet,
+        original_path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
         // Step 1: Download the video and save as MP3
-        download_video_as_mp3(video_id, video_title).await?;
+        download_video_as_mp3(video_id, original_path).await?;
 
-        // Define the file paths within MUSIC_DIR
-        let original_file_name = format!("{}.mp3", video_title);
-        let temp_file_name = format!("{}.temp.mp3", video_title); // Temporary file for compression
-
-        let original_path = PathBuf::from(&music_dir).join(&original_file_name);
-        let temp_path = PathBuf::from(&music_dir).join(&temp_file_name);
+        // Temporary file for compression, next to the download
+        let stem = original_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let temp_path = original_path.with_file_name(format!("{}.temp.mp3", stem));
 
         // Step 2: Compress the MP3 to a temporary file
         compress_mp3(original_path.to_str().unwrap(), temp_path.to_str().unwrap()).await?;
 
         // Step 3: Delete the original file
-        if let Err(e) = fs::remove_file(&original_path) {
+        if let Err(e) = fs::remove_file(original_path) {
             eprintln!("Failed to remove original file: {}", e);
             return Err(Box::new(e));
         }
 
         // Step 4: Rename the temporary file to the original name
-        if let Err(e) = fs::rename(&temp_path, &original_path) {
+        if let Err(e) = fs::rename(&temp_path, original_path) {
             eprintln!("Failed to rename temp file to original: {}", e);
             return Err(Box::new(e));
         }
 
+        // Step 5: Tag the final file so it can be traced back to its source video
+        let cover = self.fetch_thumbnail(snippet).await;
+        write_youtube_tags(original_path, video_id, snippet, cover)?;
+
         Ok(())
     }
+
+    /// Downloads a video again, e.g. to replace a broken file.
+    /// The new file takes the place of the old one, as an MP3.
+    pub async fn redownload(
+        &self,
+        video_id: &str,
+        path: &Path,
+        title: &str,
+        channel: &str,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        // Only the video ID is known here, YouTube serves its thumbnails under predictable URLs
+        let snippet = Snippet {
+            title: title.to_string(),
+            description: String::new(),
+            channel_title: channel.to_string(),
+            published_at: None,
+            live_broadcast_content: None,
+            thumbnails: Some(Thumbnails {
+                default: None,
+                medium: None,
+                high: Some(Thumbnail {
+                    url: format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", video_id),
+                }),
+            }),
+        };
+
+        let path = path.with_extension("mp3");
+        self.download_to(video_id, &snippet, &path).await?;
+        Ok(path)
+    }
+
+    /// Downloads the best available thumbnail, which is used as cover art
+    pub async fn fetch_thumbnail(&self, snippet: &Snippet) -> Option<Vec<u8>> {
+        let url = snippet.thumbnails.as_ref()?.best()?.url.clone();
+        let client = self.api_client.lock().await.client.clone();
+
+        let response = client.get(&url).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+
+        response.bytes().await.ok().map(|bytes| bytes.to_vec())
+    }
 }
@@ -1,17 +1,47 @@
+use crate::library::config::LibraryConfig;
+use crate::library::duplicates::{content_hash, find_duplicates, fingerprint, merge_duplicates};
+use crate::library::index::{walk_audio_files, IndexEntry, LibraryIndex, ScanResult};
+use crate::library::lyrics::{load_lyrics, lyrics_path};
+use crate::library::smart_playlist::{Query, SmartPlaylist};
+use crate::library::sort::{GroupBy, SortKey};
+use crate::library::stats::LibraryStats;
+use crate::library::verify::{verify_file, VerifyIssue};
+use crate::library::watcher::LibraryWatcher;
+use crate::models::song::{Song, SongStats, MAX_RATING};
 use crate::models::video::Video;
+use crate::player::audio_player::AudioPlayer;
+use crate::services::lyrics::lyrics_service::LyricsService;
+use crate::services::musicbrainz::musicbrainz_service::{MetadataProposal, MusicBrainzService};
+use crate::services::youtube::search_filters::SearchQuery;
 use crate::services::youtube::youtube_service::YoutubeService;
+use crate::tui::ui::album_art::AlbumArt;
 use crate::tui::ui::builder::LayoutBuilder;
+use crate::tui::ui::duplicates::DuplicatesReview;
+use crate::tui::ui::file_browser::FileBrowser;
+use crate::tui::ui::list_filter::ListFilter;
+use crate::tui::ui::lyrics::LyricsView;
+use crate::tui::ui::metadata_review::MetadataReview;
 use crate::tui::ui::notification::{Notification, NotificationType};
 use crate::tui::ui::playback::Playback;
 use crate::tui::ui::playlist::Playlist;
+use crate::tui::ui::prompt::{Prompt, PromptAction};
 use crate::tui::ui::queue::Queue;
 use crate::tui::ui::search_bar::SearchBar;
+use crate::tui::ui::stats_view::StatsView;
+use crate::tui::ui::tag_editor::TagEditor;
+use crate::tui::ui::verify_report::VerifyReport;
+use crate::utils::settings::Settings;
+use crate::utils::tag_tools::{read_tags, write_cover, write_tags};
 use crossterm::event::{self, poll, Event, KeyCode};
 use crossterm::execute;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use std::env;
+use std::error::Error;
+use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
@@ -22,45 +52,869 @@ pub enum Pane {
     Playlist,
     Queue,
     Playback,
+    Lyrics,
     SearchResults,
+    Duplicates,
+    MetadataReview,
+    VerifyReport,
+    Stats,
+    Browser,
 }
 
-#[derive(Clone)]
 pub struct App {
     search_bar: SearchBar,
     playlist: Playlist,
     queue: Queue,
     playback: Playback,
+    lyrics: LyricsView,
+    /// Song the lyrics were loaded for
+    lyrics_song: Option<PathBuf>,
+    album_art: AlbumArt,
     selected_pane: Pane,
     youtube_service: YoutubeService,
+    lyrics_service: LyricsService,
+    fetching_lyrics: bool,
+    musicbrainz_service: MusicBrainzService,
+    looking_up_metadata: bool,
+    verifying_library: bool,
     search_results: Option<Vec<Video>>,
-    selected_video: Option<Video>,
+    /// What the shown search results were found for
+    search_query: SearchQuery,
+    /// Token of the following page of search results, None after the last page
+    next_search_page: Option<String>,
+    /// YouTube's estimate of how many videos match the search
+    total_search_results: Option<u64>,
+    loading_search_page: bool,
     selected_search_index: usize,
     selected_playlist_song_index: usize,
     notification: Option<Notification>,
     notification_timeout: Duration,
     downloading_video_index: Option<usize>,
     selected_queue_song_index: usize,
+    library: LibraryIndex,
+    player: AudioPlayer,
+    playing_queue_index: Option<usize>,
+    duplicates: Option<DuplicatesReview>,
+    metadata_review: Option<MetadataReview>,
+    verify_report: Option<VerifyReport>,
+    stats_view: Option<StatsView>,
+    browser: FileBrowser,
+    filter: Option<ListFilter>,
+    prompt: Option<Prompt>,
+    tag_editor: Option<TagEditor>,
+    /// Index into the saved smart playlists of the one shown in the Playlist pane
+    selected_smart_playlist: Option<usize>,
+    settings: Settings,
+}
+
+/// Service errors are not Send, so they are turned into text before the task awaits
+/// anything else, e.g. the app lock
+fn err_text<T>(result: Result<T, Box<dyn Error>>) -> Result<T, String> {
+    result.map_err(|e| e.to_string())
+}
+
+/// Types a key into the search bar while it has focus. Returns false for keys that are not
+/// text, which are left to the shortcuts.
+fn search_bar_input(pane: &Pane, search_bar: &mut SearchBar, code: KeyCode) -> bool {
+    if !matches!(pane, Pane::SearchBar) {
+        return false;
+    }
+    match code {
+        KeyCode::Char(c) => search_bar.update(c),
+        KeyCode::Backspace => search_bar.delete(),
+        _ => return false,
+    }
+    true
+}
+
+/// How often settled filesystem events are picked up from the library watcher
+const LIBRARY_WATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How much one key press moves the lyrics timing
+const LYRICS_OFFSET_STEP_MS: i64 = 100;
+
+/// How often changes to the library index are written to disk
+const LIBRARY_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Polling interval, only used when the music directory cannot be watched
+const LIBRARY_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Search results fetched at a time, more load when scrolling past the last one
+const SEARCH_PAGE_SIZE: u8 = 10;
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        // Start from the cached index so the playlist shows up before any rescan
+        let library = LibraryIndex::load();
+        let settings = Settings::load();
+        let mut playlist = Playlist::new();
+        playlist.show_stats = settings.show_stats;
+        let mut queue = Queue::new(vec![]);
+        queue.show_stats = settings.show_stats;
+        playlist.set_view(
+            settings.playlist_sort,
+            settings.playlist_sort_descending,
+            settings.playlist_group_by,
+        );
+        playlist.load_playlist(&library);
+
         App {
             search_bar: SearchBar::new(),
-            playlist: Playlist::new(),
-            queue: Queue::new(vec![]),
+            playlist,
+            queue,
             youtube_service: YoutubeService::new(),
+            lyrics_service: LyricsService::new(),
+            fetching_lyrics: false,
+            musicbrainz_service: MusicBrainzService::new(),
+            looking_up_metadata: false,
+            verifying_library: false,
             search_results: None,
-            playback: Playback::new("Song 1", 100, 300),
+            search_query: SearchQuery::default(),
+            next_search_page: None,
+            total_search_results: None,
+            loading_search_page: false,
+            playback: Playback::new("", 0, 0),
+            lyrics: LyricsView {
+                offset_ms: settings.lyrics_offset_ms,
+                ..LyricsView::new()
+            },
+            lyrics_song: None,
+            album_art: AlbumArt::new(),
             selected_pane: Pane::SearchBar, // Default to the search bar
 
-            selected_video: None,
             selected_search_index: 0,
             selected_playlist_song_index: 0,
             notification: None,
             notification_timeout: Duration::from_secs(5),
             downloading_video_index: None,
             selected_queue_song_index: 0,
+            library,
+            player: AudioPlayer::new(),
+            playing_queue_index: None,
+            duplicates: None,
+            metadata_review: None,
+            verify_report: None,
+            stats_view: None,
+            browser: FileBrowser::new(LibraryConfig::from_env().roots),
+            filter: None,
+            prompt: None,
+            tag_editor: None,
+            selected_smart_playlist: None,
+            settings,
+        }
+    }
+
+    fn play_song(&mut self, song: Song, queue_index: Option<usize>) {
+        self.record_interrupted_song();
+        let title = song.title.clone();
+        match self.player.play(song) {
+            Ok(()) => self.playing_queue_index = queue_index,
+            Err(e) => self.set_notification(
+                format!("Failed to play {}: {}", title, e),
+                NotificationType::Error,
+            ),
+        }
+    }
+
+    fn stop_playback(&mut self) {
+        self.record_interrupted_song();
+        self.player.stop();
+        self.playing_queue_index = None;
+    }
+
+    /// Counts the song that is about to be stopped as played when most of it was heard,
+    /// as skipped otherwise
+    fn record_interrupted_song(&mut self) {
+        let Some(song) = self.player.current_song() else {
+            return;
+        };
+        let position = self.player.position();
+        let played = song.duration <= 0.0 || position >= song.duration / 2.0;
+        let path = song.path.clone();
+        self.record_listen(&path, played, position);
+    }
+
+    fn record_listen(&mut self, path: &Path, played: bool, seconds: f64) {
+        self.library.record_listen(seconds);
+        self.update_song_stats(path, |stats| {
+            if played {
+                stats.play_count += 1;
+                stats.last_played = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .ok();
+            } else {
+                stats.skip_count += 1;
+            }
+        });
+    }
+
+    /// Updates the statistics of a song in the library and in every pane showing it
+    fn update_song_stats(&mut self, path: &Path, update: impl FnOnce(&mut SongStats)) {
+        if self.library.update_stats(path, update).is_none() {
+            return;
+        }
+
+        self.refresh_queue_songs();
+        self.reload_playlist();
+    }
+
+    /// Picks up changed tags and statistics for the songs in the queue
+    fn refresh_queue_songs(&mut self) {
+        for song in self.queue.songs.iter_mut() {
+            if let Some(updated) = self.library.song(&song.path) {
+                *song = updated.clone();
+            }
+        }
+    }
+
+    /// The marked songs when picking from the Playlist, or else the selected song
+    fn songs_to_edit(&self) -> Vec<Song> {
+        let marked = self.playlist.marked_songs();
+        if !marked.is_empty() && self.selected_pane == Pane::Playlist {
+            marked
+        } else {
+            self.selected_song().cloned().into_iter().collect()
+        }
+    }
+
+    /// Opens the tag editor on the marked songs, or on the selected one
+    fn open_tag_editor(&mut self) {
+        let songs = self.songs_to_edit();
+        if !songs.is_empty() {
+            self.tag_editor = Some(TagEditor::new(songs));
+        }
+    }
+
+    /// Writes the edited tags without holding the app lock, then re-indexes the songs
+    async fn save_tag_editor(app: Arc<Mutex<App>>) {
+        let changes = {
+            let mut app_locked = app.lock().await;
+            let Some(editor) = app_locked.tag_editor.as_ref() else {
+                return;
+            };
+            match editor.changes() {
+                Ok(changes) => {
+                    app_locked.tag_editor = None;
+                    changes
+                }
+                Err(e) => {
+                    app_locked.set_notification(e, NotificationType::Error);
+                    return;
+                }
+            }
+        };
+        if changes.is_empty() {
+            return;
+        }
+
+        let results = tokio::task::spawn_blocking(move || {
+            changes
+                .into_iter()
+                .map(|(song, tags)| {
+                    let result = write_tags(&song.path, &tags).map_err(|e| e.to_string());
+                    (song, result)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut written = Vec::new();
+        let mut failures = Vec::new();
+        for (song, result) in results {
+            match result {
+                Ok(()) => written.push(song.path),
+                Err(e) => failures.push(format!("{}: {}", song.title, e)),
+            }
+        }
+
+        {
+            let mut app_locked = app.lock().await;
+            if failures.is_empty() {
+                app_locked.playlist.marked.clear();
+                app_locked.set_notification(
+                    format!("Updated the tags of {} songs", written.len()),
+                    NotificationType::Success,
+                );
+            } else {
+                app_locked.set_notification(
+                    format!("Failed to write tags: {}", failures.join(", ")),
+                    NotificationType::Error,
+                );
+            }
+        }
+
+        // The watcher would notice as well, this just updates the panes right away
+        App::apply_library_changes(
+            &app,
+            ScanResult {
+                changed: written,
+                removed: Vec::new(),
+            },
+        )
+        .await;
+    }
+
+    /// Looks the songs up one at a time and collects the proposals in the metadata review,
+    /// which opens with the first one. Closing the review stops the lookup.
+    async fn lookup_metadata(app: Arc<Mutex<App>>, songs: Vec<Song>) {
+        let service = {
+            let mut app_locked = app.lock().await;
+            if app_locked.looking_up_metadata {
+                return;
+            }
+            app_locked.looking_up_metadata = true;
+            app_locked.musicbrainz_service.clone()
+        };
+
+        let total = songs.len();
+        let mut proposed = 0;
+        let mut failed = 0;
+        for (i, song) in songs.iter().enumerate() {
+            {
+                let mut app_locked = app.lock().await;
+                if proposed > 0 && app_locked.metadata_review.is_none() {
+                    break;
+                }
+                app_locked.set_notification(
+                    format!("Looking up {}/{}: {}", i + 1, total, song.title),
+                    NotificationType::Info,
+                );
+            }
+
+            let result = err_text(service.lookup(song).await);
+            let mut app_locked = app.lock().await;
+            match result {
+                Ok(Some(proposal)) if proposal.has_changes() => {
+                    if proposed == 0 {
+                        app_locked.metadata_review = Some(MetadataReview::new());
+                        app_locked.selected_pane = Pane::MetadataReview;
+                    }
+                    if let Some(review) = app_locked.metadata_review.as_mut() {
+                        review.push(proposal);
+                    }
+                    proposed += 1;
+                }
+                Ok(_) => {}
+                Err(_) => failed += 1,
+            }
+        }
+
+        let mut app_locked = app.lock().await;
+        app_locked.looking_up_metadata = false;
+        app_locked.set_notification(
+            format!(
+                "Found better metadata for {} of {} songs ({} lookups failed)",
+                proposed, total, failed
+            ),
+            if failed > 0 {
+                NotificationType::Error
+            } else {
+                NotificationType::Info
+            },
+        );
+    }
+
+    /// Writes accepted proposals without holding the app lock, then re-indexes the songs
+    async fn accept_metadata(app: Arc<Mutex<App>>, proposals: Vec<MetadataProposal>) {
+        if proposals.is_empty() {
+            return;
+        }
+
+        let results = tokio::task::spawn_blocking(move || {
+            proposals
+                .into_iter()
+                .map(|proposal| {
+                    let path = &proposal.song.path;
+                    let mut result = Ok(());
+                    if proposal.tags != proposal.song.tags {
+                        result = write_tags(path, &proposal.tags);
+                    }
+                    if let (Ok(()), Some(cover)) = (&result, &proposal.cover) {
+                        result = write_cover(path, cover);
+                    }
+                    (proposal.song, result.map_err(|e| e.to_string()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut written = Vec::new();
+        let mut failures = Vec::new();
+        for (song, result) in results {
+            match result {
+                Ok(()) => written.push(song.path),
+                Err(e) => failures.push(format!("{}: {}", song.title, e)),
+            }
+        }
+
+        {
+            let mut app_locked = app.lock().await;
+            if failures.is_empty() {
+                app_locked.set_notification(
+                    format!("Updated the metadata of {} songs", written.len()),
+                    NotificationType::Success,
+                );
+            } else {
+                app_locked.set_notification(
+                    format!("Failed to write metadata: {}", failures.join(", ")),
+                    NotificationType::Error,
+                );
+            }
+            // The cover of the playing song may have changed
+            app_locked.album_art.set_song(None);
+        }
+
+        App::apply_library_changes(
+            &app,
+            ScanResult {
+                changed: written,
+                removed: Vec::new(),
+            },
+        )
+        .await;
+    }
+
+    fn close_metadata_review_if_done(&mut self) {
+        if self
+            .metadata_review
+            .as_ref()
+            .is_some_and(|review| review.is_empty())
+        {
+            self.metadata_review = None;
+            self.selected_pane = Pane::Playlist;
+        }
+    }
+
+    /// Song selected in the Playlist or Queue pane
+    fn selected_song(&self) -> Option<&Song> {
+        match self.selected_pane {
+            Pane::Playlist => self.playlist.song_at(self.selected_playlist_song_index),
+            Pane::Queue => self.queue.songs.get(self.selected_queue_song_index),
+            _ => None,
+        }
+    }
+
+    fn update_selected_song_stats(&mut self, update: impl FnOnce(&mut SongStats)) {
+        if let Some(path) = self.selected_song().map(|song| song.path.clone()) {
+            self.update_song_stats(&path, update);
+        }
+    }
+
+    fn toggle_stats_columns(&mut self) {
+        self.settings.show_stats = !self.settings.show_stats;
+        self.playlist.show_stats = self.settings.show_stats;
+        self.queue.show_stats = self.settings.show_stats;
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    fn selected_playlist_path(&self) -> Option<PathBuf> {
+        self.playlist
+            .song_at(self.selected_playlist_song_index)
+            .map(|song| song.path.clone())
+    }
+
+    /// Moves the playlist selection back onto `path` after the rows changed
+    fn select_playlist_path(&mut self, path: Option<PathBuf>) {
+        let row = path.and_then(|path| {
+            (0..self.playlist.row_count()).find(|&row| {
+                self.playlist
+                    .song_at(row)
+                    .is_some_and(|song| song.path == path)
+            })
+        });
+
+        match row {
+            Some(row) => self.selected_playlist_song_index = row,
+            None => self.clamp_playlist_selection(),
+        }
+    }
+
+    /// Reloads the playlist from the library, keeping the selection on the same song
+    fn reload_playlist(&mut self) {
+        let selected = self.selected_playlist_path();
+        self.playlist.load_playlist(&self.library);
+        self.select_playlist_path(selected);
+        self.refresh_filter();
+    }
+
+    fn clamp_playlist_selection(&mut self) {
+        self.selected_playlist_song_index = self
+            .selected_playlist_song_index
+            .min(self.playlist.row_count().saturating_sub(1));
+    }
+
+    /// Applies a new sort or grouping to the playlist and remembers it for the next start
+    fn update_playlist_view(&mut self, sort: SortKey, descending: bool, group_by: GroupBy) {
+        let selected = self.selected_playlist_path();
+        self.playlist.set_view(sort, descending, group_by);
+        self.select_playlist_path(selected);
+
+        self.settings.playlist_sort = sort;
+        self.settings.playlist_sort_descending = descending;
+        self.settings.playlist_group_by = group_by;
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// Re-runs the filter against the pane it was opened on
+    fn refresh_filter(&mut self) {
+        let App {
+            filter,
+            playlist,
+            queue,
+            ..
+        } = self;
+        if let Some(filter) = filter.as_mut() {
+            match filter.pane {
+                Pane::Queue => filter.refresh(queue.songs.iter()),
+                _ => filter.refresh(playlist.songs.iter()),
+            }
+        }
+    }
+
+    /// Closes the filter and moves the selection of its pane to the chosen hit
+    fn jump_to_filter_hit(&mut self) {
+        let Some(filter) = self.filter.take() else {
+            return;
+        };
+        let Some(index) = filter.selected_index() else {
+            return;
+        };
+
+        match filter.pane {
+            Pane::Queue => {
+                if index < self.queue.songs.len() {
+                    self.selected_queue_song_index = index;
+                }
+            }
+            _ => {
+                if let Some(row) = self.playlist.reveal(index) {
+                    self.selected_playlist_song_index = row;
+                }
+            }
+        }
+    }
+
+    /// Shows the saved smart playlist at `index` in the Playlist pane, or the whole library
+    fn show_smart_playlist(&mut self, index: Option<usize>) {
+        let selected = match index.and_then(|i| self.settings.smart_playlists.get(i)) {
+            Some(smart) => match Query::parse(&smart.query) {
+                Ok(query) => Some((smart.name.clone(), query)),
+                Err(e) => {
+                    self.set_notification(
+                        format!("Invalid smart playlist {}: {}", smart.name, e),
+                        NotificationType::Error,
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        self.selected_smart_playlist = selected.as_ref().and(index);
+        self.playlist.smart_playlist = selected;
+        self.playlist.load_playlist(&self.library);
+        self.selected_playlist_song_index = 0;
+    }
+
+    /// Cycles the Playlist pane through the library and each smart playlist
+    fn next_smart_playlist(&mut self) {
+        let next = match self.selected_smart_playlist {
+            None => 0,
+            Some(index) => index + 1,
+        };
+        if next < self.settings.smart_playlists.len() {
+            self.show_smart_playlist(Some(next));
+        } else {
+            self.show_smart_playlist(None);
+        }
+    }
+
+    /// Saves a smart playlist typed as `name = query` and shows it
+    fn add_smart_playlist(&mut self, input: &str) {
+        let Some((name, query)) = input.split_once('=') else {
+            self.set_notification(
+                "Smart playlists are written as: name = query".to_string(),
+                NotificationType::Error,
+            );
+            return;
+        };
+        let (name, query) = (name.trim(), query.trim());
+
+        if let Err(e) = Query::parse(query) {
+            self.set_notification(format!("Invalid query: {}", e), NotificationType::Error);
+            return;
+        }
+
+        self.settings.smart_playlists.push(SmartPlaylist {
+            name: name.to_string(),
+            query: query.to_string(),
+        });
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+        self.show_smart_playlist(Some(self.settings.smart_playlists.len() - 1));
+    }
+
+    fn remove_smart_playlist(&mut self) {
+        let Some(index) = self.selected_smart_playlist else {
+            return;
+        };
+
+        let removed = self.settings.smart_playlists.remove(index);
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+        self.show_smart_playlist(None);
+        self.set_notification(
+            format!("Removed smart playlist {}", removed.name),
+            NotificationType::Info,
+        );
+    }
+
+    fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+
+        match prompt.action {
+            PromptAction::NewSmartPlaylist => self.add_smart_playlist(&prompt.input),
+            PromptAction::OpenPath => self.open_path(&prompt.input),
+        }
+    }
+
+    /// Shows a folder or file in the browser, also when it lies outside the library
+    fn open_path(&mut self, input: &str) {
+        let input = input.trim();
+        let path = match (input.strip_prefix("~/"), env::var_os("HOME")) {
+            (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+            _ => PathBuf::from(input),
+        };
+
+        if !path.exists() {
+            self.set_notification(
+                format!("{} does not exist", path.display()),
+                NotificationType::Error,
+            );
+            return;
+        }
+        self.browser.open(&path);
+        self.selected_pane = Pane::Browser;
+    }
+
+    /// Adds a file, or every audio file below a folder, to the queue and optionally starts
+    /// playing the first one. Files that are not in the library are read without indexing them.
+    async fn queue_path(app: Arc<Mutex<App>>, path: PathBuf, play: bool) {
+        let paths = if path.is_dir() {
+            app.lock().await.set_notification(
+                format!("Reading {}", path.display()),
+                NotificationType::Info,
+            );
+            tokio::task::spawn_blocking(move || {
+                let config = LibraryConfig::from_env();
+                let mut paths: Vec<PathBuf> = walk_audio_files(&config, &path).collect();
+                paths.sort();
+                paths
+            })
+            .await
+            .unwrap_or_default()
+        } else {
+            vec![path]
+        };
+
+        let known: Vec<Option<Song>> = {
+            let app_locked = app.lock().await;
+            paths
+                .iter()
+                .map(|path| app_locked.library.song(path).cloned())
+                .collect()
+        };
+        let songs: Vec<Song> = tokio::task::spawn_blocking(move || {
+            paths
+                .iter()
+                .zip(known)
+                .filter_map(|(path, song)| song.or_else(|| LibraryIndex::read_song(path)))
+                .collect()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut app_locked = app.lock().await;
+        let Some(first) = songs.first().cloned() else {
+            app_locked.set_notification(
+                "No playable songs found".to_string(),
+                NotificationType::Error,
+            );
+            return;
+        };
+
+        let start = app_locked.queue.songs.len();
+        let count = songs.len();
+        for song in songs {
+            app_locked.queue.add_song(song);
+        }
+        if play {
+            app_locked.play_song(first, Some(start));
+        }
+        app_locked.set_notification(
+            format!("Added {} songs to the queue", count),
+            NotificationType::Info,
+        );
+    }
+
+    fn play_next_in_queue(&mut self) {
+        let next = self.playing_queue_index.map_or(0, |index| index + 1);
+        match self.queue.songs.get(next).cloned() {
+            Some(song) => self.play_song(song, Some(next)),
+            None => self.stop_playback(),
+        }
+    }
+
+    /// Advances through the queue when a song ends and mirrors the player state in the UI
+    fn update_playback(&mut self) {
+        if let Some(finished) = self.player.take_finished() {
+            self.record_listen(&finished.path, true, finished.duration);
+            if self.playing_queue_index.is_some() {
+                self.play_next_in_queue();
+            }
+        }
+
+        self.playback = match self.player.current_song() {
+            Some(song) => Playback {
+                current_song: song.title.clone(),
+                current_time: self.player.position() as u64,
+                total_time: song.duration as u64,
+                codec: song.codec.clone(),
+                paused: self.player.is_paused(),
+            },
+            None => Playback::new("", 0, 0),
+        };
+        self.update_lyrics();
+
+        let playing = self.player.current_song().map(|song| song.path.clone());
+        self.album_art.set_song(playing.as_deref());
+    }
+
+    /// Loads the lyrics when another song starts and follows the playback position
+    fn update_lyrics(&mut self) {
+        let playing = self.player.current_song().map(|song| song.path.clone());
+        if playing != self.lyrics_song {
+            self.lyrics.lyrics = playing.as_deref().and_then(load_lyrics);
+            self.lyrics.scroll = 0;
+            self.lyrics_song = playing;
+        }
+        self.lyrics.position = self.player.position();
+    }
+
+    /// Reloads the lyrics of the playing song, e.g. after a `.lrc` file was added
+    fn reload_lyrics(&mut self) {
+        self.lyrics_song = None;
+        self.update_lyrics();
+    }
+
+    /// Downloads the lyrics of one song, e.g. the one that is playing
+    async fn fetch_lyrics(app: Arc<Mutex<App>>, song: Song) {
+        let service = app.lock().await.lyrics_service.clone();
+        let result = err_text(service.save_lyrics(&song).await);
+
+        let mut app_locked = app.lock().await;
+        match result {
+            Ok(true) => {
+                app_locked.set_notification(
+                    format!("Saved lyrics for {}", song.title),
+                    NotificationType::Success,
+                );
+                app_locked.reload_lyrics();
+            }
+            Ok(false) => app_locked.set_notification(
+                format!("No lyrics found for {}", song.title),
+                NotificationType::Info,
+            ),
+            Err(e) => app_locked.set_notification(
+                format!("Failed to fetch lyrics: {}", e),
+                NotificationType::Error,
+            ),
+        }
+    }
+
+    /// Downloads lyrics for every song in the library that has none yet, one at a time
+    async fn fetch_library_lyrics(app: Arc<Mutex<App>>) {
+        let (songs, service) = {
+            let mut app_locked = app.lock().await;
+            if app_locked.fetching_lyrics {
+                return;
+            }
+            app_locked.fetching_lyrics = true;
+            (
+                app_locked.library.songs(),
+                app_locked.lyrics_service.clone(),
+            )
+        };
+
+        // Embedded lyrics are read from the files, so keep that off the async runtime
+        let missing = tokio::task::spawn_blocking(move || {
+            songs
+                .into_iter()
+                .filter(|song| {
+                    !lyrics_path(&song.path).exists() && load_lyrics(&song.path).is_none()
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let total = missing.len();
+        let mut found = 0;
+        let mut failed = 0;
+        for (i, song) in missing.iter().enumerate() {
+            app.lock().await.set_notification(
+                format!("Fetching lyrics {}/{}: {}", i + 1, total, song.title),
+                NotificationType::Info,
+            );
+            match service.save_lyrics(song).await {
+                Ok(true) => found += 1,
+                Ok(false) => {}
+                Err(_) => failed += 1,
+            }
+        }
+
+        let mut app_locked = app.lock().await;
+        app_locked.fetching_lyrics = false;
+        app_locked.reload_lyrics();
+        app_locked.set_notification(
+            format!(
+                "Found lyrics for {} of {} songs ({} lookups failed)",
+                found, total, failed
+            ),
+            if failed > 0 {
+                NotificationType::Error
+            } else {
+                NotificationType::Success
+            },
+        );
+    }
+
+    fn adjust_lyrics_offset(&mut self, delta_ms: i64) {
+        self.lyrics.offset_ms = if delta_ms == 0 {
+            0
+        } else {
+            self.lyrics.offset_ms + delta_ms
+        };
+        self.settings.lyrics_offset_ms = self.lyrics.offset_ms;
+        if let Err(e) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", e);
         }
     }
 
@@ -80,6 +934,649 @@ impl App {
         }
     }
 
+    /// Fingerprints the songs that have not been fingerprinted yet, without holding the app lock,
+    /// then opens the duplicates review
+    async fn find_duplicates(app: Arc<Mutex<App>>) {
+        let missing = app.lock().await.library.missing_fingerprints();
+
+        let computed = tokio::task::spawn_blocking(move || {
+            missing
+                .into_iter()
+                .filter_map(|path| {
+                    let hash = content_hash(&path).ok()?;
+                    // An empty fingerprint marks files that cannot be decoded, so they are not retried
+                    let fingerprint = fingerprint(&path).unwrap_or_default();
+                    Some((path, hash, fingerprint))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let entries: Vec<IndexEntry> = {
+            let mut app_locked = app.lock().await;
+            for (path, hash, fingerprint) in computed {
+                app_locked.library.set_fingerprint(&path, hash, fingerprint);
+            }
+            app_locked.library.entries().cloned().collect()
+        };
+
+        // Comparing every pair of fingerprints is slow on large libraries
+        let groups = tokio::task::spawn_blocking(move || {
+            find_duplicates(&entries.iter().collect::<Vec<_>>())
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut app_locked = app.lock().await;
+        if groups.is_empty() {
+            app_locked.set_notification("No duplicates found".to_string(), NotificationType::Info);
+        } else {
+            app_locked.set_notification(
+                format!("Found {} groups of duplicates", groups.len()),
+                NotificationType::Info,
+            );
+            app_locked.duplicates = Some(DuplicatesReview::new(groups));
+            app_locked.selected_pane = Pane::Duplicates;
+        }
+    }
+
+    /// Keeps the selected copy of a duplicate group and merges the other copies into it,
+    /// without holding the app lock while the files are rewritten
+    async fn keep_selected_duplicate(app: Arc<Mutex<App>>) {
+        let Some((keep, others)) = app
+            .lock()
+            .await
+            .duplicates
+            .as_mut()
+            .and_then(|review| review.take_selected())
+        else {
+            return;
+        };
+
+        let (merged_keep, merged_others) = (keep.clone(), others.clone());
+        let result = tokio::task::spawn_blocking(move || {
+            err_text(merge_duplicates(&merged_keep, &merged_others))
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+        let mut app_locked = app.lock().await;
+        match result {
+            Ok(()) => {
+                let other_paths: Vec<PathBuf> =
+                    others.iter().map(|other| other.path.clone()).collect();
+                app_locked.library.merge_stats(&keep.path, &other_paths);
+                let kept = app_locked
+                    .library
+                    .song(&keep.path)
+                    .cloned()
+                    .unwrap_or(keep.clone());
+
+                for other in &others {
+                    app_locked.library.remove(&other.path);
+                    // The kept copy takes over the queue slots of the removed ones
+                    for song in app_locked.queue.songs.iter_mut() {
+                        if song.path == other.path {
+                            *song = kept.clone();
+                        }
+                    }
+                }
+                app_locked.reload_playlist();
+
+                app_locked.set_notification(
+                    format!("Kept {}, removed {} duplicates", keep.title, others.len()),
+                    NotificationType::Success,
+                );
+            }
+            Err(e) => app_locked.set_notification(
+                format!("Failed to merge duplicates: {}", e),
+                NotificationType::Error,
+            ),
+        }
+
+        app_locked.close_duplicates_if_done();
+    }
+
+    fn close_duplicates_if_done(&mut self) {
+        if self
+            .duplicates
+            .as_ref()
+            .is_some_and(|review| review.is_empty())
+        {
+            self.duplicates = None;
+            self.selected_pane = Pane::Playlist;
+        }
+    }
+
+    /// Decodes every song, and the files that could not be indexed, then opens the report.
+    /// Files run one at a time without holding the app lock.
+    async fn verify_library(app: Arc<Mutex<App>>) {
+        let files: Vec<(PathBuf, Option<Song>)> = {
+            let mut app_locked = app.lock().await;
+            if app_locked.verifying_library {
+                return;
+            }
+            app_locked.verifying_library = true;
+
+            let songs = app_locked.library.songs();
+            let failed = app_locked.library.failed_paths();
+            songs
+                .into_iter()
+                .map(|song| (song.path.clone(), Some(song)))
+                .chain(failed.into_iter().map(|path| (path, None)))
+                .collect()
+        };
+
+        let total = files.len();
+        let mut issues = Vec::new();
+        let mut readable_again = Vec::new();
+        for (i, (path, song)) in files.into_iter().enumerate() {
+            app.lock().await.set_notification(
+                format!("Checking {}/{}: {}", i + 1, total, path.display()),
+                NotificationType::Info,
+            );
+
+            let checked = tokio::task::spawn_blocking(move || {
+                let problems = verify_file(&path);
+                // The tags of a broken file are often still readable
+                let video_id = song
+                    .as_ref()
+                    .and_then(|song| song.tags.video_id.clone())
+                    .or_else(|| read_tags(&path).video_id);
+                VerifyIssue {
+                    path,
+                    song,
+                    video_id,
+                    problems,
+                }
+            })
+            .await;
+
+            match checked {
+                Ok(issue) if !issue.problems.is_empty() => issues.push(issue),
+                // A file that failed to index before but decodes fine now
+                Ok(issue) if issue.song.is_none() => readable_again.push(issue.path),
+                _ => {}
+            }
+        }
+
+        {
+            let mut app_locked = app.lock().await;
+            app_locked.verifying_library = false;
+            if issues.is_empty() {
+                app_locked.set_notification(
+                    format!("Checked {} files, no problems found", total),
+                    NotificationType::Success,
+                );
+            } else {
+                app_locked.set_notification(
+                    format!("Found problems in {} of {} files", issues.len(), total),
+                    NotificationType::Error,
+                );
+                app_locked.verify_report = Some(VerifyReport::new(issues, total));
+                app_locked.selected_pane = Pane::VerifyReport;
+            }
+        }
+
+        App::apply_library_changes(
+            &app,
+            ScanResult {
+                changed: readable_again,
+                removed: Vec::new(),
+            },
+        )
+        .await;
+    }
+
+    /// Deletes the file of the selected issue from disk and from the library
+    fn delete_verified_file(&mut self) {
+        let Some(issue) = self
+            .verify_report
+            .as_mut()
+            .and_then(|report| report.take_selected())
+        else {
+            return;
+        };
+
+        match fs::remove_file(&issue.path) {
+            Ok(()) => {
+                self.library.remove(&issue.path);
+                self.reload_playlist();
+                self.set_notification(
+                    format!("Deleted {}", issue.path.display()),
+                    NotificationType::Success,
+                );
+            }
+            Err(e) => self.set_notification(
+                format!("Failed to delete {}: {}", issue.path.display(), e),
+                NotificationType::Error,
+            ),
+        }
+
+        self.close_verify_report_if_done();
+    }
+
+    /// Searches YouTube without holding the app lock and shows the first page of results.
+    /// Filters like `dur:long` are typed along with the search terms, see `SearchQuery::parse`.
+    async fn search(app: Arc<Mutex<App>>, input: String) {
+        let query = match SearchQuery::parse(&input) {
+            Ok(query) => query,
+            Err(err) => {
+                // Leave the input for fixing the filter
+                app.lock()
+                    .await
+                    .set_notification(err, NotificationType::Error);
+                return;
+            }
+        };
+        let service = app.lock().await.youtube_service.clone();
+
+        let result = err_text(service.search_videos(&query, SEARCH_PAGE_SIZE, None).await);
+
+        let mut app_locked = app.lock().await;
+        match result {
+            Ok(page) => {
+                app_locked.search_results = Some(page.items);
+                app_locked.search_query = query;
+                app_locked.next_search_page = page.next_page_token;
+                app_locked.total_search_results = page.page_info.map(|info| info.total_results);
+                app_locked.selected_search_index = 0;
+                app_locked.selected_pane = Pane::SearchResults;
+            }
+            Err(err) => {
+                app_locked.set_notification(
+                    format!("Error searching YouTube: {}", err),
+                    NotificationType::Error,
+                );
+                app_locked.search_results = None;
+            }
+        }
+        app_locked.search_bar.clear();
+    }
+
+    /// Appends the following page to the shown search results, if there is one
+    async fn load_next_search_page(app: Arc<Mutex<App>>) {
+        let (service, query, page_token) = {
+            let mut app_locked = app.lock().await;
+            if app_locked.loading_search_page || app_locked.search_results.is_none() {
+                return;
+            }
+            let Some(page_token) = app_locked.next_search_page.clone() else {
+                return;
+            };
+            app_locked.loading_search_page = true;
+            (
+                app_locked.youtube_service.clone(),
+                app_locked.search_query.clone(),
+                page_token,
+            )
+        };
+
+        let result = err_text(
+            service
+                .search_videos(&query, SEARCH_PAGE_SIZE, Some(page_token))
+                .await,
+        );
+
+        let mut app_locked = app.lock().await;
+        app_locked.loading_search_page = false;
+        // The results may have been closed, or replaced by another search, in the meantime
+        if app_locked.search_results.is_none() || app_locked.search_query != query {
+            return;
+        }
+
+        match result {
+            Ok(page) => {
+                if let Some(results) = app_locked.search_results.as_mut() {
+                    results.extend(page.items);
+                }
+                app_locked.next_search_page = page.next_page_token;
+                if let Some(info) = page.page_info {
+                    app_locked.total_search_results = Some(info.total_results);
+                }
+            }
+            Err(err) => app_locked.set_notification(
+                format!("Error loading more results: {}", err),
+                NotificationType::Error,
+            ),
+        }
+    }
+
+    /// Downloads a search result into the library, unless a song with its video ID is there
+    /// already. An older download without the ID gets it written into its tags instead.
+    async fn download_video(app: Arc<Mutex<App>>, video: Video) {
+        let (service, untagged) = {
+            let mut app_locked = app.lock().await;
+            let Some(video_id) = video.id.video_id.as_deref() else {
+                app_locked.set_notification(
+                    "Failed to process video: Video ID is missing.".to_string(),
+                    NotificationType::Error,
+                );
+                return;
+            };
+            if app_locked.downloading_video_index.is_some() {
+                app_locked.set_notification(
+                    "Wait for the current download to finish".to_string(),
+                    NotificationType::Info,
+                );
+                return;
+            }
+            if let Some(song) = app_locked.library.song_for_video(video_id) {
+                let message = format!("Already in the library: {}", song.path.display());
+                app_locked.set_notification(message, NotificationType::Info);
+                return;
+            }
+
+            let untagged =
+                YoutubeService::untagged_download(&app_locked.library, video_id, &video.snippet)
+                    .cloned();
+            if untagged.is_none() {
+                app_locked.downloading_video_index = Some(app_locked.selected_search_index);
+            }
+            (app_locked.youtube_service.clone(), untagged)
+        };
+        let video_id = video.id.video_id.clone().unwrap_or_default();
+
+        if let Some(song) = untagged {
+            let mut tags = song.tags.clone();
+            tags.video_id = Some(video_id);
+            let path = song.path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                write_tags(&path, &tags).map_err(|e| e.to_string())
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+
+            match result {
+                Ok(()) => {
+                    app.lock().await.set_notification(
+                        format!("Already in the library, linked {} to its video", song.title),
+                        NotificationType::Success,
+                    );
+                    let scan = ScanResult {
+                        changed: vec![song.path],
+                        removed: Vec::new(),
+                    };
+                    App::apply_library_changes(&app, scan).await;
+                }
+                Err(e) => app.lock().await.set_notification(
+                    format!("Failed to tag {}: {}", song.title, e),
+                    NotificationType::Error,
+                ),
+            }
+            return;
+        }
+
+        let result = err_text(
+            service
+                .process_video_to_audio(&video_id, &video.snippet)
+                .await,
+        );
+
+        let scan = {
+            let mut app_locked = app.lock().await;
+            app_locked.downloading_video_index = None;
+            match result {
+                Ok(path) => {
+                    app_locked.set_notification(
+                        format!("Successfully processed video to MP3: {}", video_id),
+                        NotificationType::Success,
+                    );
+                    ScanResult {
+                        changed: vec![path],
+                        removed: Vec::new(),
+                    }
+                }
+                Err(e) => {
+                    app_locked.set_notification(
+                        format!("Failed to process video to MP3: {}", e),
+                        NotificationType::Error,
+                    );
+                    return;
+                }
+            }
+        };
+
+        // Index the new song right away, so it shows as downloaded without waiting for the watcher
+        App::apply_library_changes(&app, scan).await;
+    }
+
+    /// Downloads the selected broken song again. The broken file is kept aside until the
+    /// download succeeded, so nothing is lost when it fails.
+    async fn redownload_verified_file(app: Arc<Mutex<App>>) {
+        let (issue, video_id, service) = {
+            let mut app_locked = app.lock().await;
+            let Some(video_id) = app_locked
+                .verify_report
+                .as_ref()
+                .and_then(|report| report.selected_issue())
+                .and_then(|issue| issue.video_id.clone())
+            else {
+                app_locked.set_notification(
+                    "Only songs downloaded from YouTube can be downloaded again".to_string(),
+                    NotificationType::Error,
+                );
+                return;
+            };
+
+            let Some(issue) = app_locked
+                .verify_report
+                .as_mut()
+                .and_then(|report| report.take_selected())
+            else {
+                return;
+            };
+            app_locked.close_verify_report_if_done();
+            app_locked.set_notification(
+                format!("Downloading {} again...", issue.title()),
+                NotificationType::Info,
+            );
+            (issue, video_id, app_locked.youtube_service.clone())
+        };
+
+        let path = issue.path.clone();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        // The leading dot keeps the library from picking it up
+        let aside = path.with_file_name(format!(".{}.broken", file_name));
+        if let Err(e) = fs::rename(&path, &aside) {
+            app.lock().await.set_notification(
+                format!("Failed to move {} aside: {}", path.display(), e),
+                NotificationType::Error,
+            );
+            return;
+        }
+
+        let title = issue
+            .song
+            .as_ref()
+            .and_then(|song| song.tags.title.clone())
+            .unwrap_or_else(|| {
+                path.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            });
+        let channel = issue
+            .song
+            .as_ref()
+            .and_then(|song| {
+                song.tags
+                    .channel
+                    .clone()
+                    .or_else(|| song.tags.artist.clone())
+            })
+            .unwrap_or_default();
+        let result = err_text(service.redownload(&video_id, &path, &title, &channel).await);
+
+        let scan = match result {
+            Ok(new_path) => {
+                let _ = fs::remove_file(&aside);
+                app.lock().await.set_notification(
+                    format!("Downloaded {} again", issue.title()),
+                    NotificationType::Success,
+                );
+                ScanResult {
+                    removed: if new_path == path {
+                        Vec::new()
+                    } else {
+                        vec![path]
+                    },
+                    changed: vec![new_path],
+                }
+            }
+            Err(e) => {
+                let _ = fs::rename(&aside, &path);
+                app.lock().await.set_notification(
+                    format!("Failed to download {} again: {}", issue.title(), e),
+                    NotificationType::Error,
+                );
+                return;
+            }
+        };
+
+        App::apply_library_changes(&app, scan).await;
+    }
+
+    fn close_verify_report_if_done(&mut self) {
+        if self
+            .verify_report
+            .as_ref()
+            .is_some_and(|report| report.is_empty())
+        {
+            self.verify_report = None;
+            self.selected_pane = Pane::Playlist;
+        }
+    }
+
+    /// Keeps the library index in sync with the music directory.
+    /// Files are read without holding the app lock, and only when they actually changed.
+    async fn refresh_library(app: Arc<Mutex<App>>) {
+        let config = LibraryConfig::from_env();
+
+        // Catch up on whatever changed while we were not running
+        let scan = App::scan_library(&app, &config, None).await;
+        App::apply_library_changes(&app, scan).await;
+
+        let mut watcher = match LibraryWatcher::new(&config) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                app.lock().await.set_notification(
+                    format!(
+                        "Watching the music directory failed, polling instead: {}",
+                        e
+                    ),
+                    NotificationType::Error,
+                );
+                None
+            }
+        };
+
+        loop {
+            let scan = match watcher.as_mut() {
+                Some(watcher) => {
+                    tokio::time::sleep(LIBRARY_WATCH_INTERVAL).await;
+                    let paths = watcher.settled_paths();
+                    if paths.is_empty() {
+                        continue;
+                    }
+                    App::scan_library(&app, &config, Some(paths)).await
+                }
+                None => {
+                    tokio::time::sleep(LIBRARY_REFRESH_INTERVAL).await;
+                    App::scan_library(&app, &config, None).await
+                }
+            };
+
+            App::apply_library_changes(&app, scan).await;
+        }
+    }
+
+    /// Compares the whole library, or only the `paths` reported by the watcher, against the
+    /// index. The file system is walked off the UI thread, the index is locked only for
+    /// taking the stamps of the files it knows.
+    async fn scan_library(
+        app: &Arc<Mutex<App>>,
+        config: &LibraryConfig,
+        paths: Option<Vec<PathBuf>>,
+    ) -> ScanResult {
+        let known = app.lock().await.library.known_files();
+        let config = config.clone();
+
+        tokio::task::spawn_blocking(move || match paths {
+            Some(paths) => known.classify(&config, paths),
+            None => known.scan(&config),
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Writes the library index to disk every few seconds if it changed, so ratings, plays
+    /// and rescans do not each rewrite the whole file. Quitting saves whatever is left.
+    async fn save_library_periodically(app: Arc<Mutex<App>>) {
+        loop {
+            tokio::time::sleep(LIBRARY_SAVE_INTERVAL).await;
+
+            let Some(unsaved) = app.lock().await.library.take_unsaved() else {
+                continue;
+            };
+            let saved = tokio::task::spawn_blocking(move || unsaved.save())
+                .await
+                .unwrap_or_else(|e| Err(io::Error::other(e)));
+
+            if let Err(e) = saved {
+                eprintln!("Failed to save library index: {}", e);
+                app.lock().await.library.mark_unsaved();
+            }
+        }
+    }
+
+    async fn apply_library_changes(app: &Arc<Mutex<App>>, scan: ScanResult) {
+        if scan.is_empty() {
+            return;
+        }
+
+        let ScanResult { changed, removed } = scan;
+        let indexed = tokio::task::spawn_blocking(move || {
+            changed
+                .into_iter()
+                .map(|path| {
+                    let entry = LibraryIndex::index_file(&path);
+                    (path, entry)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut app_locked = app.lock().await;
+        for path in &removed {
+            app_locked.library.remove(path);
+        }
+        let mut unreadable = 0;
+        for (path, entry) in indexed {
+            match entry {
+                Some(entry) => app_locked.library.insert(path, entry),
+                None => {
+                    app_locked.library.mark_failed(path);
+                    unreadable += 1;
+                }
+            }
+        }
+        if unreadable > 0 {
+            app_locked.set_notification(
+                format!(
+                    "Could not read {} files, press V in the Playlist to check the library",
+                    unreadable
+                ),
+                NotificationType::Error,
+            );
+        }
+
+        app_locked.refresh_queue_songs();
+        app_locked.reload_playlist();
+    }
+
     pub async fn run(app: Arc<Mutex<App>>) -> Result<(), io::Error> {
         // Initial setup (lock only once for enabling raw mode)
         {
@@ -89,6 +1586,9 @@ impl App {
             execute!(&stdout, Clear(ClearType::All))?;
         }
 
+        tokio::spawn(App::refresh_library(Arc::clone(&app)));
+        tokio::spawn(App::save_library_periodically(Arc::clone(&app)));
+
         let backend = CrosstermBackend::new(io::stdout());
         let mut terminal = Terminal::new(backend)?;
 
@@ -96,26 +1596,48 @@ impl App {
             {
                 let mut app_locked = app.lock().await;
                 app_locked.check_notification_timeout();
+                app_locked.update_playback();
 
-                app_locked.playlist.load_playlist();
-
+                // Borrow the fields separately, the album art is drawn mutably
+                let app_state = &mut *app_locked;
                 terminal.draw(|f| {
                     let size = f.size();
                     LayoutBuilder::new()
                         .frame(size)
-                        .selected_pane(&app_locked.selected_pane)
-                        .search_bar(app_locked.search_bar.clone())
-                        .playlist(app_locked.playlist.clone())
-                        .queue(app_locked.queue.clone())
-                        .playback(app_locked.playback.clone())
-                        .search_results(app_locked.search_results.clone())
-                        .selected_search_index(app_locked.selected_search_index)
-                        .selected_playlist_song_index(app_locked.selected_playlist_song_index)
-                        .downloading_video_index(app_locked.downloading_video_index)
-                        .notification(app_locked.notification.as_ref())
-                        .selected_queue_song_index(app_locked.selected_queue_song_index)
+                        .selected_pane(&app_state.selected_pane)
+                        .search_bar(app_state.search_bar.clone())
+                        .playlist(app_state.playlist.clone())
+                        .queue(app_state.queue.clone())
+                        .playback(app_state.playback.clone())
+                        .album_art(&mut app_state.album_art)
+                        .lyrics(app_state.lyrics.clone())
+                        .search_results(app_state.search_results.clone())
+                        .selected_search_index(app_state.selected_search_index)
+                        .search_progress(
+                            app_state.total_search_results,
+                            app_state.loading_search_page,
+                        )
+                        .search_filters(&app_state.search_query.filters)
+                        .selected_playlist_song_index(app_state.selected_playlist_song_index)
+                        .downloading_video_index(app_state.downloading_video_index)
+                        .notification(app_state.notification.as_ref())
+                        .selected_queue_song_index(app_state.selected_queue_song_index)
+                        .duplicates(app_state.duplicates.clone())
+                        .metadata_review(app_state.metadata_review.clone())
+                        .verify_report(app_state.verify_report.clone())
+                        .browser(&app_state.browser)
+                        .stats_view(app_state.stats_view.clone())
+                        .filter(app_state.filter.clone())
+                        .prompt(app_state.prompt.clone())
+                        .tag_editor(app_state.tag_editor.clone())
+                        .library(&app_state.library)
                         .build(f);
                 })?;
+
+                // A sixel image can only be wiped by redrawing the whole screen
+                if app_locked.album_art.present(terminal.backend_mut())? {
+                    terminal.clear()?;
+                }
             }
 
             // Poll for events with a timeout of 100ms, so we don't block the loop
@@ -124,6 +1646,132 @@ impl App {
                     let app_clone = Arc::clone(&app); // Clone app before async task
 
                     match key.code {
+                        // Tag Editor Controls, while editing every key goes to the editor
+                        KeyCode::Esc if app.lock().await.tag_editor.is_some() => {
+                            app_clone.lock().await.tag_editor = None;
+                        }
+                        KeyCode::Enter if app.lock().await.tag_editor.is_some() => {
+                            let mut app_locked = app_clone.lock().await;
+                            let derived = app_locked
+                                .tag_editor
+                                .as_mut()
+                                .filter(|editor| editor.is_pattern_selected())
+                                .map(|editor| {
+                                    (editor.derive_from_file_names(), editor.songs.len())
+                                });
+
+                            match derived {
+                                Some((matched, total)) => app_locked.set_notification(
+                                    format!(
+                                        "{} of {} file names matched the pattern",
+                                        matched, total
+                                    ),
+                                    NotificationType::Info,
+                                ),
+                                None => {
+                                    drop(app_locked);
+                                    tokio::spawn(App::save_tag_editor(Arc::clone(&app)));
+                                }
+                            }
+                        }
+                        KeyCode::Down | KeyCode::Tab if app.lock().await.tag_editor.is_some() => {
+                            if let Some(editor) = app_clone.lock().await.tag_editor.as_mut() {
+                                editor.next();
+                            }
+                        }
+                        KeyCode::Up | KeyCode::BackTab if app.lock().await.tag_editor.is_some() => {
+                            if let Some(editor) = app_clone.lock().await.tag_editor.as_mut() {
+                                editor.previous();
+                            }
+                        }
+                        KeyCode::Backspace if app.lock().await.tag_editor.is_some() => {
+                            if let Some(editor) = app_clone.lock().await.tag_editor.as_mut() {
+                                editor.delete();
+                            }
+                        }
+                        KeyCode::Char(c) if app.lock().await.tag_editor.is_some() => {
+                            if let Some(editor) = app_clone.lock().await.tag_editor.as_mut() {
+                                editor.update(c);
+                            }
+                        }
+                        _ if app.lock().await.tag_editor.is_some() => {}
+                        // Prompt Controls, while a prompt is open every key goes to it
+                        KeyCode::Esc if app.lock().await.prompt.is_some() => {
+                            app_clone.lock().await.prompt = None;
+                        }
+                        KeyCode::Enter if app.lock().await.prompt.is_some() => {
+                            app_clone.lock().await.submit_prompt();
+                        }
+                        KeyCode::Backspace if app.lock().await.prompt.is_some() => {
+                            if let Some(prompt) = app_clone.lock().await.prompt.as_mut() {
+                                prompt.delete();
+                            }
+                        }
+                        KeyCode::Char(c) if app.lock().await.prompt.is_some() => {
+                            if let Some(prompt) = app_clone.lock().await.prompt.as_mut() {
+                                prompt.update(c);
+                            }
+                        }
+                        _ if app.lock().await.prompt.is_some() => {}
+                        // Filter Controls, while filtering every key goes to the filter
+                        KeyCode::Esc if app.lock().await.filter.is_some() => {
+                            app_clone.lock().await.filter = None;
+                        }
+                        KeyCode::Enter if app.lock().await.filter.is_some() => {
+                            app_clone.lock().await.jump_to_filter_hit();
+                        }
+                        KeyCode::Down if app.lock().await.filter.is_some() => {
+                            if let Some(filter) = app_clone.lock().await.filter.as_mut() {
+                                filter.next();
+                            }
+                        }
+                        KeyCode::Up if app.lock().await.filter.is_some() => {
+                            if let Some(filter) = app_clone.lock().await.filter.as_mut() {
+                                filter.previous();
+                            }
+                        }
+                        KeyCode::Backspace if app.lock().await.filter.is_some() => {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Some(filter) = app_locked.filter.as_mut() {
+                                filter.delete();
+                            }
+                            app_locked.refresh_filter();
+                        }
+                        KeyCode::Char(c) if app.lock().await.filter.is_some() => {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Some(filter) = app_locked.filter.as_mut() {
+                                filter.update(c);
+                            }
+                            app_locked.refresh_filter();
+                        }
+                        _ if app.lock().await.filter.is_some() => {}
+                        // Search Bar Controls, every character is text there, digits and q too
+                        KeyCode::Char(_) | KeyCode::Backspace
+                            if matches!(app.lock().await.selected_pane, Pane::SearchBar) =>
+                        {
+                            let app_locked = &mut *app_clone.lock().await;
+                            search_bar_input(
+                                &app_locked.selected_pane,
+                                &mut app_locked.search_bar,
+                                key.code,
+                            );
+                        }
+                        KeyCode::Esc
+                            if matches!(app.lock().await.selected_pane, Pane::SearchBar) =>
+                        {
+                            app_clone.lock().await.selected_pane = Pane::Playlist;
+                        }
+                        KeyCode::Char('/')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let pane = app_locked.selected_pane.clone();
+                            app_locked.filter = Some(ListFilter::new(pane));
+                            app_locked.refresh_filter();
+                        }
                         KeyCode::Char('0') => {
                             let mut app_locked = app_clone.lock().await;
                             app_locked.selected_pane = Pane::SearchBar;
@@ -144,54 +1792,62 @@ impl App {
                             app_locked.selected_pane = Pane::Playback;
                             app_locked.search_results = None; // Clear search results when moving away
                         }
-                        KeyCode::Char('q') => {
-                            disable_raw_mode().unwrap();
-                            std::process::exit(0);
+                        KeyCode::Char('4') => {
+                            let mut app_locked = app_clone.lock().await;
+                            app_locked.selected_pane = Pane::Lyrics;
+                            app_locked.search_results = None; // Clear search results when moving away
                         }
-                        KeyCode::Char(c)
-                            if matches!(app.lock().await.selected_pane, Pane::SearchBar) =>
-                        {
+                        KeyCode::Char('5') => {
                             let mut app_locked = app_clone.lock().await;
-                            app_locked.search_bar.update(c);
+                            let now = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs();
+                            let stats = LibraryStats::compute(&app_locked.library, now);
+                            app_locked.stats_view = Some(StatsView::new(stats));
+                            app_locked.selected_pane = Pane::Stats;
+                            app_locked.search_results = None; // Clear search results when moving away
                         }
-                        KeyCode::Backspace
-                            if matches!(app.lock().await.selected_pane, Pane::SearchBar) =>
-                        {
+                        KeyCode::Char('6') => {
+                            let mut app_locked = app_clone.lock().await;
+                            app_locked.selected_pane = Pane::Browser;
+                            app_locked.search_results = None; // Clear search results when moving away
+                        }
+                        KeyCode::Esc if matches!(app.lock().await.selected_pane, Pane::Stats) => {
+                            let mut app_locked = app_clone.lock().await;
+                            app_locked.stats_view = None;
+                            app_locked.selected_pane = Pane::Playlist;
+                        }
+                        KeyCode::Char('q') => {
                             let mut app_locked = app_clone.lock().await;
-                            app_locked.search_bar.delete();
+                            app_locked.stop_playback();
+                            if let Some(unsaved) = app_locked.library.take_unsaved() {
+                                if let Err(e) = unsaved.save() {
+                                    eprintln!("Failed to save library index: {}", e);
+                                }
+                            }
+                            app_locked.album_art.clear(&mut io::stdout())?;
+                            disable_raw_mode().unwrap();
+                            std::process::exit(0);
                         }
                         KeyCode::Enter
                             if matches!(app.lock().await.selected_pane, Pane::SearchBar) =>
                         {
                             let query = app.lock().await.search_bar.input.clone();
-                            let app_clone_inner = Arc::clone(&app); // Clone app before async task
-
-                            // Async search task for YouTube videos
-                            tokio::spawn(async move {
-                                let mut app_locked = app_clone_inner.lock().await;
-                                let search_results =
-                                    app_locked.youtube_service.search_videos(&query, 10).await;
-                                match search_results {
-                                    Ok(videos) => {
-                                        app_locked.search_results = Some(videos);
-                                        app_locked.selected_search_index = 0;
-                                        app_locked.selected_pane = Pane::SearchResults;
-                                    }
-                                    Err(err) => {
-                                        eprintln!("Error searching YouTube: {}", err);
-                                        app_locked.search_results = None;
-                                    }
-                                }
-                                app_locked.search_bar.clear();
-                            });
+                            tokio::spawn(App::search(app_clone.clone(), query));
                         }
                         KeyCode::Char('j')
                             if matches!(app.lock().await.selected_pane, Pane::SearchResults) =>
                         {
                             let mut app_locked = app_clone.lock().await;
-                            if let Some(ref results) = app_locked.search_results {
-                                app_locked.selected_search_index =
-                                    (app_locked.selected_search_index + 1).min(results.len() - 1);
+                            let count = app_locked.search_results.as_ref().map_or(0, Vec::len);
+                            let last = count.saturating_sub(1);
+                            app_locked.selected_search_index =
+                                (app_locked.selected_search_index + 1).min(last);
+
+                            // Infinite scroll, the next page loads once the last result is selected
+                            if count > 0 && app_locked.selected_search_index == last {
+                                tokio::spawn(App::load_next_search_page(app_clone.clone()));
                             }
                         }
                         KeyCode::Char('k')
@@ -213,53 +1869,7 @@ impl App {
                             };
 
                             if let Some(video) = selected_video {
-                                if let Some(video_id) = video.id.video_id {
-                                    let app_clone_inner = Arc::clone(&app); // Clone app for async task
-
-                                    let selected_index = app.lock().await.selected_search_index; // Get selected index
-                                    {
-                                        let mut app_locked = app.lock().await;
-                                        app_locked.downloading_video_index = Some(selected_index);
-                                    }
-
-                                    // Async task for downloading video and converting to MP3
-                                    tokio::spawn(async move {
-                                        let mut app_locked = app_clone_inner.lock().await;
-
-                                        match app_locked
-                                            .youtube_service
-                                            .process_video_to_audio(&video_id, &video.snippet.title) // Pass video_id as &str
-                                            .await
-                                        {
-                                            Ok(_) => {
-                                                app_locked.set_notification(
-                                                    format!(
-                                                        "Successfully processed video to MP3: {}",
-                                                        video_id
-                                                    ),
-                                                    NotificationType::Success,
-                                                );
-                                            }
-                                            Err(e) => {
-                                                app_locked.set_notification(
-                                                    format!(
-                                                        "Failed to process video to MP3: {}",
-                                                        e
-                                                    ),
-                                                    NotificationType::Error,
-                                                );
-                                            }
-                                        }
-
-                                        app_locked.downloading_video_index = None;
-                                    });
-                                } else {
-                                    let mut app_locked = app_clone.lock().await;
-                                    app_locked.set_notification(
-                                        "Failed to process video: Video ID is missing.".to_string(),
-                                        NotificationType::Error,
-                                    );
-                                }
+                                tokio::spawn(App::download_video(app_clone.clone(), video));
                             }
                         }
                         KeyCode::Esc
@@ -274,9 +1884,10 @@ impl App {
                             if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
                         {
                             let mut app_locked = app_clone.lock().await;
-                            let playlist_len = app_locked.playlist.songs.len(); // Assuming playlist has a 'songs' field
+                            let playlist_len = app_locked.playlist.row_count();
                             app_locked.selected_playlist_song_index =
-                                (app_locked.selected_playlist_song_index + 1).min(playlist_len - 1);
+                                (app_locked.selected_playlist_song_index + 1)
+                                    .min(playlist_len.saturating_sub(1));
                         }
 
                         KeyCode::Char('k')
@@ -291,35 +1902,265 @@ impl App {
                             if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
                         {
                             let mut app_locked = app_clone.lock().await;
-                            if app_locked.selected_playlist_song_index
-                                < app_locked.playlist.songs.len()
-                            {
-                                let _index = app_locked.selected_playlist_song_index;
+                            let _index = app_locked.selected_playlist_song_index;
+
+                            app_locked.playlist.remove_song(_index);
+                            app_locked.clamp_playlist_selection();
+                        }
+                        KeyCode::Char('a')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let _index = app_locked.selected_playlist_song_index;
+                            if let Some(_song) = app_locked.playlist.song_at(_index).cloned() {
+                                app_locked.queue.add_song(_song);
+                            }
+                        }
+
+                        KeyCode::Enter
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let index = app_locked.selected_playlist_song_index;
+                            if let Some(song) = app_locked.playlist.song_at(index).cloned() {
+                                app_locked.play_song(song, None);
+                            } else if app_locked.playlist.toggle_group(index) {
+                                app_locked.clamp_playlist_selection();
+                            }
+                        }
+
+                        KeyCode::Char('s')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let sort = app_locked.playlist.sort.next();
+                            let (descending, group_by) =
+                                (app_locked.playlist.descending, app_locked.playlist.group_by);
+                            app_locked.update_playlist_view(sort, descending, group_by);
+                        }
+
+                        KeyCode::Char('S')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let (sort, descending, group_by) = (
+                                app_locked.playlist.sort,
+                                !app_locked.playlist.descending,
+                                app_locked.playlist.group_by,
+                            );
+                            app_locked.update_playlist_view(sort, descending, group_by);
+                        }
+
+                        KeyCode::Char('v')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            app_clone.lock().await.next_smart_playlist();
+                        }
+
+                        KeyCode::Char('n')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            app_clone.lock().await.prompt = Some(Prompt::new(
+                                PromptAction::NewSmartPlaylist,
+                                "New smart playlist (name = query)",
+                            ));
+                        }
+
+                        KeyCode::Char('X')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            app_clone.lock().await.remove_smart_playlist();
+                        }
+
+                        KeyCode::Char('A')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let songs = app_locked.playlist.visible_songs();
+                            let count = songs.len();
+                            for song in songs {
+                                app_locked.queue.add_song(song);
+                            }
+                            app_locked.set_notification(
+                                format!("Queued {} songs", count),
+                                NotificationType::Info,
+                            );
+                        }
+
+                        KeyCode::Char('g')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let (sort, descending, group_by) = (
+                                app_locked.playlist.sort,
+                                app_locked.playlist.descending,
+                                app_locked.playlist.group_by.next(),
+                            );
+                            app_locked.update_playlist_view(sort, descending, group_by);
+                        }
 
-                                app_locked.playlist.remove_song(_index);
+                        KeyCode::Char('D')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            app_clone.lock().await.set_notification(
+                                "Looking for duplicates...".to_string(),
+                                NotificationType::Info,
+                            );
+                            tokio::spawn(App::find_duplicates(Arc::clone(&app)));
+                        }
 
-                                if app_locked.playlist.songs.is_empty() {
-                                    app_locked.selected_playlist_song_index = 0;
-                                } else if app_locked.selected_playlist_song_index
-                                    >= app_locked.playlist.songs.len()
-                                {
-                                    app_locked.selected_playlist_song_index =
-                                        app_locked.playlist.songs.len() - 1;
+                        // Duplicates Controls
+                        KeyCode::Char('j')
+                            if matches!(app.lock().await.selected_pane, Pane::Duplicates) =>
+                        {
+                            if let Some(review) = app_clone.lock().await.duplicates.as_mut() {
+                                review.next();
+                            }
+                        }
+                        KeyCode::Char('k')
+                            if matches!(app.lock().await.selected_pane, Pane::Duplicates) =>
+                        {
+                            if let Some(review) = app_clone.lock().await.duplicates.as_mut() {
+                                review.previous();
+                            }
+                        }
+                        KeyCode::Enter
+                            if matches!(app.lock().await.selected_pane, Pane::Duplicates) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let confirmed = app_locked
+                                .duplicates
+                                .as_ref()
+                                .is_some_and(|review| review.confirming);
+                            if confirmed {
+                                drop(app_locked);
+                                tokio::spawn(App::keep_selected_duplicate(Arc::clone(&app)));
+                            } else if let Some(review) = app_locked.duplicates.as_mut() {
+                                // Deleting files needs a second Enter, after seeing which
+                                review.confirming = !review.selected_duplicates().is_empty();
+                            }
+                        }
+                        KeyCode::Char('x')
+                            if matches!(app.lock().await.selected_pane, Pane::Duplicates) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Some(review) = app_locked.duplicates.as_mut() {
+                                review.skip_selected();
+                            }
+                            app_locked.close_duplicates_if_done();
+                        }
+                        KeyCode::Esc
+                            if matches!(app.lock().await.selected_pane, Pane::Duplicates) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            match app_locked.duplicates.as_mut() {
+                                Some(review) if review.confirming => review.confirming = false,
+                                _ => {
+                                    app_locked.duplicates = None;
+                                    app_locked.selected_pane = Pane::Playlist;
                                 }
                             }
                         }
-                        KeyCode::Char('a')
+
+                        KeyCode::Char('V')
                             if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            tokio::spawn(App::verify_library(Arc::clone(&app)));
+                        }
+
+                        // Verify Report Controls
+                        KeyCode::Char('j')
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
+                        {
+                            if let Some(report) = app_clone.lock().await.verify_report.as_mut() {
+                                report.next();
+                            }
+                        }
+                        KeyCode::Char('k')
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
+                        {
+                            if let Some(report) = app_clone.lock().await.verify_report.as_mut() {
+                                report.previous();
+                            }
+                        }
+                        KeyCode::Char('r')
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
+                        {
+                            tokio::spawn(App::redownload_verified_file(Arc::clone(&app)));
+                        }
+                        KeyCode::Char('d')
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
+                        {
+                            app_clone.lock().await.delete_verified_file();
+                        }
+                        KeyCode::Char('x')
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Some(report) = app_locked.verify_report.as_mut() {
+                                report.take_selected();
+                            }
+                            app_locked.close_verify_report_if_done();
+                        }
+                        KeyCode::Esc
+                            if matches!(app.lock().await.selected_pane, Pane::VerifyReport) =>
                         {
                             let mut app_locked = app_clone.lock().await;
-                            if app_locked.selected_playlist_song_index
-                                < app_locked.playlist.songs.len()
-                            {
-                                let _index = app_locked.selected_playlist_song_index;
-                                let _song = app_locked.playlist.songs[_index].clone();
+                            app_locked.verify_report = None;
+                            app_locked.selected_pane = Pane::Playlist;
+                        }
 
-                                app_locked.queue.add_song(_song);
+                        // Metadata Review Controls
+                        KeyCode::Char('j')
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            if let Some(review) = app_clone.lock().await.metadata_review.as_mut() {
+                                review.next();
+                            }
+                        }
+                        KeyCode::Char('k')
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            if let Some(review) = app_clone.lock().await.metadata_review.as_mut() {
+                                review.previous();
+                            }
+                        }
+                        KeyCode::Char('c')
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            if let Some(review) = app_clone.lock().await.metadata_review.as_mut() {
+                                review.toggle_cover();
+                            }
+                        }
+                        KeyCode::Enter | KeyCode::Char('a')
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let proposals = match app_locked.metadata_review.as_mut() {
+                                Some(review) if key.code == KeyCode::Enter => {
+                                    review.take_selected().into_iter().collect()
+                                }
+                                Some(review) => review.take_all(),
+                                None => Vec::new(),
+                            };
+                            app_locked.close_metadata_review_if_done();
+                            tokio::spawn(App::accept_metadata(Arc::clone(&app), proposals));
+                        }
+                        KeyCode::Char('x')
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Some(review) = app_locked.metadata_review.as_mut() {
+                                review.skip_selected();
                             }
+                            app_locked.close_metadata_review_if_done();
+                        }
+                        KeyCode::Esc
+                            if matches!(app.lock().await.selected_pane, Pane::MetadataReview) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            app_locked.metadata_review = None;
+                            app_locked.selected_pane = Pane::Playlist;
                         }
 
                         // Queue Controls
@@ -329,7 +2170,8 @@ impl App {
                             let mut app_locked = app_clone.lock().await;
                             let queue_len = app_locked.queue.songs.len();
                             app_locked.selected_queue_song_index =
-                                (app_locked.selected_queue_song_index + 1).min(queue_len - 1);
+                                (app_locked.selected_queue_song_index + 1)
+                                    .min(queue_len.saturating_sub(1));
                         }
 
                         KeyCode::Char('k')
@@ -340,6 +2182,234 @@ impl App {
                                 app_locked.selected_queue_song_index -= 1;
                             }
                         }
+                        KeyCode::Enter if matches!(app.lock().await.selected_pane, Pane::Queue) => {
+                            let mut app_locked = app_clone.lock().await;
+                            let index = app_locked.selected_queue_song_index;
+                            if let Some(song) = app_locked.queue.songs.get(index).cloned() {
+                                app_locked.play_song(song, Some(index));
+                            }
+                        }
+
+                        // Rating Controls
+                        KeyCode::Char('+')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            app_clone.lock().await.update_selected_song_stats(|stats| {
+                                stats.rating = (stats.rating + 1).min(MAX_RATING)
+                            });
+                        }
+                        KeyCode::Char('-')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            app_clone.lock().await.update_selected_song_stats(|stats| {
+                                stats.rating = stats.rating.saturating_sub(1)
+                            });
+                        }
+                        KeyCode::Char('f')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            app_clone.lock().await.update_selected_song_stats(|stats| {
+                                stats.favourite = !stats.favourite
+                            });
+                        }
+                        KeyCode::Char('e')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            app_clone.lock().await.open_tag_editor();
+                        }
+                        KeyCode::Char('M')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            let songs = app_clone.lock().await.songs_to_edit();
+                            if !songs.is_empty() {
+                                tokio::spawn(App::lookup_metadata(Arc::clone(&app), songs));
+                            }
+                        }
+                        KeyCode::Char('m')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            let row = app_locked.selected_playlist_song_index;
+                            app_locked.playlist.toggle_mark(row);
+                        }
+                        KeyCode::Char('c')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            app_clone.lock().await.toggle_stats_columns();
+                        }
+
+                        KeyCode::Char('l')
+                            if matches!(
+                                app.lock().await.selected_pane,
+                                Pane::Playlist | Pane::Queue
+                            ) =>
+                        {
+                            let song = app_clone.lock().await.selected_song().cloned();
+                            if let Some(song) = song {
+                                tokio::spawn(App::fetch_lyrics(Arc::clone(&app), song));
+                            }
+                        }
+                        KeyCode::Char('L')
+                            if matches!(app.lock().await.selected_pane, Pane::Playlist) =>
+                        {
+                            tokio::spawn(App::fetch_library_lyrics(Arc::clone(&app)));
+                        }
+
+                        // Lyrics Controls
+                        KeyCode::Char('f')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            let song = app_clone.lock().await.player.current_song().cloned();
+                            if let Some(song) = song {
+                                tokio::spawn(App::fetch_lyrics(Arc::clone(&app), song));
+                            }
+                        }
+                        KeyCode::Char('j')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            app_clone.lock().await.lyrics.scroll_down();
+                        }
+                        KeyCode::Char('k')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            app_clone.lock().await.lyrics.scroll_up();
+                        }
+                        KeyCode::Char('+')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            app_clone
+                                .lock()
+                                .await
+                                .adjust_lyrics_offset(LYRICS_OFFSET_STEP_MS);
+                        }
+                        KeyCode::Char('-')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            app_clone
+                                .lock()
+                                .await
+                                .adjust_lyrics_offset(-LYRICS_OFFSET_STEP_MS);
+                        }
+                        KeyCode::Char('r')
+                            if matches!(app.lock().await.selected_pane, Pane::Lyrics) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            app_locked.adjust_lyrics_offset(0);
+                            app_locked.reload_lyrics();
+                        }
+
+                        // Browser Controls
+                        KeyCode::Char('j') | KeyCode::Down
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            app_clone.lock().await.browser.next();
+                        }
+                        KeyCode::Char('k') | KeyCode::Up
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            app_clone.lock().await.browser.previous();
+                        }
+                        KeyCode::Char('l') | KeyCode::Right
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            app_clone.lock().await.browser.expand();
+                        }
+                        KeyCode::Char('h') | KeyCode::Left
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            app_clone.lock().await.browser.collapse();
+                        }
+                        KeyCode::Enter
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            match app_locked.browser.selected_node().cloned() {
+                                Some(node) if node.is_dir => app_locked.browser.toggle(),
+                                Some(node) => {
+                                    let song = app_locked.library.song(&node.path).cloned();
+                                    drop(app_locked);
+                                    // Reading the tags touches the disk, which may be slow
+                                    let song = match song {
+                                        Some(song) => Some(song),
+                                        None => tokio::task::spawn_blocking(move || {
+                                            LibraryIndex::read_song(&node.path)
+                                        })
+                                        .await
+                                        .unwrap_or_default(),
+                                    };
+                                    let mut app_locked = app_clone.lock().await;
+                                    match song {
+                                        Some(song) => app_locked.play_song(song, None),
+                                        None => app_locked.set_notification(
+                                            "Could not read this file".to_string(),
+                                            NotificationType::Error,
+                                        ),
+                                    }
+                                }
+                                None => {}
+                            }
+                        }
+                        KeyCode::Char('a') | KeyCode::Char('p')
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            let path = app_clone
+                                .lock()
+                                .await
+                                .browser
+                                .selected_node()
+                                .map(|node| node.path.clone());
+                            if let Some(path) = path {
+                                let play = key.code == KeyCode::Char('p');
+                                tokio::spawn(App::queue_path(app_clone.clone(), path, play));
+                            }
+                        }
+                        KeyCode::Char('o')
+                            if matches!(app.lock().await.selected_pane, Pane::Browser) =>
+                        {
+                            app_clone.lock().await.prompt =
+                                Some(Prompt::new(PromptAction::OpenPath, "Open folder or file"));
+                        }
+
+                        // Playback Controls
+                        KeyCode::Char('p') | KeyCode::Char(' ')
+                            if matches!(app.lock().await.selected_pane, Pane::Playback) =>
+                        {
+                            let mut app_locked = app_clone.lock().await;
+                            if let Err(e) = app_locked.player.toggle_pause() {
+                                app_locked.set_notification(
+                                    format!("Failed to pause playback: {}", e),
+                                    NotificationType::Error,
+                                );
+                            }
+                        }
+                        KeyCode::Char('s')
+                            if matches!(app.lock().await.selected_pane, Pane::Playback) =>
+                        {
+                            app_clone.lock().await.stop_playback();
+                        }
+                        KeyCode::Char('n')
+                            if matches!(app.lock().await.selected_pane, Pane::Playback) =>
+                        {
+                            app_clone.lock().await.play_next_in_queue();
+                        }
                         _ => {}
                     }
                 }
@@ -347,3 +2417,55 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_filters_reach_the_search_query() {
+        let mut search_bar = SearchBar::new();
+        for c in "q lofi after:2024-06-01 cat:10 region:de".chars() {
+            assert!(search_bar_input(
+                &Pane::SearchBar,
+                &mut search_bar,
+                KeyCode::Char(c)
+            ));
+        }
+        assert!(search_bar_input(
+            &Pane::SearchBar,
+            &mut search_bar,
+            KeyCode::Backspace
+        ));
+        assert!(search_bar_input(
+            &Pane::SearchBar,
+            &mut search_bar,
+            KeyCode::Char('e')
+        ));
+
+        let query = SearchQuery::parse(&search_bar.input).unwrap();
+        assert_eq!(query.text, "q lofi");
+        assert_eq!(
+            query.filters.published_after.as_deref(),
+            Some("2024-06-01T00:00:00Z")
+        );
+        assert_eq!(query.filters.video_category_id.as_deref(), Some("10"));
+        assert_eq!(query.filters.region_code.as_deref(), Some("DE"));
+    }
+
+    #[test]
+    fn keys_outside_the_search_bar_stay_shortcuts() {
+        let mut search_bar = SearchBar::new();
+        assert!(!search_bar_input(
+            &Pane::Playlist,
+            &mut search_bar,
+            KeyCode::Char('1')
+        ));
+        assert!(!search_bar_input(
+            &Pane::SearchBar,
+            &mut search_bar,
+            KeyCode::Enter
+        ));
+        assert!(search_bar.input.is_empty());
+    }
+}
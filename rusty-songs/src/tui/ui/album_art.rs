@@ -0,0 +1,484 @@
+use crate::library::cover_art::load_cover_art;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use crossterm::{queue, terminal};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, RgbImage};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Clear, Paragraph};
+use tui::Frame;
+
+/// Cell size assumed when the terminal doesn't report its size in pixels
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+/// Kitty accepts at most this many bytes of base64 per escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+/// Levels per channel of the fixed sixel palette, 6 * 6 * 6 = 216 colours
+const SIXEL_LEVELS: u32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+    HalfBlocks,
+    Off,
+}
+
+impl GraphicsProtocol {
+    /// Picks the protocol from `ALBUM_ART` (kitty, sixel, blocks or off),
+    /// or else from what the terminal is known to support
+    pub fn detect() -> Self {
+        Self::detect_from(|name| env::var(name).ok())
+    }
+
+    /// `detect` with the environment variables looked up through `var`
+    fn detect_from(var: impl Fn(&str) -> Option<String>) -> Self {
+        match var("ALBUM_ART")
+            .map(|value| value.to_lowercase())
+            .as_deref()
+        {
+            Some("kitty") => return GraphicsProtocol::Kitty,
+            Some("sixel") => return GraphicsProtocol::Sixel,
+            Some("blocks" | "halfblocks") => return GraphicsProtocol::HalfBlocks,
+            Some("off" | "none") => return GraphicsProtocol::Off,
+            _ => {}
+        }
+
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+
+        // Multiplexers don't pass the escape sequences through
+        if var("TMUX").is_some() || term.starts_with("screen") {
+            return GraphicsProtocol::HalfBlocks;
+        }
+
+        if var("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "WezTerm"
+            || program == "ghostty"
+        {
+            GraphicsProtocol::Kitty
+        } else if ["foot", "mlterm", "yaft", "contour", "sixel"]
+            .iter()
+            .any(|name| term.contains(name))
+            || program == "iTerm.app"
+        {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::HalfBlocks
+        }
+    }
+
+    fn uses_escape_sequences(self) -> bool {
+        matches!(self, GraphicsProtocol::Kitty | GraphicsProtocol::Sixel)
+    }
+}
+
+/// The cover scaled to one pane size
+enum Rendered {
+    Blocks(Vec<Spans<'static>>),
+    /// Escape sequence drawing the image, and where it starts within the pane
+    Graphics {
+        sequence: String,
+        offset: (u16, u16),
+    },
+}
+
+/// Cover art of the playing song, drawn in the Now Playing pane
+pub struct AlbumArt {
+    protocol: GraphicsProtocol,
+    song: Option<PathBuf>,
+    image: Option<DynamicImage>,
+    /// Scaled renders by pane size and kind, so redraws don't resize the image again
+    cache: HashMap<(u16, u16, bool), Rendered>,
+    /// Where the terminal graphics go in the frame being drawn
+    placement: Option<Rect>,
+    /// The song whose cover is on screen, and where
+    shown: Option<(PathBuf, Rect)>,
+}
+
+impl Default for AlbumArt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlbumArt {
+    pub fn new() -> Self {
+        AlbumArt {
+            protocol: GraphicsProtocol::detect(),
+            song: None,
+            image: None,
+            cache: HashMap::new(),
+            placement: None,
+            shown: None,
+        }
+    }
+
+    pub fn has_art(&self) -> bool {
+        self.image.is_some() && self.protocol != GraphicsProtocol::Off
+    }
+
+    /// Loads the cover when another song starts playing
+    pub fn set_song(&mut self, song: Option<&Path>) {
+        if self.song.as_deref() == song {
+            return;
+        }
+
+        self.song = song.map(Path::to_path_buf);
+        self.image = song
+            .and_then(load_cover_art)
+            .and_then(|data| image::load_from_memory(&data).ok());
+        self.cache.clear();
+    }
+
+    /// Draws half blocks into the frame, or reserves `area` for the terminal graphics.
+    /// Graphics would cover the popups, so they fall back to half blocks while one is open.
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, graphics_allowed: bool) {
+        if !self.has_art() || area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        if self.protocol.uses_escape_sequences() && graphics_allowed {
+            f.render_widget(Clear, area);
+            self.placement = Some(area);
+            return;
+        }
+
+        if let Some(Rendered::Blocks(lines)) = self.rendered(area, false) {
+            f.render_widget(Paragraph::new(lines.clone()), area);
+        }
+    }
+
+    /// Writes the terminal graphics after a frame was drawn, only when they changed.
+    /// Returns true when the screen has to be cleared first to wipe an old sixel image.
+    pub fn present<W: Write>(&mut self, out: &mut W) -> io::Result<bool> {
+        let wanted = self
+            .placement
+            .take()
+            .zip(self.song.clone())
+            .map(|(area, song)| (song, area));
+        if wanted == self.shown {
+            return Ok(false);
+        }
+
+        if self.protocol == GraphicsProtocol::Sixel && self.shown.take().is_some() {
+            return Ok(true);
+        }
+        self.clear(out)?;
+
+        let Some((song, area)) = wanted else {
+            return Ok(false);
+        };
+        if let Some(Rendered::Graphics { sequence, offset }) = self.rendered(area, true) {
+            queue!(
+                out,
+                SavePosition,
+                MoveTo(area.x + offset.0, area.y + offset.1)
+            )?;
+            out.write_all(sequence.as_bytes())?;
+            queue!(out, RestorePosition)?;
+            out.flush()?;
+            self.shown = Some((song, area));
+        }
+        Ok(false)
+    }
+
+    /// Removes the Kitty images from the screen, e.g. before exiting
+    pub fn clear<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        if self.shown.take().is_some() && self.protocol == GraphicsProtocol::Kitty {
+            out.write_all(b"\x1b_Ga=d,d=A,q=2\x1b\\")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn rendered(&mut self, area: Rect, graphics: bool) -> Option<&Rendered> {
+        let image = self.image.as_ref()?;
+        let protocol = self.protocol;
+
+        Some(
+            self.cache
+                .entry((area.width, area.height, graphics))
+                .or_insert_with(|| render_image(image, protocol, area, graphics)),
+        )
+    }
+}
+
+fn render_image(
+    image: &DynamicImage,
+    protocol: GraphicsProtocol,
+    area: Rect,
+    graphics: bool,
+) -> Rendered {
+    if !graphics {
+        return Rendered::Blocks(half_blocks(image, area));
+    }
+
+    let (cell_width, cell_height) = cell_size();
+    let scaled = image
+        .resize(
+            u32::from(area.width) * cell_width,
+            u32::from(area.height) * cell_height,
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+
+    let columns = scaled.width().div_ceil(cell_width) as u16;
+    let rows = scaled.height().div_ceil(cell_height) as u16;
+    let offset = (
+        area.width.saturating_sub(columns) / 2,
+        area.height.saturating_sub(rows) / 2,
+    );
+
+    let sequence = match protocol {
+        GraphicsProtocol::Kitty => kitty_sequence(&scaled),
+        _ => sixel_sequence(&scaled),
+    };
+    Rendered::Graphics { sequence, offset }
+}
+
+/// Size of a terminal cell in pixels
+fn cell_size() -> (u32, u32) {
+    terminal::window_size()
+        .ok()
+        .filter(|size| size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0)
+        .map(|size| {
+            (
+                u32::from(size.width / size.columns),
+                u32::from(size.height / size.rows),
+            )
+        })
+        .unwrap_or(DEFAULT_CELL_SIZE)
+}
+
+/// Two pixels per cell: the upper one as the foreground of "▀", the lower one as its background
+fn half_blocks(image: &DynamicImage, area: Rect) -> Vec<Spans<'static>> {
+    let scaled = image
+        .resize(
+            u32::from(area.width),
+            u32::from(area.height) * 2,
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+
+    let padding = " ".repeat((u32::from(area.width).saturating_sub(scaled.width()) / 2) as usize);
+    let rows = scaled.height().div_ceil(2);
+    let mut lines =
+        vec![Spans::default(); (u32::from(area.height).saturating_sub(rows) / 2) as usize];
+
+    for y in (0..scaled.height()).step_by(2) {
+        let mut spans = vec![Span::raw(padding.clone())];
+        for x in 0..scaled.width() {
+            let [r, g, b] = scaled.get_pixel(x, y).0;
+            let mut style = Style::default().fg(Color::Rgb(r, g, b));
+            if y + 1 < scaled.height() {
+                let [r, g, b] = scaled.get_pixel(x, y + 1).0;
+                style = style.bg(Color::Rgb(r, g, b));
+            }
+            spans.push(Span::styled("▀", style));
+        }
+        lines.push(Spans::from(spans));
+    }
+
+    lines
+}
+
+/// Transmits the image as PNG and shows it at the cursor, without moving the cursor
+fn kitty_sequence(image: &RgbImage) -> String {
+    let mut png = Vec::new();
+    if image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .is_err()
+    {
+        return String::new();
+    }
+
+    let data = STANDARD.encode(png);
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+
+    let mut sequence = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            sequence.push_str(&format!(
+                "\x1b_Gf=100,a=T,q=2,C=1,m={};{}\x1b\\",
+                more, chunk
+            ));
+        } else {
+            sequence.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    sequence
+}
+
+fn sixel_color_index(pixel: [u8; 3]) -> usize {
+    let level = |channel: u8| (u32::from(channel) * (SIXEL_LEVELS - 1) + 127) / 255;
+    let [r, g, b] = pixel;
+    (level(r) * SIXEL_LEVELS * SIXEL_LEVELS + level(g) * SIXEL_LEVELS + level(b)) as usize
+}
+
+/// Appends a run of one sixel character, compressed with `!` when that is shorter
+fn push_sixel_run(sequence: &mut String, sixel: char, count: usize) {
+    if count > 3 {
+        sequence.push_str(&format!("!{}{}", count, sixel));
+    } else {
+        sequence.extend(std::iter::repeat_n(sixel, count));
+    }
+}
+
+/// Encodes the image as sixels, using a fixed palette of 216 colours
+fn sixel_sequence(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let palette_size = (SIXEL_LEVELS * SIXEL_LEVELS * SIXEL_LEVELS) as usize;
+    let indices: Vec<usize> = image
+        .pixels()
+        .map(|pixel| sixel_color_index(pixel.0))
+        .collect();
+
+    let mut sequence = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    let percent = |level: usize| level * 100 / (SIXEL_LEVELS as usize - 1);
+    for color in 0..palette_size {
+        let levels = SIXEL_LEVELS as usize;
+        sequence.push_str(&format!(
+            "#{};2;{};{};{}",
+            color,
+            percent(color / (levels * levels)),
+            percent(color / levels % levels),
+            percent(color % levels)
+        ));
+    }
+
+    for band_top in (0..height).step_by(6) {
+        let band_rows = (band_top..height.min(band_top + 6)).collect::<Vec<_>>();
+        let mut used = vec![false; palette_size];
+        for &y in &band_rows {
+            for x in 0..width {
+                used[indices[(y * width + x) as usize]] = true;
+            }
+        }
+
+        for color in (0..palette_size).filter(|&color| used[color]) {
+            sequence.push_str(&format!("#{}", color));
+
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..width {
+                let bits = band_rows
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &y)| indices[(y * width + x) as usize] == color)
+                    .fold(0u8, |bits, (bit, _)| bits | (1 << bit));
+                let sixel = char::from(63 + bits);
+
+                run = match run {
+                    Some((current, count)) if current == sixel => Some((current, count + 1)),
+                    Some((current, count)) => {
+                        push_sixel_run(&mut sequence, current, count);
+                        Some((sixel, 1))
+                    }
+                    None => Some((sixel, 1)),
+                };
+            }
+            if let Some((current, count)) = run {
+                push_sixel_run(&mut sequence, current, count);
+            }
+            sequence.push('$');
+        }
+        sequence.push('-');
+    }
+
+    sequence.push_str("\x1b\\");
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn detect(vars: &[(&str, &str)]) -> GraphicsProtocol {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        GraphicsProtocol::detect_from(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn detects_the_protocol_from_the_terminal() {
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), GraphicsProtocol::Kitty);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "WezTerm")]),
+            GraphicsProtocol::Kitty
+        );
+        assert_eq!(detect(&[("TERM", "foot")]), GraphicsProtocol::Sixel);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color")]),
+            GraphicsProtocol::HalfBlocks
+        );
+        assert_eq!(detect(&[]), GraphicsProtocol::HalfBlocks);
+        // Escape sequences would not make it through tmux
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux-1000/default")]),
+            GraphicsProtocol::HalfBlocks
+        );
+        // ALBUM_ART overrides the guess
+        assert_eq!(
+            detect(&[("TERM", "foot"), ("ALBUM_ART", "Off")]),
+            GraphicsProtocol::Off
+        );
+        assert_eq!(
+            detect(&[("TMUX", "1"), ("ALBUM_ART", "kitty")]),
+            GraphicsProtocol::Kitty
+        );
+    }
+
+    #[test]
+    fn half_blocks_pair_up_rows_and_center_the_image() {
+        let image = RgbImage::from_fn(2, 4, |_, y| {
+            if y % 2 == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+        let lines = half_blocks(&DynamicImage::ImageRgb8(image), Rect::new(0, 0, 6, 2));
+
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            assert_eq!(line.0[0].content, "  ");
+            assert_eq!(line.0.len(), 3);
+            for span in &line.0[1..] {
+                assert_eq!(span.content, "▀");
+                assert_eq!(span.style.fg, Some(Color::Rgb(255, 0, 0)));
+                assert_eq!(span.style.bg, Some(Color::Rgb(0, 0, 255)));
+            }
+        }
+
+        // Scaled up to 4x4 pixels, which is two rows of cells in the middle of four
+        let image = RgbImage::from_pixel(1, 1, Rgb([10, 20, 30]));
+        let lines = half_blocks(&DynamicImage::ImageRgb8(image), Rect::new(0, 0, 4, 4));
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].0.is_empty());
+        assert_eq!(lines[1].0.len(), 5);
+    }
+
+    #[test]
+    fn odd_heights_leave_the_last_row_without_background() {
+        let image = RgbImage::from_pixel(1, 3, Rgb([10, 20, 30]));
+        let lines = half_blocks(&DynamicImage::ImageRgb8(image), Rect::new(0, 0, 1, 2));
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0[1].style.bg, Some(Color::Rgb(10, 20, 30)));
+        assert_eq!(lines[1].0[1].style.bg, None);
+    }
+}
@@ -1,30 +1,68 @@
-use crate::models::video::Video;
+use crate::library::index::LibraryIndex;
+use crate::models::video::{LiveStatus, Video};
+use crate::services::youtube::search_filters::SearchFilters;
+use crate::services::youtube::youtube_service::YoutubeService;
 use crate::tui::app::Pane;
+use crate::tui::ui::album_art::AlbumArt;
 use crate::tui::ui::color_theme::ColorTheme;
+use crate::tui::ui::duplicates::DuplicatesReview;
+use crate::tui::ui::file_browser::FileBrowser;
+use crate::tui::ui::list_filter::ListFilter;
+use crate::tui::ui::lyrics::LyricsView;
+use crate::tui::ui::metadata_review::MetadataReview;
 use crate::tui::ui::notification::Notification;
+use crate::tui::ui::prompt::Prompt;
+use crate::tui::ui::stats_view::StatsView;
+use crate::tui::ui::tag_editor::TagEditor;
+use crate::tui::ui::verify_report::VerifyReport;
 use crate::tui::ui::{playback::Playback, playlist::Playlist, queue::Queue, search_bar::SearchBar};
+use crate::utils::format::{format_clock, format_count};
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
-use tui::style::Style;
-use tui::widgets::{Block, Borders, List, ListItem};
+use tui::style::{Modifier, Style};
+use tui::text::Span;
+use tui::widgets::{Block, Borders, Cell, Row, Table, TableState};
 use tui::Frame;
 
+/// Search results longer than any single song, like hour long loops and full albums
+const LONG_VIDEO_SECONDS: f64 = 20.0 * 60.0;
+
 pub struct LayoutBuilder<'a> {
     frame: Option<Rect>,
     search_bar: Option<SearchBar>,
     playlist: Option<Playlist>,
     queue: Option<Queue>,
     playback: Option<Playback>,
+    album_art: Option<&'a mut AlbumArt>,
+    lyrics: Option<LyricsView>,
     search_results: Option<Vec<Video>>,
     selected_pane: Option<&'a Pane>,
     selected_search_index: Option<usize>,
+    total_search_results: Option<u64>,
+    loading_search_page: bool,
+    search_filters: Option<&'a SearchFilters>,
     selected_playlist_song_index: usize,
     notification: Option<&'a Notification>,
     downloading_video_index: Option<usize>,
     selected_queue_song_index: usize,
+    duplicates: Option<DuplicatesReview>,
+    metadata_review: Option<MetadataReview>,
+    verify_report: Option<VerifyReport>,
+    stats_view: Option<StatsView>,
+    browser: Option<&'a FileBrowser>,
+    filter: Option<ListFilter>,
+    prompt: Option<Prompt>,
+    tag_editor: Option<TagEditor>,
+    library: Option<&'a LibraryIndex>,
     theme: ColorTheme,
 }
 
+impl Default for LayoutBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> LayoutBuilder<'a> {
     pub fn new() -> Self {
         LayoutBuilder {
@@ -33,13 +71,27 @@ impl<'a> LayoutBuilder<'a> {
             playlist: None,
             queue: None,
             playback: None,
+            album_art: None,
+            lyrics: None,
             search_results: None,
             selected_pane: None,
             selected_search_index: None,
+            total_search_results: None,
+            loading_search_page: false,
+            search_filters: None,
             selected_playlist_song_index: 0,
             downloading_video_index: None,
             selected_queue_song_index: 0,
             notification: None,
+            duplicates: None,
+            metadata_review: None,
+            verify_report: None,
+            stats_view: None,
+            browser: None,
+            filter: None,
+            prompt: None,
+            tag_editor: None,
+            library: None,
             theme: ColorTheme::catppuccin_mocha(),
         }
     }
@@ -84,6 +136,29 @@ impl<'a> LayoutBuilder<'a> {
         self
     }
 
+    pub fn album_art(mut self, album_art: &'a mut AlbumArt) -> Self {
+        self.album_art = Some(album_art);
+        self
+    }
+
+    pub fn lyrics(mut self, lyrics: LyricsView) -> Self {
+        self.lyrics = Some(lyrics);
+        self
+    }
+
+    /// YouTube's estimate of all matches, and whether another page is on its way
+    pub fn search_progress(mut self, total_results: Option<u64>, loading: bool) -> Self {
+        self.total_search_results = total_results;
+        self.loading_search_page = loading;
+        self
+    }
+
+    /// Filters the shown search results were found with, named in the title
+    pub fn search_filters(mut self, search_filters: &'a SearchFilters) -> Self {
+        self.search_filters = Some(search_filters);
+        self
+    }
+
     pub fn search_results(mut self, search_results: Option<Vec<Video>>) -> Self {
         self.search_results = search_results;
         self
@@ -104,6 +179,51 @@ impl<'a> LayoutBuilder<'a> {
         self
     }
 
+    pub fn duplicates(mut self, duplicates: Option<DuplicatesReview>) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn metadata_review(mut self, metadata_review: Option<MetadataReview>) -> Self {
+        self.metadata_review = metadata_review;
+        self
+    }
+
+    pub fn verify_report(mut self, verify_report: Option<VerifyReport>) -> Self {
+        self.verify_report = verify_report;
+        self
+    }
+
+    pub fn browser(mut self, browser: &'a FileBrowser) -> Self {
+        self.browser = Some(browser);
+        self
+    }
+
+    pub fn stats_view(mut self, stats_view: Option<StatsView>) -> Self {
+        self.stats_view = stats_view;
+        self
+    }
+
+    pub fn filter(mut self, filter: Option<ListFilter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: Option<Prompt>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn tag_editor(mut self, tag_editor: Option<TagEditor>) -> Self {
+        self.tag_editor = tag_editor;
+        self
+    }
+
+    pub fn library(mut self, library: &'a LibraryIndex) -> Self {
+        self.library = Some(library);
+        self
+    }
+
     pub fn build<B: Backend>(self, f: &mut Frame<B>) {
         let main_chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -117,7 +237,14 @@ impl<'a> LayoutBuilder<'a> {
 
         let right_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref()) // Top: Queue, Bottom: Playback
+            .constraints(
+                [
+                    Constraint::Percentage(40),
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(25),
+                ]
+                .as_ref(),
+            ) // Top: Queue, Middle: Lyrics, Bottom: Playback
             .split(main_chunks[1]);
 
         if let Some(search_bar) = self.search_bar {
@@ -129,13 +256,27 @@ impl<'a> LayoutBuilder<'a> {
             search_bar.render_with_style(f, left_chunks[0], style);
         }
 
-        if let Some(playlist) = self.playlist.as_ref() {
+        // The browser takes the place of the playlist while it is selected
+        let browsing = matches!(self.selected_pane, Some(Pane::Browser));
+        if let Some(browser) = self.browser.filter(|_| browsing) {
+            browser.render_with_style(f, left_chunks[1], Style::default().fg(self.theme.accent1));
+        } else if let Some(playlist) = self.playlist.as_ref() {
             let style = if matches!(self.selected_pane, Some(Pane::Playlist)) {
                 Style::default().fg(self.theme.accent1)
             } else {
                 Style::default().fg(self.theme.text)
             };
-            playlist.render_with_style(f, left_chunks[1], style, self.selected_playlist_song_index);
+            match self.filter.as_ref() {
+                Some(filter) if filter.pane == Pane::Playlist => {
+                    filter.render_with_style(f, left_chunks[1], style)
+                }
+                _ => playlist.render_with_style(
+                    f,
+                    left_chunks[1],
+                    style,
+                    self.selected_playlist_song_index,
+                ),
+            }
         }
 
         if let Some(queue) = self.queue {
@@ -144,7 +285,17 @@ impl<'a> LayoutBuilder<'a> {
             } else {
                 Style::default().fg(self.theme.text)
             };
-            queue.render_with_style(f, right_chunks[0], style, self.selected_queue_song_index);
+            match self.filter.as_ref() {
+                Some(filter) if filter.pane == Pane::Queue => {
+                    filter.render_with_style(f, right_chunks[0], style)
+                }
+                _ => queue.render_with_style(
+                    f,
+                    right_chunks[0],
+                    style,
+                    self.selected_queue_song_index,
+                ),
+            }
         }
 
         if let Some(playback) = self.playback {
@@ -153,7 +304,26 @@ impl<'a> LayoutBuilder<'a> {
             } else {
                 Style::default().fg(self.theme.text)
             };
-            playback.render_with_style(f, right_chunks[1], style);
+            let popup_open = self.search_results.is_some()
+                || (self.duplicates.is_some()
+                    && matches!(self.selected_pane, Some(Pane::Duplicates)))
+                || (self.metadata_review.is_some()
+                    && matches!(self.selected_pane, Some(Pane::MetadataReview)))
+                || (self.verify_report.is_some()
+                    && matches!(self.selected_pane, Some(Pane::VerifyReport)))
+                || (self.stats_view.is_some() && matches!(self.selected_pane, Some(Pane::Stats)))
+                || self.tag_editor.is_some()
+                || self.prompt.is_some();
+            playback.render_with_style(f, right_chunks[2], style, self.album_art, popup_open);
+        }
+
+        if let Some(lyrics) = self.lyrics {
+            let style = if matches!(self.selected_pane, Some(Pane::Lyrics)) {
+                Style::default().fg(self.theme.accent2)
+            } else {
+                Style::default().fg(self.theme.text)
+            };
+            lyrics.render_with_style(f, right_chunks[1], style);
         }
 
         if let Some(search_results) = &self.search_results {
@@ -164,21 +334,19 @@ impl<'a> LayoutBuilder<'a> {
                 height: self.frame.unwrap().height / 4, // Take 1/4 of the screen height
             };
 
-            // Borrow `self.playlist` once
-            let playlist_ref = self.playlist.as_ref();
-
-            let items: Vec<ListItem> = search_results
+            let long_style = Style::default().fg(self.theme.error);
+            let mut rows: Vec<Row> = search_results
                 .iter()
                 .enumerate()
                 .map(|(i, video)| {
-                    // Check if the video is already downloaded by referencing the borrowed playlist
-                    let already_downloaded = if let Some(playlist) = playlist_ref {
-                        playlist.songs.iter().any(|song| {
-                            song.title.ends_with(".mp3")
-                                && song.title == format!("{}.mp3", video.snippet.title)
-                        })
-                    } else {
-                        false // If playlist is None, assume the video hasn't been downloaded
+                    // Check the whole library, the playlist may only show a smart playlist.
+                    // Songs are matched by video ID, so renamed files still count.
+                    let already_downloaded = match (self.library, &video.id.video_id) {
+                        (Some(library), Some(video_id)) => {
+                            YoutubeService::downloaded_song(library, video_id, &video.snippet)
+                                .is_some()
+                        }
+                        _ => false, // Without a library, assume the video hasn't been downloaded
                     };
 
                     // Determine which symbol to show
@@ -190,34 +358,173 @@ impl<'a> LayoutBuilder<'a> {
                         "⚠️" // Yellow warning symbol for not downloaded yet
                     };
 
-                    // Format the content for each search result item
-                    let content = format!(
-                        "{} - {} {}",
-                        video.snippet.title, video.snippet.channel_title, download_status_symbol
-                    );
+                    // Live streams and hour long loops stand out, they are rarely worth downloading
+                    let duration = match (video.snippet.live_status(), video.duration()) {
+                        (LiveStatus::Live, _) => Span::styled("LIVE", long_style),
+                        (LiveStatus::Upcoming, _) => Span::styled("upcoming", long_style),
+                        (_, Some(duration)) if duration > LONG_VIDEO_SECONDS => {
+                            Span::styled(format_clock(duration), long_style)
+                        }
+                        (_, Some(duration)) => Span::raw(format_clock(duration)),
+                        (_, None) => Span::raw(""),
+                    };
+
+                    let row = Row::new(vec![
+                        Cell::from(download_status_symbol),
+                        Cell::from(video.snippet.title.clone()),
+                        Cell::from(video.snippet.channel_title.clone()),
+                        Cell::from(duration),
+                        Cell::from(video.view_count().map(format_count).unwrap_or_default()),
+                        Cell::from(
+                            video
+                                .snippet
+                                .published_date()
+                                .unwrap_or_default()
+                                .to_string(),
+                        ),
+                    ]);
 
                     // Highlight the selected search result
                     if Some(i) == self.selected_search_index {
-                        ListItem::new(content).style(Style::default().fg(self.theme.accent2))
+                        row.style(Style::default().fg(self.theme.accent2))
                     } else {
-                        ListItem::new(content).style(Style::default().fg(self.theme.text))
+                        row.style(Style::default().fg(self.theme.text))
                     }
                 })
                 .collect();
 
-            let search_result_list = List::new(items).block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Search Results")
-                    .style(
-                        Style::default()
-                            .bg(self.theme.background)
-                            .fg(self.theme.text),
-                    )
-                    .border_style(Style::default().fg(self.theme.accent1)),
-            );
-
-            f.render_widget(search_result_list, search_overlay);
+            if self.loading_search_page {
+                rows.push(
+                    Row::new(vec!["⏳", "Loading more results..."])
+                        .style(Style::default().fg(self.theme.accent1)),
+                );
+            }
+
+            let mut title = match self.total_search_results {
+                Some(total) => format!(
+                    "Search Results ({} of about {})",
+                    search_results.len(),
+                    format_count(total)
+                ),
+                None => "Search Results".to_string(),
+            };
+            if let Some(filters) = self.search_filters.filter(|filters| !filters.is_empty()) {
+                title = format!("{} [{}]", title, filters);
+            }
+
+            // Keep the selection in view as more pages are appended
+            let mut state = TableState::default();
+            state.select(self.selected_search_index);
+
+            let search_result_table = Table::new(rows)
+                .header(
+                    Row::new(vec!["", "Title", "Channel", "Length", "Views", "Uploaded"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .widths(&[
+                    Constraint::Length(2),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(20),
+                    Constraint::Length(8),
+                    Constraint::Length(6),
+                    Constraint::Length(10),
+                ])
+                .column_spacing(1)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(title)
+                        .style(
+                            Style::default()
+                                .bg(self.theme.background)
+                                .fg(self.theme.text),
+                        )
+                        .border_style(Style::default().fg(self.theme.accent1)),
+                );
+
+            f.render_stateful_widget(search_result_table, search_overlay, &mut state);
+        }
+
+        if let Some(duplicates) = &self.duplicates {
+            if matches!(self.selected_pane, Some(Pane::Duplicates)) {
+                let frame = self.frame.unwrap();
+                let overlay = Rect {
+                    x: 2,
+                    y: 3,
+                    width: frame.width.saturating_sub(4),
+                    height: frame.height.saturating_sub(6),
+                };
+                duplicates.render_with_style(f, overlay, Style::default().fg(self.theme.accent1));
+            }
+        }
+
+        if let Some(verify_report) = &self.verify_report {
+            if matches!(self.selected_pane, Some(Pane::VerifyReport)) {
+                let frame = self.frame.unwrap();
+                let overlay = Rect {
+                    x: 2,
+                    y: 3,
+                    width: frame.width.saturating_sub(4),
+                    height: frame.height.saturating_sub(6),
+                };
+                verify_report.render_with_style(
+                    f,
+                    overlay,
+                    Style::default().fg(self.theme.accent1),
+                );
+            }
+        }
+
+        if let Some(stats_view) = &self.stats_view {
+            if matches!(self.selected_pane, Some(Pane::Stats)) {
+                let frame = self.frame.unwrap();
+                let overlay = Rect {
+                    x: 2,
+                    y: 3,
+                    width: frame.width.saturating_sub(4),
+                    height: frame.height.saturating_sub(6),
+                };
+                stats_view.render_with_style(f, overlay, Style::default().fg(self.theme.accent1));
+            }
+        }
+
+        if let Some(metadata_review) = &self.metadata_review {
+            if matches!(self.selected_pane, Some(Pane::MetadataReview)) {
+                let frame = self.frame.unwrap();
+                let overlay = Rect {
+                    x: 2,
+                    y: frame.height / 4,
+                    width: frame.width.saturating_sub(4),
+                    height: 13.min(frame.height),
+                };
+                metadata_review.render_with_style(
+                    f,
+                    overlay,
+                    Style::default().fg(self.theme.accent1),
+                );
+            }
+        }
+
+        if let Some(tag_editor) = &self.tag_editor {
+            let frame = self.frame.unwrap();
+            let overlay = Rect {
+                x: 2,
+                y: frame.height / 4,
+                width: frame.width.saturating_sub(4),
+                height: 9.min(frame.height),
+            };
+            tag_editor.render_with_style(f, overlay, Style::default().fg(self.theme.accent1));
+        }
+
+        if let Some(prompt) = &self.prompt {
+            let frame = self.frame.unwrap();
+            let overlay = Rect {
+                x: 2,
+                y: frame.height / 3,
+                width: frame.width.saturating_sub(4),
+                height: 4.min(frame.height),
+            };
+            prompt.render_with_style(f, overlay, Style::default().fg(self.theme.accent1));
         }
     }
 }
@@ -0,0 +1,212 @@
+use crate::library::duplicates::{DuplicateGroup, DuplicateKind};
+use crate::models::song::Song;
+use crate::tui::ui::color_theme::ColorTheme;
+use crate::utils::format::format_duration;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use tui::Frame;
+
+/// Review pane listing groups of duplicate songs, where the user picks the copy to keep
+#[derive(Clone)]
+pub struct DuplicatesReview {
+    pub groups: Vec<DuplicateGroup>,
+    pub selected_group: usize,
+    pub selected_song: usize,
+    /// Whether the files that keeping the selected song deletes are shown for confirmation
+    pub confirming: bool,
+}
+
+impl DuplicatesReview {
+    pub fn new(groups: Vec<DuplicateGroup>) -> Self {
+        DuplicatesReview {
+            groups,
+            selected_group: 0,
+            selected_song: 0,
+            confirming: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The copies that keeping the selected song deletes
+    pub fn selected_duplicates(&self) -> Vec<&Song> {
+        self.groups
+            .get(self.selected_group)
+            .map(|group| {
+                group
+                    .songs
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != self.selected_song)
+                    .map(|(_, song)| song)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Moves the selection through the songs of all groups
+    pub fn next(&mut self) {
+        self.confirming = false;
+        let Some(group) = self.groups.get(self.selected_group) else {
+            return;
+        };
+        if self.selected_song + 1 < group.songs.len() {
+            self.selected_song += 1;
+        } else if self.selected_group + 1 < self.groups.len() {
+            self.selected_group += 1;
+            self.selected_song = 0;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.confirming = false;
+        if self.selected_song > 0 {
+            self.selected_song -= 1;
+        } else if self.selected_group > 0 {
+            self.selected_group -= 1;
+            self.selected_song = self.groups[self.selected_group].songs.len() - 1;
+        }
+    }
+
+    /// Removes the selected group, returning the song to keep and the ones to merge into it
+    pub fn take_selected(&mut self) -> Option<(Song, Vec<Song>)> {
+        if self.selected_group >= self.groups.len() {
+            return None;
+        }
+
+        let mut group = self.groups.remove(self.selected_group);
+        let keep = group
+            .songs
+            .remove(self.selected_song.min(group.songs.len() - 1));
+        self.clamp_selection();
+
+        Some((keep, group.songs))
+    }
+
+    /// Leaves the selected group alone and drops it from the review
+    pub fn skip_selected(&mut self) {
+        if self.selected_group < self.groups.len() {
+            self.groups.remove(self.selected_group);
+            self.clamp_selection();
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        self.confirming = false;
+        self.selected_song = 0;
+        if self.selected_group >= self.groups.len() {
+            self.selected_group = self.groups.len().saturating_sub(1);
+        }
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let mut items = Vec::new();
+        let mut selected_row = 0;
+
+        for (group_index, group) in self.groups.iter().enumerate() {
+            let kind = match group.kind {
+                DuplicateKind::Identical => "identical files",
+                DuplicateKind::Similar => "sounds the same",
+            };
+            items.push(ListItem::new(Spans::from(Span::styled(
+                format!("Group {} ({})", group_index + 1, kind),
+                Style::default()
+                    .fg(theme.accent1)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+
+            for (song_index, song) in group.songs.iter().enumerate() {
+                let selected =
+                    group_index == self.selected_group && song_index == self.selected_song;
+                if selected {
+                    selected_row = items.len();
+                }
+
+                let spans = Spans::from(vec![
+                    Span::raw(format!("  {}", song.title)),
+                    Span::styled(
+                        format!(" [{}] {}", format_duration(song.duration), song.codec),
+                        Style::default().fg(theme.accent2),
+                    ),
+                    Span::styled(
+                        format!("  {}", song.path.display()),
+                        Style::default().fg(theme.text),
+                    ),
+                ]);
+
+                items.push(ListItem::new(spans).style(if selected {
+                    Style::default().fg(theme.highlight)
+                } else {
+                    Style::default().fg(theme.text)
+                }));
+            }
+        }
+
+        if items.is_empty() {
+            items.push(ListItem::new("No duplicates found"));
+        }
+
+        let mut state = ListState::default();
+        state.select(Some(selected_row));
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Duplicates [Enter: keep selected, x: skip group, Esc: close]")
+                    .style(Style::default().bg(theme.background).fg(theme.text))
+                    .border_style(style),
+            )
+            .highlight_symbol("> ");
+
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+
+        if self.confirming {
+            self.render_confirmation(f, area, style);
+        }
+    }
+
+    /// Lists the files that are about to be deleted below the review
+    fn render_confirmation<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+        let duplicates = self.selected_duplicates();
+
+        let mut lines = vec![Spans::from(Span::styled(
+            format!(
+                "Keeping the selected song deletes these {} files from disk:",
+                duplicates.len()
+            ),
+            Style::default().fg(theme.error),
+        ))];
+        lines.extend(
+            duplicates
+                .iter()
+                .map(|song| Spans::from(format!("  {}", song.path.display()))),
+        );
+
+        let height = (lines.len() as u16 + 2).min(area.height / 2).max(3);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(height)])
+            .split(area);
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Delete duplicates? [Enter: delete, Esc: cancel]")
+                .style(Style::default().bg(theme.background).fg(theme.text))
+                .border_style(style),
+        );
+
+        f.render_widget(Clear, chunks[1]);
+        f.render_widget(paragraph, chunks[1]);
+    }
+}
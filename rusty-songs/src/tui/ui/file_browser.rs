@@ -0,0 +1,325 @@
+use crate::library::index::is_audio_file;
+use crate::tui::ui::color_theme::ColorTheme;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState};
+use tui::Frame;
+
+/// A folder or audio file shown in the browser tree
+#[derive(Clone)]
+pub struct BrowserNode {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+    /// Roots opened by hand rather than configured as library roots
+    pub external: bool,
+}
+
+impl BrowserNode {
+    fn new(path: PathBuf, depth: usize, is_dir: bool) -> Self {
+        BrowserNode {
+            path,
+            depth,
+            is_dir,
+            expanded: false,
+            external: false,
+        }
+    }
+
+    fn name(&self) -> String {
+        if self.depth == 0 {
+            return self.path.display().to_string();
+        }
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Folders first, then audio files, each by name. Hidden entries are left out.
+fn read_children(dir: &Path, depth: usize) -> Vec<BrowserNode> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut children: Vec<BrowserNode> = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                Some(BrowserNode::new(path, depth, true))
+            } else if is_audio_file(&path) {
+                Some(BrowserNode::new(path, depth, false))
+            } else {
+                None
+            }
+        })
+        .collect();
+    children.sort_by_cached_key(|node| (!node.is_dir, node.name().to_lowercase()));
+    children
+}
+
+/// Tree of the library roots, plus any folders opened from outside the library
+#[derive(Clone)]
+pub struct FileBrowser {
+    /// The visible rows, children following their expanded parent
+    pub nodes: Vec<BrowserNode>,
+    pub selected: usize,
+}
+
+impl FileBrowser {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        FileBrowser {
+            nodes: roots
+                .into_iter()
+                .map(|root| BrowserNode::new(root, 0, true))
+                .collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected_node(&self) -> Option<&BrowserNode> {
+        self.nodes.get(self.selected)
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.nodes.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Index just past the last descendant of the node at `index`
+    fn subtree_end(&self, index: usize) -> usize {
+        let depth = self.nodes[index].depth;
+        self.nodes[index + 1..]
+            .iter()
+            .position(|node| node.depth <= depth)
+            .map_or(self.nodes.len(), |offset| index + 1 + offset)
+    }
+
+    fn expand_at(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        if !node.is_dir || node.expanded {
+            return;
+        }
+
+        let children = read_children(&node.path, node.depth + 1);
+        self.nodes[index].expanded = true;
+        self.nodes.splice(index + 1..index + 1, children);
+    }
+
+    fn collapse_at(&mut self, index: usize) {
+        let end = self.subtree_end(index);
+        self.nodes.drain(index + 1..end);
+        self.nodes[index].expanded = false;
+    }
+
+    pub fn expand(&mut self) {
+        if self.selected < self.nodes.len() {
+            self.expand_at(self.selected);
+        }
+    }
+
+    /// Collapses the selected folder, or moves up to the parent when it is already closed
+    pub fn collapse(&mut self) {
+        let Some(node) = self.nodes.get(self.selected) else {
+            return;
+        };
+
+        if node.is_dir && node.expanded {
+            self.collapse_at(self.selected);
+        } else if node.depth > 0 {
+            let depth = node.depth;
+            if let Some(parent) = self.nodes[..self.selected]
+                .iter()
+                .rposition(|node| node.depth < depth)
+            {
+                self.selected = parent;
+            }
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        match self.nodes.get(self.selected) {
+            Some(node) if node.expanded => self.collapse_at(self.selected),
+            Some(_) => self.expand_at(self.selected),
+            None => {}
+        }
+    }
+
+    /// Shows a folder from outside the library as an extra root and selects it.
+    /// For a file, its folder is opened with the file selected.
+    pub fn open(&mut self, path: &Path) {
+        let (dir, file) = if path.is_dir() {
+            (path.to_path_buf(), None)
+        } else {
+            (
+                path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+                Some(path),
+            )
+        };
+
+        let root = match self
+            .nodes
+            .iter()
+            .position(|node| node.depth == 0 && node.path == dir)
+        {
+            Some(root) => root,
+            None => {
+                let mut node = BrowserNode::new(dir, 0, true);
+                node.external = true;
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+
+        self.expand_at(root);
+        self.selected = file
+            .and_then(|file| {
+                self.nodes[root..self.subtree_end(root)]
+                    .iter()
+                    .position(|node| node.path == file)
+                    .map(|offset| root + offset)
+            })
+            .unwrap_or(root);
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let items: Vec<ListItem> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let marker = match (node.is_dir, node.expanded) {
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                    (false, _) => "  ",
+                };
+                let name_style = if i == self.selected {
+                    Style::default().fg(theme.highlight)
+                } else if node.is_dir {
+                    Style::default().fg(theme.accent1)
+                } else {
+                    Style::default().fg(theme.text)
+                };
+
+                let mut spans = vec![
+                    Span::raw("  ".repeat(node.depth)),
+                    Span::styled(marker, Style::default().fg(theme.accent2)),
+                    Span::styled(node.name(), name_style),
+                ];
+                if node.external {
+                    spans.push(Span::styled(
+                        " (outside library)",
+                        Style::default()
+                            .fg(theme.accent2)
+                            .add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                ListItem::new(Spans::from(spans))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Browser [6] [Enter: open/play, a: queue, p: play all, o: open path]")
+                .border_style(style),
+        );
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// root/{b.mp3, notes.txt, .hidden.mp3, Album/{1.mp3, 2.mp3}}
+    fn library(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!(
+            "rusty_songs_browser_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Album")).unwrap();
+        for file in [
+            "b.mp3",
+            "notes.txt",
+            ".hidden.mp3",
+            "Album/1.mp3",
+            "Album/2.mp3",
+        ] {
+            fs::write(root.join(file), b"").unwrap();
+        }
+        root
+    }
+
+    fn names(browser: &FileBrowser) -> Vec<String> {
+        browser.nodes[1..].iter().map(BrowserNode::name).collect()
+    }
+
+    #[test]
+    fn expands_folders_first_and_collapses_whole_subtrees() {
+        let root = library("tree");
+        let mut browser = FileBrowser::new(vec![root.clone()]);
+
+        browser.expand();
+        assert_eq!(names(&browser), vec!["Album", "b.mp3"]);
+
+        browser.next();
+        browser.toggle();
+        assert_eq!(names(&browser), vec!["Album", "1.mp3", "2.mp3", "b.mp3"]);
+        assert_eq!(browser.nodes[2].depth, 2);
+
+        // On a file, collapsing moves up to its folder, and then closes it
+        browser.next();
+        browser.collapse();
+        assert_eq!(browser.selected_node().unwrap().name(), "Album");
+        browser.collapse();
+        assert_eq!(names(&browser), vec!["Album", "b.mp3"]);
+
+        browser.selected = 0;
+        browser.toggle();
+        assert!(names(&browser).is_empty());
+        assert!(!browser.nodes[0].expanded);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn opening_a_file_selects_it_below_its_folder() {
+        let root = library("open");
+        let mut browser = FileBrowser::new(vec![PathBuf::from("/music")]);
+
+        let song = root.join("Album").join("2.mp3");
+        browser.open(&song);
+        assert_eq!(browser.nodes.len(), 4);
+        assert!(browser.nodes[1].external);
+        assert_eq!(browser.selected_node().unwrap().path, song);
+
+        // Opened again, the folder is reused rather than added twice
+        browser.open(&root.join("Album"));
+        assert_eq!(browser.nodes.len(), 4);
+        assert_eq!(browser.selected, 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+use crate::models::song::Song;
+use crate::tui::app::Pane;
+use crate::tui::ui::color_theme::ColorTheme;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Reverse;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use tui::Frame;
+
+/// A song matching the filter query
+#[derive(Clone)]
+pub struct FilterHit {
+    /// Index of the song in the list being filtered
+    pub index: usize,
+    pub label: String,
+    /// Character positions in `label` that matched the query
+    pub matched: Vec<usize>,
+}
+
+/// Narrows the Playlist or Queue down to the songs fuzzily matching what is typed
+#[derive(Clone)]
+pub struct ListFilter {
+    pub pane: Pane,
+    pub query: String,
+    pub hits: Vec<FilterHit>,
+    pub selected: usize,
+}
+
+/// The text a song is matched against: its title, artist and album
+fn label(song: &Song) -> String {
+    let mut parts = vec![song.title.as_str()];
+    if let Some(artist) = &song.tags.artist {
+        parts.push(artist);
+    }
+    if let Some(album) = &song.tags.album {
+        parts.push(album);
+    }
+    parts.join(" - ")
+}
+
+impl ListFilter {
+    pub fn new(pane: Pane) -> Self {
+        ListFilter {
+            pane,
+            query: String::new(),
+            hits: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn update(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn delete(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Matches the query against the songs, best hits first
+    pub fn refresh<'s>(&mut self, songs: impl Iterator<Item = &'s Song>) {
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        let mut scored: Vec<(i64, FilterHit)> = songs
+            .enumerate()
+            .filter_map(|(index, song)| {
+                let label = label(song);
+                if self.query.is_empty() {
+                    return Some((
+                        0,
+                        FilterHit {
+                            index,
+                            label,
+                            matched: Vec::new(),
+                        },
+                    ));
+                }
+
+                let (score, matched) = matcher.fuzzy_indices(&label, &self.query)?;
+                Some((
+                    score,
+                    FilterHit {
+                        index,
+                        label,
+                        matched,
+                    },
+                ))
+            })
+            .collect();
+
+        // Stable, so equally good hits keep the order of the list
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+
+        self.hits = scored.into_iter().map(|(_, hit)| hit).collect();
+        self.selected = self.selected.min(self.hits.len().saturating_sub(1));
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.hits.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Index in the filtered list of the selected hit
+    pub fn selected_index(&self) -> Option<usize> {
+        self.hits.get(self.selected).map(|hit| hit.index)
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let input = Paragraph::new(Spans::from(vec![
+            Span::styled("/", Style::default().fg(theme.accent1)),
+            Span::styled(&self.query, Style::default().fg(theme.highlight)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Filter [Enter: jump, Esc: cancel]"),
+        )
+        .style(style);
+
+        f.render_widget(input, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(i, hit)| {
+                let base = if i == self.selected {
+                    Style::default().fg(theme.highlight)
+                } else {
+                    Style::default().fg(theme.text)
+                };
+                let matched_style = Style::default()
+                    .fg(theme.accent2)
+                    .add_modifier(Modifier::BOLD);
+
+                let spans: Vec<Span> = hit
+                    .label
+                    .chars()
+                    .enumerate()
+                    .map(|(position, c)| {
+                        if hit.matched.contains(&position) {
+                            Span::styled(c.to_string(), matched_style)
+                        } else {
+                            Span::styled(c.to_string(), base)
+                        }
+                    })
+                    .collect();
+
+                ListItem::new(Spans::from(spans))
+            })
+            .collect();
+
+        let title = format!("{} matches", self.hits.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(style);
+
+        let mut state = ListState::default();
+        if !self.hits.is_empty() {
+            state.select(Some(self.selected));
+        }
+
+        f.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::song::{SongStats, TrackTags};
+    use std::path::PathBuf;
+
+    fn song(title: &str, artist: &str) -> Song {
+        Song {
+            title: title.to_string(),
+            duration: 1.0,
+            path: PathBuf::from(format!("{}.mp3", title)),
+            codec: "MP3".to_string(),
+            tags: TrackTags {
+                artist: Some(artist.to_string()),
+                ..TrackTags::default()
+            },
+            stats: SongStats::default(),
+        }
+    }
+
+    fn filter(query: &str, songs: &[Song]) -> ListFilter {
+        let mut filter = ListFilter::new(Pane::Playlist);
+        for c in query.chars() {
+            filter.update(c);
+        }
+        filter.refresh(songs.iter());
+        filter
+    }
+
+    fn matched_text(hit: &FilterHit) -> String {
+        hit.label
+            .chars()
+            .enumerate()
+            .filter(|(position, _)| hit.matched.contains(position))
+            .map(|(_, c)| c)
+            .collect()
+    }
+
+    #[test]
+    fn highlights_the_matched_characters_of_the_label() {
+        let songs = [
+            song("One More Time", "Daft Punk"),
+            song("Money", "Pink Floyd"),
+        ];
+
+        let filter = filter("dp", &songs);
+        assert_eq!(filter.hits.len(), 1);
+        assert_eq!(filter.hits[0].label, "One More Time - Daft Punk");
+        assert_eq!(matched_text(&filter.hits[0]), "DP");
+        assert_eq!(filter.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn best_hits_come_first_and_an_empty_query_keeps_the_order() {
+        let songs = [
+            song("Happy Sunday Lake", "Someone"),
+            song("Around the World", "Daft Punk"),
+        ];
+
+        let mut filter = filter("punk", &songs);
+        assert_eq!(
+            filter.hits.iter().map(|hit| hit.index).collect::<Vec<_>>(),
+            vec![1, 0]
+        );
+        filter.next();
+        filter.next();
+        assert_eq!(filter.selected_index(), Some(0));
+
+        for _ in 0.."punk".len() {
+            filter.delete();
+        }
+        filter.refresh(songs.iter());
+        assert_eq!(
+            filter.hits.iter().map(|hit| hit.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert!(filter.hits.iter().all(|hit| hit.matched.is_empty()));
+
+        filter.update('x');
+        filter.refresh(songs.iter());
+        assert_eq!(filter.selected_index(), None);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::library::lyrics::Lyrics;
+use crate::tui::ui::color_theme::ColorTheme;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Wrap};
+use tui::Frame;
+
+/// Lyrics of the playing song, following the playback position when they are synced
+#[derive(Clone, Default)]
+pub struct LyricsView {
+    pub lyrics: Option<Lyrics>,
+    /// Playback position in seconds
+    pub position: f64,
+    /// User adjustment on top of the file's own offset, positive shows lines earlier
+    pub offset_ms: i64,
+    /// Manual scroll position for unsynced lyrics
+    pub scroll: u16,
+}
+
+impl LyricsView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn scroll_down(&mut self) {
+        let lines = self.lyrics.as_ref().map_or(0, |lyrics| lyrics.lines.len());
+        if usize::from(self.scroll) + 1 < lines {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let title = if self.offset_ms == 0 {
+            "Lyrics [4]".to_string()
+        } else {
+            format!("Lyrics [4] - offset {:+}ms", self.offset_ms)
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+
+        let Some(lyrics) = self
+            .lyrics
+            .as_ref()
+            .filter(|lyrics| !lyrics.lines.is_empty())
+        else {
+            let paragraph = Paragraph::new("No lyrics").block(block).style(style);
+            f.render_widget(paragraph, area);
+            return;
+        };
+
+        let current = lyrics.current_line(self.position, self.offset_ms);
+        let text: Vec<Spans> = lyrics
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if Some(i) == current {
+                    Spans::from(Span::styled(
+                        line.text.clone(),
+                        Style::default()
+                            .fg(theme.highlight)
+                            .add_modifier(Modifier::BOLD),
+                    ))
+                } else if lyrics.is_synced() {
+                    Spans::from(Span::styled(
+                        line.text.clone(),
+                        Style::default().fg(theme.accent2),
+                    ))
+                } else {
+                    Spans::from(line.text.clone())
+                }
+            })
+            .collect();
+
+        // Synced lyrics keep the current line in the middle of the pane
+        let scroll = if lyrics.is_synced() {
+            let half_height = area.height.saturating_sub(2) / 2;
+            current
+                .map_or(0, |line| line as u16)
+                .saturating_sub(half_height)
+        } else {
+            self.scroll
+        };
+
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .style(style)
+            .wrap(Wrap { trim: true })
+            .scroll((scroll, 0));
+
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::services::musicbrainz::musicbrainz_service::MetadataProposal;
+use crate::tui::ui::color_theme::ColorTheme;
+use crate::tui::ui::tag_editor::TagField;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph, Row, Table};
+use tui::Frame;
+
+/// Review pane showing the tags proposed by the metadata lookup next to the current ones
+#[derive(Clone, Default)]
+pub struct MetadataReview {
+    pub proposals: Vec<MetadataProposal>,
+    pub selected: usize,
+    /// Whether the proposed cover of each proposal gets written as well
+    use_cover: Vec<bool>,
+}
+
+impl MetadataReview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, proposal: MetadataProposal) {
+        self.use_cover.push(proposal.cover.is_some());
+        self.proposals.push(proposal);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proposals.is_empty()
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.proposals.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn toggle_cover(&mut self) {
+        if let Some(use_cover) = self.use_cover.get_mut(self.selected) {
+            *use_cover = !*use_cover;
+        }
+    }
+
+    /// Removes a proposal, without its cover if the user turned that off
+    fn take(&mut self, index: usize) -> MetadataProposal {
+        let mut proposal = self.proposals.remove(index);
+        if !self.use_cover.remove(index) {
+            proposal.cover = None;
+        }
+        proposal
+    }
+
+    /// Removes the selected proposal to apply it
+    pub fn take_selected(&mut self) -> Option<MetadataProposal> {
+        if self.selected >= self.proposals.len() {
+            return None;
+        }
+
+        let proposal = self.take(self.selected);
+        self.clamp_selection();
+        Some(proposal)
+    }
+
+    /// Removes every proposal to apply them all
+    pub fn take_all(&mut self) -> Vec<MetadataProposal> {
+        let proposals = (0..self.proposals.len()).map(|_| self.take(0)).collect();
+        self.selected = 0;
+        proposals
+    }
+
+    /// Drops the selected proposal, leaving the song as it is
+    pub fn skip_selected(&mut self) {
+        if self.selected < self.proposals.len() {
+            self.take(self.selected);
+            self.clamp_selection();
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        if self.selected >= self.proposals.len() {
+            self.selected = self.proposals.len().saturating_sub(1);
+        }
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(
+                "Metadata [j/k: song, Enter: accept, a: accept all, x: skip, c: cover, Esc: close]",
+            )
+            .style(Style::default().bg(theme.background).fg(theme.text))
+            .border_style(style);
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        let Some(proposal) = self.proposals.get(self.selected) else {
+            f.render_widget(Paragraph::new("No proposals left"), inner);
+            return;
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+            .split(inner);
+
+        let header = vec![
+            Spans::from(vec![
+                Span::styled(
+                    format!("{}/{} ", self.selected + 1, self.proposals.len()),
+                    Style::default().fg(theme.accent1),
+                ),
+                Span::styled(
+                    proposal.song.title.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("  match score {}%", proposal.score),
+                    Style::default().fg(theme.accent2),
+                ),
+            ]),
+            Spans::from(proposal.song.path.display().to_string()),
+        ];
+        f.render_widget(Paragraph::new(header), chunks[0]);
+
+        let mut rows: Vec<Row> = TagField::ALL
+            .iter()
+            .map(|field| {
+                let current = field.get(&proposal.song.tags).unwrap_or_default();
+                let proposed = field.get(&proposal.tags).unwrap_or_default();
+                let proposed_style = if current == proposed {
+                    Style::default().fg(theme.text)
+                } else {
+                    Style::default()
+                        .fg(theme.highlight)
+                        .add_modifier(Modifier::BOLD)
+                };
+
+                Row::new(vec![
+                    Span::styled(field.label(), Style::default().fg(theme.accent1)),
+                    Span::raw(current),
+                    Span::styled(proposed, proposed_style),
+                ])
+            })
+            .collect();
+
+        let cover = match (&proposal.cover, self.use_cover[self.selected]) {
+            (Some(cover), true) => Span::styled(
+                format!("replace ({} KB)", cover.len() / 1024),
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            (Some(_), false) => Span::raw("keep (press c to replace)"),
+            (None, _) => Span::raw("none found"),
+        };
+        rows.push(Row::new(vec![
+            Span::styled("Cover", Style::default().fg(theme.accent1)),
+            Span::raw(""),
+            cover,
+        ]));
+
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["", "Current", "Proposed"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&[
+                Constraint::Length(8),
+                Constraint::Percentage(45),
+                Constraint::Percentage(45),
+            ]);
+        f.render_widget(table, chunks[1]);
+    }
+}
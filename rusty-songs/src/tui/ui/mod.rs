@@ -1,7 +1,17 @@
+pub mod album_art;
 pub mod builder;
 pub mod color_theme;
+pub mod duplicates;
+pub mod file_browser;
+pub mod list_filter;
+pub mod lyrics;
+pub mod metadata_review;
 pub mod notification;
 pub mod playback;
 pub mod playlist;
+pub mod prompt;
 pub mod queue;
 pub mod search_bar;
+pub mod stats_view;
+pub mod tag_editor;
+pub mod verify_report;
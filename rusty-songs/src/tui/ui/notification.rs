@@ -36,4 +36,3 @@ impl Notification {
         self.timestamp.elapsed() > timeout
     }
 }
-
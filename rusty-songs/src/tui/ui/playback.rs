@@ -1,7 +1,9 @@
+use crate::tui::ui::album_art::AlbumArt;
+use crate::utils::format::format_duration;
 use tui::backend::Backend;
-use tui::layout::Rect;
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, Gauge, Paragraph};
+use tui::widgets::{Block, Borders, Gauge, Paragraph, Wrap};
 use tui::Frame;
 
 #[derive(Clone)]
@@ -9,6 +11,8 @@ pub struct Playback {
     pub current_song: String,
     pub current_time: u64,
     pub total_time: u64,
+    pub codec: String,
+    pub paused: bool,
 }
 
 impl Playback {
@@ -17,28 +21,78 @@ impl Playback {
             current_song: song.to_string(),
             current_time,
             total_time,
+            codec: String::new(),
+            paused: false,
         }
     }
 
-    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+    /// Draws the song info with the cover art on its left, if the song has any.
+    /// `popup_open` keeps terminal graphics from covering a popup.
+    pub fn render_with_style<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        style: Style,
+        album_art: Option<&mut AlbumArt>,
+        popup_open: bool,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+            .split(area);
+
         // Display song info
-        let song_info = format!(
-            "Playing: {} - {}/{}",
-            self.current_song, self.current_time, self.total_time
-        );
-        let paragraph = Paragraph::new(song_info)
-            .block(Block::default().borders(Borders::ALL).title("Now Playing"))
+        let state = if self.paused { "Paused" } else { "Playing" };
+        let song_info = if self.current_song.is_empty() {
+            "Nothing playing".to_string()
+        } else {
+            format!(
+                "{}: {} [{}] - {}/{}",
+                state,
+                self.current_song,
+                self.codec,
+                format_duration(self.current_time as f64),
+                format_duration(self.total_time as f64)
+            )
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Now Playing [3]")
             .style(style);
+        let mut info_area = block.inner(chunks[0]);
+        f.render_widget(block, chunks[0]);
 
-        f.render_widget(paragraph, area);
+        if let Some(album_art) = album_art.filter(|album_art| album_art.has_art()) {
+            // Cells are about twice as high as wide, so a square cover is twice as many columns
+            let art_width = (info_area.height * 2).min(info_area.width / 3);
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(art_width), Constraint::Min(0)].as_ref())
+                .split(info_area);
+            album_art.render(f, columns[0], !popup_open);
+            info_area = Rect {
+                x: columns[1].x + 1,
+                width: columns[1].width.saturating_sub(1),
+                ..columns[1]
+            };
+        }
+
+        let paragraph = Paragraph::new(song_info)
+            .style(style)
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, info_area);
 
         // Display the progress bar
-        let percentage = self.current_time as f64 / self.total_time as f64;
+        let percentage = if self.total_time > 0 {
+            (self.current_time as f64 / self.total_time as f64).min(1.0)
+        } else {
+            0.0
+        };
         let gauge = Gauge::default()
             .block(Block::default().borders(Borders::ALL))
             .gauge_style(Style::default().fg(Color::Yellow))
             .percent((percentage * 100.0) as u16);
 
-        f.render_widget(gauge, area);
+        f.render_widget(gauge, chunks[1]);
     }
 }
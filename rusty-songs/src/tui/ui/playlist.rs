@@ -1,91 +1,198 @@
-use crate::models::song::Song;
+use crate::library::index::LibraryIndex;
+use crate::library::smart_playlist::Query;
+use crate::library::sort::{sort_songs, GroupBy, SortKey};
+use crate::models::song::{Song, MAX_RATING};
 use crate::tui::ui::color_theme::ColorTheme;
-use crate::utils::format::format_duration;
-use std::env;
+use crate::utils::format::{fit_width, format_age, format_duration, format_rating};
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-use symphonia::default::get_probe;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tui::backend::Backend;
 use tui::layout::Rect;
-use tui::style::Color;
-use tui::style::Style;
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
-use tui::widgets::{Block, Borders, List, ListItem};
+use tui::widgets::{Block, Borders, List, ListItem, ListState};
 use tui::Frame;
 
+/// A line of the playlist: either a group header or a song (as an index into `songs`)
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlaylistRow {
+    Header {
+        name: String,
+        count: usize,
+        collapsed: bool,
+    },
+    Song(usize),
+}
+
 #[derive(Clone)]
 pub struct Playlist {
     pub songs: Vec<Song>,
+    pub rows: Vec<PlaylistRow>,
+    pub sort: SortKey,
+    pub descending: bool,
+    pub group_by: GroupBy,
+    /// The smart playlist shown instead of the whole library, if any
+    pub smart_playlist: Option<(String, Query)>,
+    /// Show play counts, ratings and favourites as columns next to each song
+    pub show_stats: bool,
+    /// Songs picked for batch operations such as editing tags
+    pub marked: HashSet<PathBuf>,
+    collapsed: HashSet<String>,
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Playlist {
     pub fn new() -> Self {
-        Playlist { songs: Vec::new() }
+        Playlist {
+            songs: Vec::new(),
+            rows: Vec::new(),
+            sort: SortKey::default(),
+            descending: false,
+            group_by: GroupBy::default(),
+            smart_playlist: None,
+            show_stats: false,
+            marked: HashSet::new(),
+            collapsed: HashSet::new(),
+        }
+    }
+
+    /// Syncs the playlist with the library index, in the chosen sort order.
+    /// Smart playlists are re-evaluated every time.
+    pub fn load_playlist(&mut self, index: &LibraryIndex) {
+        self.songs = index.songs();
+        self.marked.retain(|path| index.contains(path));
+        if let Some((_, query)) = &self.smart_playlist {
+            self.songs.retain(|song| query.matches(song));
+        }
+        self.refresh_view();
+    }
+
+    /// Songs in the order they are shown, e.g. to queue the whole playlist
+    pub fn visible_songs(&self) -> Vec<Song> {
+        self.rows
+            .iter()
+            .filter_map(|row| match row {
+                PlaylistRow::Song(index) => self.songs.get(*index).cloned(),
+                PlaylistRow::Header { .. } => None,
+            })
+            .collect()
     }
 
-    pub fn load_playlist(&mut self) {
-        let music_dir = env::var("MUSIC_DIR").unwrap_or_else(|_| "music".to_string());
+    pub fn set_view(&mut self, sort: SortKey, descending: bool, group_by: GroupBy) {
+        self.sort = sort;
+        self.descending = descending;
+        self.group_by = group_by;
+        self.refresh_view();
+    }
 
-        for entry in fs::read_dir(&music_dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
+    /// Re-sorts the songs and rebuilds the visible rows
+    pub fn refresh_view(&mut self) {
+        sort_songs(&mut self.songs, self.sort, self.descending, self.group_by);
 
-            if path.extension().map_or(false, |ext| ext == "mp3") {
-                let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                if self.songs.iter().any(|song| song.title == file_name) {
-                    continue;
-                }
+        self.rows.clear();
+        let mut current_group: Option<(String, usize)> = None;
 
-                if let Some(duration) = Self::get_mp3_duration(&path) {
-                    self.songs.push(Song {
-                        title: file_name,
-                        duration,
+        for (i, song) in self.songs.iter().enumerate() {
+            if let Some(name) = self.group_by.group_name(song) {
+                let is_new_group = current_group
+                    .as_ref()
+                    .is_none_or(|(current, _)| *current != name);
+                if is_new_group {
+                    self.rows.push(PlaylistRow::Header {
+                        name: name.clone(),
+                        count: 0,
+                        collapsed: self.collapsed.contains(&name),
                     });
+                    current_group = Some((name.clone(), self.rows.len() - 1));
+                }
+
+                if let Some((_, header_row)) = current_group {
+                    if let PlaylistRow::Header { count, .. } = &mut self.rows[header_row] {
+                        *count += 1;
+                    }
+                }
+                if self.collapsed.contains(&name) {
+                    continue;
                 }
             }
+            self.rows.push(PlaylistRow::Song(i));
         }
     }
 
-    fn get_mp3_duration(path: &PathBuf) -> Option<f64> {
-        let file = std::fs::File::open(path).ok()?;
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-        let mut hint = Hint::new();
-        hint.with_extension("mp3");
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
 
-        // Use Symphonia to probe the file format and extract duration
-        let mut probed = get_probe()
-            .format(
-                &hint,
-                mss,
-                &FormatOptions::default(),
-                &MetadataOptions::default(),
-            )
-            .ok()?;
-        let format = &mut probed.format; // Mutable borrow for later
+    pub fn song_at(&self, row: usize) -> Option<&Song> {
+        match self.rows.get(row) {
+            Some(PlaylistRow::Song(index)) => self.songs.get(*index),
+            _ => None,
+        }
+    }
 
-        // Extract track and codec information before the loop
-        let track = format.default_track()?; // Immutable borrow here
-        let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
+    /// Row showing the song at `index` of `songs`, expanding its group if it is collapsed
+    pub fn reveal(&mut self, index: usize) -> Option<usize> {
+        let song = self.songs.get(index)?;
+        if let Some(name) = self.group_by.group_name(song) {
+            if self.collapsed.remove(&name) {
+                self.refresh_view();
+            }
+        }
 
-        let mut decoder = symphonia::default::get_codecs()
-            .make(&track.codec_params, &Default::default())
-            .ok()?; // Handle codec creation result correctly
+        self.rows
+            .iter()
+            .position(|row| *row == PlaylistRow::Song(index))
+    }
 
-        let mut total_duration = 0.0;
+    /// Marks or unmarks the song at `row`, or every song of the group whose header is at `row`
+    pub fn toggle_mark(&mut self, row: usize) {
+        let paths: Vec<PathBuf> = match self.rows.get(row) {
+            Some(PlaylistRow::Song(index)) => vec![self.songs[*index].path.clone()],
+            Some(PlaylistRow::Header { name, .. }) => self
+                .songs
+                .iter()
+                .filter(|song| self.group_by.group_name(song).as_ref() == Some(name))
+                .map(|song| song.path.clone())
+                .collect(),
+            None => return,
+        };
 
-        // Use the mutable borrow of format for reading packets
-        while let Ok(packet) = format.next_packet() {
-            if let Ok(decoded) = decoder.decode(&packet) {
-                total_duration += decoded.frames() as f64 / sample_rate as f64;
+        if paths.iter().all(|path| self.marked.contains(path)) {
+            for path in &paths {
+                self.marked.remove(path);
             }
+        } else {
+            self.marked.extend(paths);
         }
+    }
+
+    pub fn marked_songs(&self) -> Vec<Song> {
+        self.songs
+            .iter()
+            .filter(|song| self.marked.contains(&song.path))
+            .cloned()
+            .collect()
+    }
 
-        Some(total_duration)
+    /// Collapses or expands the group whose header is at `row`, returns false for song rows
+    pub fn toggle_group(&mut self, row: usize) -> bool {
+        let Some(PlaylistRow::Header { name, .. }) = self.rows.get(row) else {
+            return false;
+        };
+
+        let name = name.clone();
+        if !self.collapsed.remove(&name) {
+            self.collapsed.insert(name);
+        }
+        self.refresh_view();
+        true
     }
 
     pub fn render_with_style<B: Backend>(
@@ -96,25 +203,105 @@ impl Playlist {
         selected_index: usize,
     ) {
         let theme = ColorTheme::catppuccin_mocha();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let indent = if self.group_by == GroupBy::None {
+            ""
+        } else {
+            "  "
+        };
 
         let items: Vec<ListItem> = self
-            .songs
+            .rows
             .iter()
-            .enumerate() // Enumerate to track the index of each song
-            .map(|(i, song)| {
-                let duration = format_duration(song.duration);
-                let spans = Spans::from(vec![
-                    Span::raw(
-                        song.title
-                            .strip_suffix(".mp3")
-                            .unwrap_or(&song.title)
-                            .to_string(),
-                    ),
-                    Span::styled(
-                        format!(" [{}]", duration),
-                        Style::default().fg(theme.accent2),
-                    ),
-                ]);
+            .enumerate() // Enumerate to track the index of each row
+            .map(|(i, row)| {
+                let spans = match row {
+                    PlaylistRow::Header {
+                        name,
+                        count,
+                        collapsed,
+                    } => Spans::from(Span::styled(
+                        format!(
+                            "{} {} ({})",
+                            if *collapsed { "▸" } else { "▾" },
+                            name,
+                            count
+                        ),
+                        Style::default()
+                            .fg(theme.accent1)
+                            .add_modifier(Modifier::BOLD),
+                    )),
+                    PlaylistRow::Song(index) => {
+                        let song = &self.songs[*index];
+                        let duration = format_duration(song.duration);
+                        let mark = if self.marked.contains(&song.path) {
+                            "● "
+                        } else {
+                            ""
+                        };
+                        let title = format!("{}{}{}", indent, mark, song.title);
+
+                        if self.show_stats {
+                            // Fixed width columns after a title that fills the rest of the row,
+                            // so the statistics of every song line up
+                            let stats = &song.stats;
+                            let last_played = stats
+                                .last_played
+                                .map_or("never".to_string(), |played| format_age(played, now));
+                            let columns = vec![
+                                Span::styled(
+                                    format!(" {:>6}", duration),
+                                    Style::default().fg(theme.accent2),
+                                ),
+                                Span::styled(
+                                    format!(" {:<6}", song.codec),
+                                    Style::default().fg(theme.accent1),
+                                ),
+                                Span::styled(
+                                    format!(
+                                        " {} {:>4}▶ {:>3}⏭ {:>8}",
+                                        format_rating(stats.rating, MAX_RATING),
+                                        stats.play_count,
+                                        stats.skip_count,
+                                        last_played
+                                    ),
+                                    Style::default().fg(theme.accent2),
+                                ),
+                                Span::styled(
+                                    if stats.favourite { " ♥" } else { "  " },
+                                    Style::default().fg(theme.error),
+                                ),
+                            ];
+                            let columns_width: usize = columns.iter().map(Span::width).sum();
+                            let title_width = usize::from(area.width)
+                                .saturating_sub(2) // Borders
+                                .saturating_sub(columns_width);
+
+                            let mut spans = vec![Span::raw(fit_width(&title, title_width))];
+                            spans.extend(columns);
+                            Spans::from(spans)
+                        } else {
+                            let mut spans = vec![
+                                Span::raw(title),
+                                Span::styled(
+                                    format!(" [{}]", duration),
+                                    Style::default().fg(theme.accent2),
+                                ),
+                                Span::styled(
+                                    format!(" {}", song.codec),
+                                    Style::default().fg(theme.accent1),
+                                ),
+                            ];
+                            if song.stats.favourite {
+                                spans.push(Span::styled(" ♥", Style::default().fg(theme.error)));
+                            }
+                            Spans::from(spans)
+                        }
+                    }
+                };
 
                 if i == selected_index {
                     ListItem::new(spans).style(Style::default().fg(theme.highlight))
@@ -124,25 +311,151 @@ impl Playlist {
             })
             .collect();
 
+        let title = format!(
+            "Playlist [1] - {} - sort: {} {} - group: {}",
+            self.smart_playlist
+                .as_ref()
+                .map_or("Library", |(name, _)| name.as_str()),
+            self.sort.label(),
+            if self.descending { "↓" } else { "↑" },
+            self.group_by.label()
+        );
+
         let playlist = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Playlist [1]"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .style(style);
 
-        f.render_widget(playlist, area);
-    }
-
-    pub fn remove_song(&mut self, index: usize) {
-        if index < self.songs.len() {
-            let song_title = self.songs[index].title.clone();
+        // Keeps the selected row scrolled into view
+        let mut state = ListState::default();
+        if !self.rows.is_empty() {
+            state.select(Some(selected_index.min(self.rows.len() - 1)));
+        }
 
-            self.songs.remove(index);
+        f.render_stateful_widget(playlist, area, &mut state);
+    }
 
-            let music_dir = std::env::var("MUSIC_DIR").unwrap_or_else(|_| "music".to_string());
-            let file_path = PathBuf::from(music_dir).join(song_title);
+    /// Deletes the song shown at `row` from disk
+    pub fn remove_song(&mut self, row: usize) {
+        if let Some(PlaylistRow::Song(index)) = self.rows.get(row) {
+            let file_path = self.songs.remove(*index).path;
 
             if let Err(e) = fs::remove_file(&file_path) {
                 eprintln!("Failed to delete file {}: {}", file_path.display(), e);
             }
+
+            self.refresh_view();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::song::{SongStats, TrackTags};
+    use tui::backend::TestBackend;
+    use tui::Terminal;
+
+    fn song(title: &str, artist: &str) -> Song {
+        Song {
+            title: title.to_string(),
+            duration: 1.0,
+            path: PathBuf::from(format!("{}.mp3", title)),
+            codec: "MP3".to_string(),
+            tags: TrackTags {
+                artist: Some(artist.to_string()),
+                ..TrackTags::default()
+            },
+            stats: SongStats::default(),
+        }
+    }
+
+    fn header(name: &str, count: usize, collapsed: bool) -> PlaylistRow {
+        PlaylistRow::Header {
+            name: name.to_string(),
+            count,
+            collapsed,
         }
     }
+
+    fn grouped_playlist() -> Playlist {
+        let mut playlist = Playlist::new();
+        playlist.songs = vec![song("c", "B"), song("b", "A"), song("a", "A")];
+        playlist.set_view(SortKey::Title, false, GroupBy::Artist);
+        playlist
+    }
+
+    #[test]
+    fn collapsed_groups_keep_their_header_and_count() {
+        let mut playlist = grouped_playlist();
+        assert_eq!(
+            playlist.rows,
+            vec![
+                header("A", 2, false),
+                PlaylistRow::Song(0),
+                PlaylistRow::Song(1),
+                header("B", 1, false),
+                PlaylistRow::Song(2),
+            ]
+        );
+
+        assert!(!playlist.toggle_group(1));
+        assert!(playlist.toggle_group(0));
+        assert_eq!(
+            playlist.rows,
+            vec![
+                header("A", 2, true),
+                header("B", 1, false),
+                PlaylistRow::Song(2)
+            ]
+        );
+        assert_eq!(playlist.song_at(2).unwrap().title, "c");
+        assert_eq!(playlist.visible_songs().len(), 1);
+
+        // Collapsed groups stay collapsed when the view is rebuilt
+        playlist.set_view(SortKey::Title, true, GroupBy::Artist);
+        assert_eq!(playlist.row_count(), 3);
+
+        assert!(playlist.toggle_group(0));
+        assert_eq!(playlist.row_count(), 5);
+    }
+
+    #[test]
+    fn revealing_a_song_expands_its_group() {
+        let mut playlist = grouped_playlist();
+        playlist.toggle_group(0);
+
+        assert_eq!(playlist.reveal(1), Some(2));
+        assert_eq!(playlist.rows[0], header("A", 2, false));
+        assert_eq!(playlist.song_at(2).unwrap().title, "b");
+        assert_eq!(playlist.reveal(3), None);
+    }
+
+    #[test]
+    fn statistics_line_up_as_columns() {
+        let mut playlist = Playlist::new();
+        let mut long = song("A title far too long for the pane", "Artist");
+        long.stats.play_count = 1234;
+        long.stats.favourite = true;
+        playlist.songs = vec![long, song("Short", "Artist")];
+        playlist.show_stats = true;
+        playlist.refresh_view();
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 4)).unwrap();
+        terminal
+            .draw(|f| playlist.render_with_style(f, f.size(), Style::default(), 0))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (0..buffer.area.width)
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect()
+        };
+        let plays_column = |line: &str| line.chars().position(|c| c == '▶');
+        assert!(row(1).contains("A title far"));
+        assert!(row(1).contains('…'));
+        assert!(row(1).contains("1234▶"));
+        assert_eq!(plays_column(&row(1)), plays_column(&row(2)));
+        assert!(plays_column(&row(1)).is_some());
+    }
 }
@@ -0,0 +1,62 @@
+use crate::tui::ui::color_theme::ColorTheme;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::Style;
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use tui::Frame;
+
+/// What the text typed into a prompt is used for
+#[derive(Clone, PartialEq)]
+pub enum PromptAction {
+    /// `name = query`
+    NewSmartPlaylist,
+    /// A folder or file to show in the browser
+    OpenPath,
+}
+
+/// A single line text input shown on top of the panes
+#[derive(Clone)]
+pub struct Prompt {
+    pub action: PromptAction,
+    pub title: String,
+    pub input: String,
+}
+
+impl Prompt {
+    pub fn new(action: PromptAction, title: &str) -> Self {
+        Prompt {
+            action,
+            title: title.to_string(),
+            input: String::new(),
+        }
+    }
+
+    pub fn update(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    pub fn delete(&mut self) {
+        self.input.pop();
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let paragraph = Paragraph::new(Spans::from(vec![
+            Span::styled("> ", Style::default().fg(theme.accent1)),
+            Span::styled(&self.input, Style::default().fg(theme.highlight)),
+        ]))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} [Enter: confirm, Esc: cancel]", self.title))
+                .style(Style::default().bg(theme.background).fg(theme.text))
+                .border_style(style),
+        );
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
@@ -1,21 +1,27 @@
+use std::time::{SystemTime, UNIX_EPOCH};
 use tui::backend::Backend;
-use tui::layout::Rect;
+use tui::layout::{Constraint, Rect};
 use tui::style::Style;
 use tui::widgets::{Block, Borders, Row, Table};
 use tui::Frame;
 
-use crate::models::song::Song;
+use crate::models::song::{Song, MAX_RATING};
 use crate::tui::ui::color_theme::ColorTheme;
-use crate::utils::format::format_duration;
+use crate::utils::format::{format_age, format_duration, format_rating};
 
 #[derive(Clone)]
 pub struct Queue {
     pub songs: Vec<Song>,
+    /// Show play counts, ratings and favourites as extra columns
+    pub show_stats: bool,
 }
 
 impl Queue {
     pub fn new(songs: Vec<Song>) -> Self {
-        Queue { songs }
+        Queue {
+            songs,
+            show_stats: false,
+        }
     }
 
     pub fn add_song(&mut self, song: Song) {
@@ -30,6 +36,10 @@ impl Queue {
         selected_index: usize,
     ) {
         let theme = ColorTheme::catppuccin_mocha();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
 
         // Create rows for each song
         let rows: Vec<Row> = self
@@ -38,14 +48,27 @@ impl Queue {
             .enumerate()
             .map(|(i, song)| {
                 let order = format!("{}", i + 1); // Order starts from 1
-                let title = song
-                    .title
-                    .strip_suffix(".mp3")
-                    .unwrap_or(&song.title)
-                    .to_string(); // Remove ".mp3" suffix if present
+                let title = if song.stats.favourite {
+                    format!("♥ {}", song.title)
+                } else {
+                    song.title.clone()
+                };
                 let duration = format_duration(song.duration); // Format the duration
+                let codec = song.codec.clone();
+
+                let mut cells = vec![order, title, duration, codec];
+                if self.show_stats {
+                    cells.push(format_rating(song.stats.rating, MAX_RATING));
+                    cells.push(song.stats.play_count.to_string());
+                    cells.push(song.stats.skip_count.to_string());
+                    cells.push(
+                        song.stats
+                            .last_played
+                            .map_or("never".to_string(), |played| format_age(played, now)),
+                    );
+                }
 
-                Row::new(vec![order, title, duration]).style(if i == selected_index {
+                Row::new(cells).style(if i == selected_index {
                     Style::default().fg(theme.highlight) // Highlight the selected row
                 } else {
                     Style::default().fg(theme.text) // Default text color for other rows
@@ -53,14 +76,30 @@ impl Queue {
             })
             .collect();
 
+        let widths: &[Constraint] = if self.show_stats {
+            &[
+                Constraint::Percentage(5),  // Order
+                Constraint::Percentage(35), // Title
+                Constraint::Percentage(10), // Duration
+                Constraint::Percentage(8),  // Codec
+                Constraint::Percentage(12), // Rating
+                Constraint::Percentage(7),  // Plays
+                Constraint::Percentage(7),  // Skips
+                Constraint::Percentage(16), // Last played
+            ]
+        } else {
+            &[
+                Constraint::Percentage(10), // Order
+                Constraint::Percentage(55), // Title
+                Constraint::Percentage(20), // Duration
+                Constraint::Percentage(15), // Codec
+            ]
+        };
+
         let table = Table::new(rows)
             .block(Block::default().borders(Borders::ALL).title("Queue [2]")) // Set a title and borders
             .style(style) // Apply the passed-in style
-            .widths(&[
-                tui::layout::Constraint::Percentage(10), // Order
-                tui::layout::Constraint::Percentage(60), // Title
-                tui::layout::Constraint::Percentage(30), // Duration
-            ]);
+            .widths(widths);
 
         f.render_widget(table, area);
     }
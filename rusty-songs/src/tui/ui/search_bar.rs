@@ -1,7 +1,7 @@
 use crate::tui::ui::color_theme::ColorTheme;
 use tui::backend::Backend;
 use tui::layout::Rect;
-use tui::style::Style;
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{Block, Borders, Paragraph};
 use tui::Frame;
@@ -11,6 +11,12 @@ pub struct SearchBar {
     pub input: String,
 }
 
+impl Default for SearchBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SearchBar {
     pub fn new() -> Self {
         SearchBar {
@@ -25,11 +31,23 @@ impl SearchBar {
     pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
         let theme = ColorTheme::catppuccin_mocha();
         let song_label = Span::styled("Song: ", Style::default().fg(theme.accent1)); // Use accent1 for label
-        let song_input = Span::styled(&self.input, Style::default().fg(theme.highlight)); // Highlight input in another color
+        let song_input = if self.input.is_empty() {
+            // Hint at the filters that can be typed along with the search terms
+            Span::styled(
+                "e.g. lofi dur:long order:date after:1y cat:music",
+                Style::default().fg(theme.text).add_modifier(Modifier::DIM),
+            )
+        } else {
+            Span::styled(&self.input, Style::default().fg(theme.highlight)) // Highlight input in another color
+        };
 
         // Create the paragraph with multiple spans
         let search = Paragraph::new(Spans::from(vec![song_label, song_input]))
-            .block(Block::default().borders(Borders::ALL).title("Search [0]"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Search [0, Esc: leave]"),
+            )
             .style(style); // Use block style for the whole block
 
         f.render_widget(search, area);
@@ -0,0 +1,235 @@
+use crate::library::stats::{LibraryStats, DAYS};
+use crate::tui::ui::color_theme::ColorTheme;
+use crate::utils::format::{format_long_duration, format_size};
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{BarChart, Block, Borders, Clear, List, ListItem, Paragraph, Sparkline};
+use tui::Frame;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Dashboard with totals, disk usage, top artists and listening time
+#[derive(Clone)]
+pub struct StatsView {
+    pub stats: LibraryStats,
+}
+
+/// Widest bars that fit `count` bars with a gap of one into `area`
+fn bar_width(area: Rect, count: usize) -> u16 {
+    let count = count.max(1) as u16;
+    (area.width.saturating_sub(2) / count)
+        .saturating_sub(1)
+        .clamp(1, 9)
+}
+
+impl StatsView {
+    pub fn new(stats: LibraryStats) -> Self {
+        StatsView { stats }
+    }
+
+    fn bar_chart<'a, B: Backend>(
+        f: &mut Frame<B>,
+        area: Rect,
+        title: &'a str,
+        data: &'a [(&'a str, u64)],
+    ) {
+        let theme = ColorTheme::catppuccin_mocha();
+        let chart = BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(data)
+            .bar_width(bar_width(area, data.len()))
+            .bar_gap(1)
+            .bar_style(Style::default().fg(theme.accent1))
+            .value_style(Style::default().fg(theme.background).bg(theme.accent1))
+            .label_style(Style::default().fg(theme.text));
+        f.render_widget(chart, area);
+    }
+
+    fn list<B: Backend>(f: &mut Frame<B>, area: Rect, title: String, items: Vec<ListItem>) {
+        let items = if items.is_empty() {
+            vec![ListItem::new("Nothing yet")]
+        } else {
+            items
+        };
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(list, area);
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+        let stats = &self.stats;
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Stats [5] [Esc: close]")
+            .style(Style::default().bg(theme.background).fg(theme.text))
+            .border_style(style);
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(2),
+                    Constraint::Percentage(50),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(inner);
+
+        let highlight = Style::default()
+            .fg(theme.highlight)
+            .add_modifier(Modifier::BOLD);
+        let summary = vec![
+            Spans::from(vec![
+                Span::styled(stats.total_tracks.to_string(), highlight),
+                Span::raw(" songs, "),
+                Span::styled(format_long_duration(stats.total_duration), highlight),
+                Span::raw(" of music, "),
+                Span::styled(format_size(stats.total_size), highlight),
+                Span::raw(" on disk"),
+            ]),
+            Spans::from(vec![
+                Span::styled(
+                    format_long_duration(stats.total_listening as f64),
+                    highlight,
+                ),
+                Span::raw(" listened in total, "),
+                Span::styled(stats.never_played.len().to_string(), highlight),
+                Span::raw(" songs never played"),
+            ]),
+        ];
+        f.render_widget(Paragraph::new(summary), rows[0]);
+
+        // Disk usage and top artists
+        let middle = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(40),
+                ]
+                .as_ref(),
+            )
+            .split(rows[1]);
+
+        let by_format: Vec<(&str, u64)> = stats
+            .size_by_format
+            .iter()
+            .map(|(format, size)| (format.as_str(), size / BYTES_PER_MB))
+            .collect();
+        Self::bar_chart(f, middle[0], "Disk usage by format (MB)", &by_format);
+
+        let by_bitrate: Vec<(&str, u64)> = stats
+            .size_by_bitrate
+            .iter()
+            .map(|(bucket, size)| (bucket.as_str(), size / BYTES_PER_MB))
+            .collect();
+        Self::bar_chart(f, middle[1], "Disk usage by bitrate (MB)", &by_bitrate);
+
+        let top_lists = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(middle[2]);
+        for (area, title, top) in [
+            (top_lists[0], "Top artists", &stats.top_artists),
+            (top_lists[1], "Top channels", &stats.top_channels),
+        ] {
+            let items = top
+                .iter()
+                .map(|(name, plays, songs)| {
+                    ListItem::new(Spans::from(vec![
+                        Span::raw(name.clone()),
+                        Span::styled(
+                            format!("  {} plays, {} songs", plays, songs),
+                            Style::default().fg(theme.accent2),
+                        ),
+                    ]))
+                })
+                .collect();
+            Self::list(f, area, title.to_string(), items);
+        }
+
+        // Listening time and songs
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(40),
+                ]
+                .as_ref(),
+            )
+            .split(rows[2]);
+
+        let daily: Vec<u64> = stats
+            .daily_listening
+            .iter()
+            .map(|seconds| seconds / 60)
+            .collect();
+        let sparkline = Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Minutes per day ({} days)", DAYS)),
+            )
+            .data(&daily)
+            .style(Style::default().fg(theme.accent2));
+        f.render_widget(sparkline, bottom[0]);
+
+        let week_labels: Vec<String> = (0..stats.weekly_listening.len())
+            .rev()
+            .map(|weeks_ago| {
+                if weeks_ago == 0 {
+                    "now".to_string()
+                } else {
+                    format!("-{}w", weeks_ago)
+                }
+            })
+            .collect();
+        let weekly: Vec<(&str, u64)> = week_labels
+            .iter()
+            .zip(stats.weekly_listening.iter())
+            .map(|(label, seconds)| (label.as_str(), seconds / 60))
+            .collect();
+        Self::bar_chart(f, bottom[1], "Minutes per week", &weekly);
+
+        let song_lists = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(bottom[2]);
+        let most_played = stats
+            .most_played
+            .iter()
+            .map(|song| {
+                ListItem::new(Spans::from(vec![
+                    Span::raw(song.title.clone()),
+                    Span::styled(
+                        format!("  {} plays", song.stats.play_count),
+                        Style::default().fg(theme.accent2),
+                    ),
+                ]))
+            })
+            .collect();
+        Self::list(f, song_lists[0], "Most played".to_string(), most_played);
+
+        let never_played = stats
+            .never_played
+            .iter()
+            .map(|song| ListItem::new(song.title.clone()))
+            .collect();
+        Self::list(
+            f,
+            song_lists[1],
+            format!("Never played ({})", stats.never_played.len()),
+            never_played,
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::models::song::{Song, TrackTags};
+use crate::tui::ui::color_theme::ColorTheme;
+use crate::utils::tag_tools::tags_from_file_name;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use tui::Frame;
+
+const DEFAULT_PATTERN: &str = "%artist% - %title%";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    Track,
+    Year,
+    Genre,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 6] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::Track,
+        TagField::Year,
+        TagField::Genre,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagField::Title => "Title",
+            TagField::Artist => "Artist",
+            TagField::Album => "Album",
+            TagField::Track => "Track",
+            TagField::Year => "Year",
+            TagField::Genre => "Genre",
+        }
+    }
+
+    pub fn get(self, tags: &TrackTags) -> Option<String> {
+        match self {
+            TagField::Title => tags.title.clone(),
+            TagField::Artist => tags.artist.clone(),
+            TagField::Album => tags.album.clone(),
+            TagField::Track => tags.track.map(|track| track.to_string()),
+            TagField::Year => tags.year.map(|year| year.to_string()),
+            TagField::Genre => tags.genre.clone(),
+        }
+    }
+
+    /// Sets the field from typed text, an empty text removes it
+    fn set(self, tags: &mut TrackTags, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let text = (!value.is_empty()).then(|| value.to_string());
+        let number_error = || format!("{} must be a number", self.label());
+
+        match self {
+            TagField::Title => tags.title = text,
+            TagField::Artist => tags.artist = text,
+            TagField::Album => tags.album = text,
+            TagField::Genre => tags.genre = text,
+            TagField::Track => {
+                tags.track = text
+                    .map(|text| text.parse().map_err(|_| number_error()))
+                    .transpose()?
+            }
+            TagField::Year => {
+                tags.year = text
+                    .map(|text| text.parse().map_err(|_| number_error()))
+                    .transpose()?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text typed into a field. Untouched fields keep each song's own value.
+#[derive(Clone, Default)]
+struct FieldInput {
+    value: String,
+    edited: bool,
+}
+
+/// Modal editor for the tags of one song, or of several songs at once
+#[derive(Clone)]
+pub struct TagEditor {
+    pub songs: Vec<Song>,
+    /// The tags each song will be written with
+    tags: Vec<TrackTags>,
+    inputs: Vec<FieldInput>,
+    pattern: String,
+    /// Index into `TagField::ALL`, one past the end selects the file name pattern
+    pub selected: usize,
+}
+
+impl TagEditor {
+    pub fn new(songs: Vec<Song>) -> Self {
+        TagEditor {
+            tags: songs.iter().map(|song| song.tags.clone()).collect(),
+            songs,
+            inputs: vec![FieldInput::default(); TagField::ALL.len()],
+            pattern: DEFAULT_PATTERN.to_string(),
+            selected: 0,
+        }
+    }
+
+    pub fn is_pattern_selected(&self) -> bool {
+        self.selected == TagField::ALL.len()
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % (TagField::ALL.len() + 1);
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + TagField::ALL.len()) % (TagField::ALL.len() + 1);
+    }
+
+    /// The value shared by all songs, or None when they differ
+    fn common_value(&self, field: TagField) -> Option<Option<String>> {
+        let mut values = self.tags.iter().map(|tags| field.get(tags));
+        let first = values.next()?;
+        values.all(|value| value == first).then_some(first)
+    }
+
+    fn selected_input(&mut self) -> &mut String {
+        if self.is_pattern_selected() {
+            return &mut self.pattern;
+        }
+
+        let field = TagField::ALL[self.selected];
+        let common = self.common_value(field).flatten().unwrap_or_default();
+        let input = &mut self.inputs[self.selected];
+        if !input.edited {
+            input.value = common;
+            input.edited = true;
+        }
+        &mut input.value
+    }
+
+    pub fn update(&mut self, c: char) {
+        self.selected_input().push(c);
+    }
+
+    pub fn delete(&mut self) {
+        self.selected_input().pop();
+    }
+
+    /// Fills in the tags of every song from its file name, returns how many songs matched
+    pub fn derive_from_file_names(&mut self) -> usize {
+        let mut matched = 0;
+        for (song, tags) in self.songs.iter().zip(self.tags.iter_mut()) {
+            if let Some(derived) = tags_from_file_name(&song.path, &self.pattern) {
+                tags.overwrite_with(&derived);
+                matched += 1;
+            }
+        }
+
+        // Show the derived values rather than what was typed before
+        self.inputs = vec![FieldInput::default(); TagField::ALL.len()];
+        matched
+    }
+
+    /// The songs whose tags changed, with the tags to write
+    pub fn changes(&self) -> Result<Vec<(Song, TrackTags)>, String> {
+        let mut changes = Vec::new();
+
+        for (song, tags) in self.songs.iter().zip(self.tags.iter()) {
+            let mut tags = tags.clone();
+            for (field, input) in TagField::ALL.iter().zip(self.inputs.iter()) {
+                if input.edited {
+                    field.set(&mut tags, &input.value)?;
+                }
+            }
+            if tags != song.tags {
+                changes.push((song.clone(), tags));
+            }
+        }
+
+        Ok(changes)
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+        let label_style = Style::default().fg(theme.accent1);
+
+        let mut items: Vec<ListItem> = TagField::ALL
+            .iter()
+            .zip(self.inputs.iter())
+            .map(|(field, input)| {
+                let value = if input.edited {
+                    Span::styled(input.value.clone(), Style::default().fg(theme.highlight))
+                } else {
+                    match self.common_value(*field) {
+                        Some(value) => Span::raw(value.unwrap_or_default()),
+                        None => Span::styled(
+                            "<multiple values>",
+                            Style::default().add_modifier(Modifier::ITALIC),
+                        ),
+                    }
+                };
+
+                ListItem::new(Spans::from(vec![
+                    Span::styled(format!("{:<8}", field.label()), label_style),
+                    value,
+                ]))
+            })
+            .collect();
+
+        items.push(ListItem::new(Spans::from(vec![
+            Span::styled("From file name (Enter to apply): ", label_style),
+            Span::styled(self.pattern.clone(), Style::default().fg(theme.accent2)),
+        ])));
+
+        let title = if self.songs.len() == 1 {
+            format!("Edit tags: {}", self.songs[0].title)
+        } else {
+            format!("Edit tags of {} songs", self.songs.len())
+        };
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(
+                        "{} [Up/Down: field, Enter: save, Esc: cancel]",
+                        title
+                    ))
+                    .style(Style::default().bg(theme.background).fg(theme.text))
+                    .border_style(style),
+            )
+            .highlight_style(Style::default().fg(theme.highlight))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
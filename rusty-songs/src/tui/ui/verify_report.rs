@@ -0,0 +1,121 @@
+use crate::library::verify::VerifyIssue;
+use crate::tui::ui::color_theme::ColorTheme;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use tui::Frame;
+
+/// Report pane listing the files the library check found problems with
+#[derive(Clone)]
+pub struct VerifyReport {
+    pub issues: Vec<VerifyIssue>,
+    /// How many files were checked
+    pub checked: usize,
+    pub selected: usize,
+}
+
+impl VerifyReport {
+    pub fn new(issues: Vec<VerifyIssue>, checked: usize) -> Self {
+        VerifyReport {
+            issues,
+            checked,
+            selected: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.issues.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_issue(&self) -> Option<&VerifyIssue> {
+        self.issues.get(self.selected)
+    }
+
+    /// Drops the selected issue from the report, e.g. once it was dealt with
+    pub fn take_selected(&mut self) -> Option<VerifyIssue> {
+        if self.selected >= self.issues.len() {
+            return None;
+        }
+
+        let issue = self.issues.remove(self.selected);
+        if self.selected >= self.issues.len() {
+            self.selected = self.issues.len().saturating_sub(1);
+        }
+        Some(issue)
+    }
+
+    pub fn render_with_style<B: Backend>(&self, f: &mut Frame<B>, area: Rect, style: Style) {
+        let theme = ColorTheme::catppuccin_mocha();
+
+        let mut items = Vec::new();
+        let mut selected_row = 0;
+
+        for (i, issue) in self.issues.iter().enumerate() {
+            let selected = i == self.selected;
+            if selected {
+                selected_row = items.len();
+            }
+
+            let mut spans = vec![Span::styled(
+                issue.title(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )];
+            if issue.video_id.is_some() {
+                spans.push(Span::styled(
+                    " (YouTube)",
+                    Style::default().fg(theme.accent2),
+                ));
+            }
+            spans.push(Span::raw(format!("  {}", issue.path.display())));
+
+            items.push(ListItem::new(Spans::from(spans)).style(if selected {
+                Style::default().fg(theme.highlight)
+            } else {
+                Style::default().fg(theme.text)
+            }));
+
+            for problem in &issue.problems {
+                items.push(ListItem::new(Spans::from(Span::styled(
+                    format!("    {}", problem.describe()),
+                    Style::default().fg(theme.error),
+                ))));
+            }
+        }
+
+        if items.is_empty() {
+            items.push(ListItem::new("No problems left"));
+        }
+
+        let mut state = ListState::default();
+        state.select(Some(selected_row));
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(
+                        "Library check: {} of {} files [r: re-download, d: delete, x: dismiss, Esc: close]",
+                        self.issues.len(),
+                        self.checked
+                    ))
+                    .style(Style::default().bg(theme.background).fg(theme.text))
+                    .border_style(style),
+            )
+            .highlight_symbol("> ");
+
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
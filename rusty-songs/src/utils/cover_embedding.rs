@@ -0,0 +1,529 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use symphonia::core::checksum::Crc32;
+use symphonia::core::io::Monitor;
+
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+const FLAC_PADDING_BLOCK: u8 = 1;
+const FLAC_PICTURE_BLOCK: u8 = 6;
+
+/// Metadata block lengths are stored in 24 bits
+const FLAC_MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+/// Picture type of the front cover, shared by FLAC, Vorbis comments and ID3
+const FRONT_COVER: u32 = 3;
+
+const OGG_PAGE_HEADER_LEN: usize = 27;
+const OGG_CONTINUED_PACKET: u8 = 0x01;
+
+/// Vorbis comment holding a base64 encoded FLAC picture block
+const PICTURE_COMMENT: &str = "METADATA_BLOCK_PICTURE";
+
+/// Older, unofficial comment that some taggers use for covers
+const LEGACY_COVER_COMMENT: &str = "COVERART";
+
+/// Where the rewritten file is written before it replaces the original.
+/// The leading dot keeps the library from picking up the half written copy.
+fn temp_path(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::other("path has no file name"))?
+        .to_string_lossy();
+    Ok(path.with_file_name(format!(".tagging.{}", file_name)))
+}
+
+/// Writes a file through a temporary copy, so a failure halfway leaves the original intact
+fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    let temp_path = temp_path(path)?;
+    let result = File::create(&temp_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()
+    });
+
+    match result {
+        Ok(()) => Ok(fs::rename(&temp_path, path)?),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(Box::new(e))
+        }
+    }
+}
+
+/// A FLAC PICTURE block holding the front cover, also used inside Vorbis comments
+fn picture_block(mime_type: &str, data: &[u8]) -> Vec<u8> {
+    let (width, height) = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .unwrap_or((0, 0));
+
+    let mut block = Vec::with_capacity(data.len() + 64);
+    block.extend_from_slice(&FRONT_COVER.to_be_bytes());
+    block.extend_from_slice(&(mime_type.len() as u32).to_be_bytes());
+    block.extend_from_slice(mime_type.as_bytes());
+    block.extend_from_slice(&0u32.to_be_bytes()); // Empty description
+    block.extend_from_slice(&width.to_be_bytes());
+    block.extend_from_slice(&height.to_be_bytes());
+    block.extend_from_slice(&24u32.to_be_bytes()); // Colour depth
+    block.extend_from_slice(&0u32.to_be_bytes()); // Not an indexed colour image
+    block.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    block.extend_from_slice(data);
+    block
+}
+
+/// Replaces the pictures in the metadata blocks of a FLAC file with the given front cover.
+/// The audio frames are copied as they are.
+pub fn embed_flac_cover(path: &Path, mime_type: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let picture = picture_block(mime_type, data);
+    if picture.len() > FLAC_MAX_BLOCK_LEN {
+        return Err(Box::new(io::Error::other(
+            "cover is too large for a FLAC picture block",
+        )));
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut marker = [0u8; 4];
+    reader.read_exact(&mut marker)?;
+    if &marker != FLAC_MARKER {
+        return Err(Box::new(io::Error::other("not a FLAC file")));
+    }
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let block_type = header[0] & 0x7f;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body)?;
+
+        if block_type != FLAC_PICTURE_BLOCK {
+            blocks.push((block_type, body));
+        }
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+
+    // STREAMINFO has to stay first, and padding is conventionally last
+    let position = blocks
+        .iter()
+        .rposition(|(block_type, _)| *block_type != FLAC_PADDING_BLOCK)
+        .map_or(blocks.len(), |last| last + 1)
+        .max(1);
+    blocks.insert(position, (FLAC_PICTURE_BLOCK, picture));
+
+    replace_file(path, |writer| {
+        writer.write_all(FLAC_MARKER)?;
+        let count = blocks.len();
+        for (i, (block_type, body)) in blocks.iter().enumerate() {
+            let last_flag = if i + 1 == count { 0x80 } else { 0 };
+            writer.write_all(&[block_type | last_flag])?;
+            writer.write_all(&(body.len() as u32).to_be_bytes()[1..])?;
+            writer.write_all(body)?;
+        }
+        io::copy(&mut reader, writer)?;
+        Ok(())
+    })
+}
+
+struct OggPage {
+    header_type: u8,
+    granule: u64,
+    serial: u32,
+    sequence: u32,
+    segments: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl OggPage {
+    /// Reads the next page, or None at the end of the file
+    fn read<R: BufRead>(reader: &mut R) -> io::Result<Option<OggPage>> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut header = [0u8; OGG_PAGE_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != b"OggS" || header[4] != 0 {
+            return Err(io::Error::other("not an Ogg page"));
+        }
+
+        let mut segments = vec![0u8; usize::from(header[26])];
+        reader.read_exact(&mut segments)?;
+        let mut body = vec![0u8; segments.iter().map(|&len| usize::from(len)).sum()];
+        reader.read_exact(&mut body)?;
+
+        Ok(Some(OggPage {
+            header_type: header[5],
+            granule: u64::from_le_bytes(header[6..14].try_into().unwrap()),
+            serial: u32::from_le_bytes(header[14..18].try_into().unwrap()),
+            sequence: u32::from_le_bytes(header[18..22].try_into().unwrap()),
+            segments,
+            body,
+        }))
+    }
+
+    /// Writes the page with a freshly computed checksum
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut page =
+            Vec::with_capacity(OGG_PAGE_HEADER_LEN + self.segments.len() + self.body.len());
+        page.extend_from_slice(b"OggS\0");
+        page.push(self.header_type);
+        page.extend_from_slice(&self.granule.to_le_bytes());
+        page.extend_from_slice(&self.serial.to_le_bytes());
+        page.extend_from_slice(&self.sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(self.segments.len() as u8);
+        page.extend_from_slice(&self.segments);
+        page.extend_from_slice(&self.body);
+
+        // Ogg's CRC-32 runs over the whole page with the checksum field zeroed
+        let mut crc = Crc32::new(0);
+        crc.process_buf_bytes(&page);
+        page[22..26].copy_from_slice(&crc.crc().to_le_bytes());
+
+        writer.write_all(&page)
+    }
+
+    /// Splits a header packet over as many pages as it needs, starting at `sequence`
+    fn paginate(packet: &[u8], serial: u32, sequence: u32) -> Vec<OggPage> {
+        let mut lacing = vec![255u8; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+
+        let chunks: Vec<&[u8]> = lacing.chunks(255).collect();
+        let mut offset = 0;
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(i, segments)| {
+                let len: usize = segments.iter().map(|&len| usize::from(len)).sum();
+                let body = packet[offset..offset + len].to_vec();
+                offset += len;
+                OggPage {
+                    header_type: if i > 0 { OGG_CONTINUED_PACKET } else { 0 },
+                    // Header pages are at granule 0, pages where no packet ends have none
+                    granule: if i + 1 == chunks.len() { 0 } else { u64::MAX },
+                    serial,
+                    sequence: sequence.wrapping_add(i as u32),
+                    segments: segments.to_vec(),
+                    body,
+                }
+            })
+            .collect()
+    }
+}
+
+fn take<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let bytes = data.get(*offset..offset.checked_add(len)?)?;
+    *offset += len;
+    Some(bytes)
+}
+
+fn take_u32(data: &[u8], offset: &mut usize) -> Option<u32> {
+    Some(u32::from_le_bytes(take(data, offset, 4)?.try_into().ok()?))
+}
+
+/// Rebuilds a Vorbis comment packet with the cover as its only picture.
+/// Whatever follows the comments, like Vorbis' framing bit, is kept.
+fn replace_picture_comment(packet: &[u8], magic: &[u8], picture: &[u8]) -> Option<Vec<u8>> {
+    let mut offset = magic.len();
+    let vendor_len = take_u32(packet, &mut offset)? as usize;
+    let vendor = take(packet, &mut offset, vendor_len)?;
+
+    let count = take_u32(packet, &mut offset)?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let len = take_u32(packet, &mut offset)? as usize;
+        let comment = take(packet, &mut offset, len)?;
+        let key = comment
+            .split(|&byte| byte == b'=')
+            .next()
+            .unwrap_or_default();
+        if !key.eq_ignore_ascii_case(PICTURE_COMMENT.as_bytes())
+            && !key.eq_ignore_ascii_case(LEGACY_COVER_COMMENT.as_bytes())
+        {
+            comments.push(comment);
+        }
+    }
+    let picture_comment = format!("{}={}", PICTURE_COMMENT, STANDARD.encode(picture));
+    comments.push(picture_comment.as_bytes());
+
+    let mut rebuilt = magic.to_vec();
+    rebuilt.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    rebuilt.extend_from_slice(vendor);
+    rebuilt.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        rebuilt.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        rebuilt.extend_from_slice(comment);
+    }
+    rebuilt.extend_from_slice(&packet[offset..]);
+    Some(rebuilt)
+}
+
+/// Replaces the cover in the comment header of an Ogg Vorbis or Opus file. The header pages
+/// are rebuilt, and the pages after them are renumbered and copied otherwise unchanged.
+pub fn embed_ogg_cover(path: &Path, mime_type: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let first_page = OggPage::read(&mut reader)?.ok_or_else(|| io::Error::other("empty file"))?;
+
+    // Opus has one header after the identification header, Vorbis has two
+    let (magic, header_count): (&[u8], usize) = if first_page.body.starts_with(b"OpusHead") {
+        (b"OpusTags", 1)
+    } else if first_page.body.starts_with(b"\x01vorbis") {
+        (b"\x03vorbis", 2)
+    } else {
+        return Err(Box::new(io::Error::other(
+            "only Ogg Vorbis and Opus files can hold a cover",
+        )));
+    };
+    let serial = first_page.serial;
+
+    let mut headers = Vec::new();
+    let mut packet = Vec::new();
+    let mut old_page_count = 0u32;
+    while headers.len() < header_count {
+        let page = OggPage::read(&mut reader)?
+            .ok_or_else(|| io::Error::other("Ogg headers are truncated"))?;
+        if page.serial != serial {
+            return Err(Box::new(io::Error::other(
+                "multiplexed Ogg streams are not supported",
+            )));
+        }
+        old_page_count += 1;
+
+        let mut offset = 0;
+        for &len in &page.segments {
+            let len = usize::from(len);
+            packet.extend_from_slice(&page.body[offset..offset + len]);
+            offset += len;
+            if len < 255 {
+                headers.push(std::mem::take(&mut packet));
+            }
+        }
+    }
+    // Both codecs start the audio on a fresh page
+    if headers.len() != header_count || !packet.is_empty() || !headers[0].starts_with(magic) {
+        return Err(Box::new(io::Error::other("malformed Ogg headers")));
+    }
+
+    let picture = picture_block(mime_type, data);
+    headers[0] = replace_picture_comment(&headers[0], magic, &picture)
+        .ok_or_else(|| io::Error::other("malformed comment header"))?;
+
+    let mut header_pages = Vec::new();
+    for header in &headers {
+        let sequence = first_page
+            .sequence
+            .wrapping_add(1 + header_pages.len() as u32);
+        header_pages.extend(OggPage::paginate(header, serial, sequence));
+    }
+    let shift = (header_pages.len() as u32).wrapping_sub(old_page_count);
+
+    replace_file(path, |writer| {
+        first_page.write(writer)?;
+        for page in &header_pages {
+            page.write(writer)?;
+        }
+        while let Some(mut page) = OggPage::read(&mut reader)? {
+            if page.serial == serial {
+                page.sequence = page.sequence.wrapping_add(shift);
+            }
+            page.write(writer)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::cover_art::load_cover_art;
+
+    const COVER: &[u8] = b"\xff\xd8\xff\xe0 not really a JPEG";
+    const OTHER_COVER: &[u8] = b"\x89PNG not really a PNG either";
+
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rusty_songs_cover_{}_{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    const AUDIO_FRAMES: &[u8] = b"\xff\xf8 audio frames";
+
+    fn flac_file(blocks: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut data = FLAC_MARKER.to_vec();
+        for (block_type, body) in blocks {
+            data.push(*block_type);
+            data.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            data.extend_from_slice(body);
+        }
+        data.extend_from_slice(AUDIO_FRAMES);
+        data
+    }
+
+    /// The metadata blocks with their raw first header byte, and what follows them
+    fn flac_blocks(data: &[u8]) -> (Vec<(u8, Vec<u8>)>, &[u8]) {
+        let mut blocks = Vec::new();
+        let mut offset = 4;
+        loop {
+            let header = data[offset];
+            let len = u32::from_be_bytes([0, data[offset + 1], data[offset + 2], data[offset + 3]]);
+            let body = data[offset + 4..offset + 4 + len as usize].to_vec();
+            offset += 4 + len as usize;
+            blocks.push((header, body));
+            if header & 0x80 != 0 {
+                return (blocks, &data[offset..]);
+            }
+        }
+    }
+
+    #[test]
+    fn embeds_a_cover_in_flac_files() {
+        let streaminfo = vec![0; 34];
+        let original = flac_file(&[
+            (0, streaminfo.clone()),
+            (FLAC_PICTURE_BLOCK, picture_block("image/png", OTHER_COVER)),
+            (0x80 | FLAC_PADDING_BLOCK, vec![0; 16]),
+        ]);
+        let path = temp_file("embed.flac", &original);
+
+        embed_flac_cover(&path, "image/jpeg", COVER).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The old picture is replaced, STREAMINFO stays first and padding last
+        let (blocks, audio) = flac_blocks(&data);
+        assert_eq!(
+            blocks,
+            vec![
+                (0, streaminfo),
+                (FLAC_PICTURE_BLOCK, picture_block("image/jpeg", COVER)),
+                (0x80 | FLAC_PADDING_BLOCK, vec![0; 16]),
+            ]
+        );
+        assert_eq!(audio, AUDIO_FRAMES);
+    }
+
+    #[test]
+    fn adds_a_cover_after_the_last_flac_block() {
+        let path = temp_file("add.flac", &flac_file(&[(0x80, vec![0; 34])]));
+
+        embed_flac_cover(&path, "image/jpeg", COVER).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (blocks, audio) = flac_blocks(&data);
+        assert_eq!(
+            blocks,
+            vec![
+                (0, vec![0; 34]),
+                (
+                    0x80 | FLAC_PICTURE_BLOCK,
+                    picture_block("image/jpeg", COVER)
+                ),
+            ]
+        );
+        assert_eq!(audio, AUDIO_FRAMES);
+        assert!(embed_flac_cover(&path, "image/jpeg", COVER).is_err());
+    }
+
+    fn ogg_stream(packets: &[&[u8]]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (sequence, packet) in packets.iter().enumerate() {
+            let granule = if sequence < 2 {
+                0
+            } else {
+                48_000 * sequence as u64
+            };
+            let mut page = OggPage::paginate(packet, 7, sequence as u32).remove(0);
+            page.granule = granule;
+            if sequence == 0 {
+                page.header_type = 0x02;
+            }
+            page.write(&mut data).unwrap();
+        }
+        data
+    }
+
+    fn comment_packet(magic: &[u8], comments: &[&str]) -> Vec<u8> {
+        let mut packet = magic.to_vec();
+        packet.extend_from_slice(&6u32.to_le_bytes());
+        packet.extend_from_slice(b"vendor");
+        packet.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            packet.extend_from_slice(comment.as_bytes());
+        }
+        packet
+    }
+
+    fn read_pages(data: &[u8]) -> Vec<OggPage> {
+        let mut reader = Cursor::new(data);
+        std::iter::from_fn(|| OggPage::read(&mut reader).unwrap()).collect()
+    }
+
+    #[test]
+    fn embeds_a_cover_in_opus_files() {
+        let head = b"OpusHead\x01\x02\x38\x01\x80\xbb\0\0\0\0\0";
+        let old_cover = format!(
+            "{}={}",
+            PICTURE_COMMENT,
+            STANDARD.encode(picture_block("image/png", OTHER_COVER))
+        );
+        let tags = comment_packet(b"OpusTags", &["TITLE=Song", &old_cover]);
+        let original = ogg_stream(&[head, &tags, &[1; 100], &[2; 100]]);
+        let path = temp_file("embed.opus", &original);
+
+        // A cover this large needs several header pages
+        let large_cover = [COVER, &[0u8; 100_000]].concat();
+        embed_ogg_cover(&path, "image/jpeg", &large_cover).unwrap();
+        let data = fs::read(&path).unwrap();
+        let cover = load_cover_art(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(cover, Some(large_cover));
+
+        let pages = read_pages(&data);
+        assert!(pages.len() > 4);
+        for (sequence, page) in pages.iter().enumerate() {
+            assert_eq!(page.sequence, sequence as u32);
+        }
+        assert_eq!(pages[pages.len() - 2].body, vec![1; 100]);
+        assert_eq!(pages[pages.len() - 1].body, vec![2; 100]);
+        assert_eq!(pages[pages.len() - 1].granule, 3 * 48_000);
+        // Every page was written with a valid checksum
+        let mut rewritten = Vec::new();
+        for page in &pages {
+            page.write(&mut rewritten).unwrap();
+        }
+        assert_eq!(rewritten, data);
+    }
+
+    #[test]
+    fn keeps_other_comments_and_the_vorbis_framing_bit() {
+        let mut packet = comment_packet(b"\x03vorbis", &["TITLE=Song", "coverart=old"]);
+        packet.push(1);
+
+        let rebuilt = replace_picture_comment(&packet, b"\x03vorbis", b"picture").unwrap();
+
+        let cover = format!("{}={}", PICTURE_COMMENT, STANDARD.encode(b"picture"));
+        let mut expected = comment_packet(b"\x03vorbis", &["TITLE=Song", &cover]);
+        expected.push(1);
+        assert_eq!(rebuilt, expected);
+
+        assert_eq!(
+            replace_picture_comment(&packet[..20], b"\x03vorbis", b""),
+            None
+        );
+    }
+}
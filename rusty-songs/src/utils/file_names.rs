@@ -0,0 +1,156 @@
+use html_escape::decode_html_entities;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// Most file systems stop at 255 bytes per name. Staying well below leaves room for
+/// de-duplication suffixes and the `.temp.mp3` and `.part` files made while downloading.
+pub const MAX_FILE_NAME_BYTES: usize = 200;
+
+/// Not allowed in file names on Windows, and so on the FAT and exFAT drives music often lives on
+const RESERVED_CHARACTERS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Device names Windows refuses as file names, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Decodes entities like `&amp;` and `&#39;`, which the YouTube Data API leaves in titles
+pub fn decode_entities(text: &str) -> String {
+    decode_html_entities(text).into_owned()
+}
+
+/// Turns arbitrary text, e.g. a video title, into a single file name: entities decoded,
+/// Unicode normalised, without separators, control or reserved characters, never hidden
+/// and never `.` or `..`. The result is not shortened, see `truncate_file_name`.
+pub fn sanitize_file_name(text: &str) -> String {
+    let cleaned: String = decode_entities(text)
+        .nfc()
+        .map(|c| {
+            if RESERVED_CHARACTERS.contains(&c) {
+                '_'
+            } else if c.is_control() {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    // Runs of whitespace come from removed line breaks and tabs
+    let mut name = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // A leading dot hides the file, and Windows drops trailing dots and spaces
+    name = name
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .to_string();
+
+    if name.is_empty() {
+        return "_".to_string();
+    }
+
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Shortens a file name to at most `max_bytes`, keeping its extension and whole characters
+pub fn truncate_file_name(name: &str, max_bytes: usize) -> String {
+    if name.len() <= max_bytes {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && extension.len() <= 16 => {
+            (stem, format!(".{}", extension))
+        }
+        _ => (name, String::new()),
+    };
+
+    let budget = max_bytes.saturating_sub(extension.len());
+    let mut end = budget.min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let stem = stem[..end].trim_end_matches(['.', ' ']);
+    format!("{}{}", if stem.is_empty() { "_" } else { stem }, extension)
+}
+
+/// Returns `path` when it is free, otherwise the first free `name (2).ext`, `name (3).ext`, ...
+pub fn unique_path(path: &Path, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+    if !is_taken(path) {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !is_taken(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_titles_into_file_names() {
+        assert_eq!(
+            sanitize_file_name("AC/DC: Back in Black?"),
+            "AC_DC_ Back in Black_"
+        );
+        assert_eq!(
+            sanitize_file_name("Simon &amp; Garfunkel &#39;Live&#39;"),
+            "Simon & Garfunkel 'Live'"
+        );
+        assert_eq!(
+            sanitize_file_name("line\nbreak\t and  tab"),
+            "line break and tab"
+        );
+        // Composed and decomposed accents give the same name
+        assert_eq!(sanitize_file_name("Beyonce\u{301}"), "Beyoncé");
+        assert_eq!(sanitize_file_name(".hidden. "), "hidden");
+        assert_eq!(sanitize_file_name(".."), "_");
+        assert_eq!(sanitize_file_name(" \n "), "_");
+        assert_eq!(sanitize_file_name("con.mp3"), "_con.mp3");
+        assert_eq!(sanitize_file_name("Console"), "Console");
+    }
+
+    #[test]
+    fn truncates_names_keeping_the_extension() {
+        assert_eq!(truncate_file_name("short.mp3", 20), "short.mp3");
+        assert_eq!(truncate_file_name("a long title.mp3", 10), "a long.mp3");
+        assert_eq!(truncate_file_name("ééé.mp3", 9), "éé.mp3");
+        assert_eq!(truncate_file_name("no extension", 5), "no ex");
+    }
+
+    #[test]
+    fn finds_a_free_name() {
+        let taken = [
+            PathBuf::from("music/a.mp3"),
+            PathBuf::from("music/a (2).mp3"),
+        ];
+        let is_taken = |path: &Path| taken.iter().any(|taken| taken == path);
+
+        assert_eq!(
+            unique_path(Path::new("music/b.mp3"), is_taken),
+            PathBuf::from("music/b.mp3")
+        );
+        assert_eq!(
+            unique_path(Path::new("music/a.mp3"), is_taken),
+            PathBuf::from("music/a (3).mp3")
+        );
+    }
+}
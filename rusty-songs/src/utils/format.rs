@@ -1,5 +1,147 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 pub fn format_duration(seconds: f64) -> String {
     let minutes = (seconds / 60.0).floor() as u64;
     let seconds = (seconds % 60.0).floor() as u64;
     format!("{:02}:{:02}", minutes, seconds)
 }
+
+/// Short relative age like "5m ago", "3d ago" for a unix timestamp
+pub fn format_age(timestamp: u64, now: u64) -> String {
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// Star rating like "★★★☆☆"
+pub fn format_rating(rating: u8, max: u8) -> String {
+    let rating = rating.min(max) as usize;
+    format!(
+        "{}{}",
+        "★".repeat(rating),
+        "☆".repeat(max as usize - rating)
+    )
+}
+
+/// Byte count like "512 KB" or "1.4 GB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+
+    if unit == "B" || size >= 100.0 {
+        format!("{:.0} {}", size, unit)
+    } else {
+        format!("{:.1} {}", size, unit)
+    }
+}
+
+/// Long durations like "3d 4h", "5h 12m" or "42m"
+pub fn format_long_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor() as u64;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Large counts like "812", "12.3K" or "4.1M"
+pub fn format_count(count: u64) -> String {
+    const UNITS: [&str; 3] = ["K", "M", "B"];
+    let mut value = count as f64;
+    let mut unit = "";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+
+    if unit.is_empty() {
+        count.to_string()
+    } else if value >= 100.0 {
+        format!("{:.0}{}", value, unit)
+    } else {
+        format!("{:.1}{}", value, unit)
+    }
+}
+
+/// Video length like "3:45" or "10:02:03"
+pub fn format_clock(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Pads `text` with spaces to exactly `width` terminal columns, cutting it short with "…"
+/// when it is wider, so the text after it lines up as a column
+pub fn fit_width(text: &str, width: usize) -> String {
+    let text_width = text.width();
+    if text_width <= width {
+        return format!("{}{}", text, " ".repeat(width - text_width));
+    }
+
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        fitted.push(c);
+        used += char_width;
+    }
+    if width > 0 {
+        fitted.push('…');
+        used += 1;
+    }
+    fitted + &" ".repeat(width - used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortens_large_counts() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(812), "812");
+        assert_eq!(format_count(1_000), "1.0K");
+        assert_eq!(format_count(12_345), "12.3K");
+        assert_eq!(format_count(123_456), "123K");
+        assert_eq!(format_count(4_100_000), "4.1M");
+        assert_eq!(format_count(7_000_000_000), "7.0B");
+        // Past the largest unit the number keeps growing
+        assert_eq!(format_count(2_000_000_000_000), "2000B");
+    }
+
+    #[test]
+    fn fits_text_into_a_column() {
+        assert_eq!(fit_width("Song", 6), "Song  ");
+        assert_eq!(fit_width("Song", 4), "Song");
+        assert_eq!(fit_width("Long title", 6), "Long …");
+        // Wide characters take two columns and are never cut in half
+        assert_eq!(fit_width("日本語", 4), "日… ");
+        assert_eq!(fit_width("Song", 0), "");
+    }
+}
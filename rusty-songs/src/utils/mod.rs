@@ -1,3 +1,7 @@
+pub mod cover_embedding;
+pub mod file_names;
 pub mod format;
 pub mod logger;
+pub mod settings;
+pub mod tag_tools;
 pub mod video_tools;
@@ -0,0 +1,45 @@
+use crate::library::smart_playlist::SmartPlaylist;
+use crate::library::sort::{GroupBy, SortKey};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// User preferences that survive restarts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub playlist_sort: SortKey,
+    #[serde(default)]
+    pub playlist_sort_descending: bool,
+    #[serde(default)]
+    pub playlist_group_by: GroupBy,
+    #[serde(default)]
+    pub smart_playlists: Vec<SmartPlaylist>,
+    /// Show play counts, ratings and favourites in the Playlist and Queue
+    #[serde(default)]
+    pub show_stats: bool,
+    /// Added to every song's lyrics timing, positive shows lines earlier
+    #[serde(default)]
+    pub lyrics_offset_ms: i64,
+}
+
+impl Settings {
+    /// Location of the settings file, configurable through SETTINGS_FILE
+    pub fn path() -> PathBuf {
+        PathBuf::from(env::var("SETTINGS_FILE").unwrap_or_else(|_| "settings.json".to_string()))
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(Self::path(), content)
+    }
+}
@@ -0,0 +1,724 @@
+use crate::library::audio_format::probe;
+use crate::models::song::{Song, TrackTags};
+use crate::models::video::Snippet;
+use crate::utils::cover_embedding::{embed_flac_cover, embed_ogg_cover};
+use id3::frame::{Comment, ExtendedText, Picture, PictureType};
+use id3::{Tag, TagLike, Timestamp, Version};
+use std::error::Error;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use symphonia::core::meta::StandardTagKey;
+
+pub const YOUTUBE_ID_DESCRIPTION: &str = "YOUTUBE_VIDEO_ID";
+pub const YOUTUBE_URL_DESCRIPTION: &str = "YOUTUBE_URL";
+pub const YOUTUBE_CHANNEL_DESCRIPTION: &str = "YOUTUBE_CHANNEL";
+
+/// YouTube's auto generated artist channels are called "<artist> - Topic"
+const TOPIC_CHANNEL_SUFFIX: &str = " - Topic";
+/// Words marking the bracketed parts of video titles that are not part of the song title
+const TITLE_NOISE: [&str; 7] = ["official", "video", "audio", "lyric", "visuali", "hd", "4k"];
+
+/// Writes ID3v2 tags derived from the YouTube snippet onto a downloaded MP3
+pub fn write_youtube_tags(
+    path: &Path,
+    video_id: &str,
+    snippet: &Snippet,
+    cover: Option<Vec<u8>>,
+) -> Result<(), Box<dyn Error>> {
+    let url = format!("https://www.youtube.com/watch?v={}", video_id);
+
+    // Start from whatever yt-dlp/ffmpeg left behind so we only override our own frames
+    let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+
+    tag.set_title(snippet.title.as_str());
+    tag.set_artist(snippet.channel_title.as_str());
+
+    tag.add_frame(ExtendedText {
+        description: YOUTUBE_ID_DESCRIPTION.to_string(),
+        value: video_id.to_string(),
+    });
+    tag.add_frame(ExtendedText {
+        description: YOUTUBE_URL_DESCRIPTION.to_string(),
+        value: url.clone(),
+    });
+    tag.add_frame(ExtendedText {
+        description: YOUTUBE_CHANNEL_DESCRIPTION.to_string(),
+        value: snippet.channel_title.clone(),
+    });
+    tag.add_frame(Comment {
+        lang: "eng".to_string(),
+        description: String::new(),
+        text: url,
+    });
+
+    // publishedAt looks like "2021-03-04T05:06:07Z"
+    if let Some(timestamp) = snippet
+        .published_at
+        .as_deref()
+        .and_then(|date| date.parse::<Timestamp>().ok())
+    {
+        tag.set_date_released(timestamp);
+        tag.set_date_recorded(timestamp);
+    }
+
+    if let Some(data) = cover {
+        tag.remove_all_pictures();
+        tag.add_frame(Picture {
+            mime_type: "image/jpeg".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "YouTube thumbnail".to_string(),
+            data,
+        });
+    }
+
+    tag.write_to_path(path, Version::Id3v24)?;
+
+    Ok(())
+}
+
+fn is_mp3(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Reads the tags of any supported file: ID3v2 for MP3, Symphonia's metadata for everything else
+pub fn read_tags(path: &Path) -> TrackTags {
+    if is_mp3(path) {
+        if let Ok(tag) = Tag::read_from_path(path) {
+            return id3_to_track_tags(&tag);
+        }
+    }
+
+    read_symphonia_tags(path).unwrap_or_default()
+}
+
+/// Extracts the video ID from a YouTube link, e.g. `https://www.youtube.com/watch?v=<id>`
+/// or `https://youtu.be/<id>`, also when the link is part of a longer text
+pub fn video_id_from_url(text: &str) -> Option<String> {
+    let start = ["youtube.com/watch?v=", "youtu.be/", "youtube.com/shorts/"]
+        .iter()
+        .find_map(|prefix| text.find(prefix).map(|index| index + prefix.len()))?;
+
+    // IDs are 11 characters of [A-Za-z0-9_-]
+    let id: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+    (id.len() == 11).then_some(id)
+}
+
+fn id3_to_track_tags(tag: &Tag) -> TrackTags {
+    // Files tagged before the ID got its own frame, or by yt-dlp, still carry the link
+    let video_id = tag
+        .extended_texts()
+        .find(|text| text.description == YOUTUBE_ID_DESCRIPTION)
+        .and_then(|text| non_empty(&text.value))
+        .or_else(|| {
+            tag.extended_texts()
+                .filter(|text| {
+                    text.description
+                        .eq_ignore_ascii_case(YOUTUBE_URL_DESCRIPTION)
+                        || text.description.eq_ignore_ascii_case("purl")
+                })
+                .find_map(|text| video_id_from_url(&text.value))
+        })
+        .or_else(|| {
+            tag.comments()
+                .find_map(|comment| video_id_from_url(&comment.text))
+        });
+
+    TrackTags {
+        title: tag.title().and_then(non_empty),
+        artist: tag.artist().and_then(non_empty),
+        album: tag.album().and_then(non_empty),
+        track: tag.track(),
+        year: tag
+            .date_recorded()
+            .map(|date| date.year)
+            .or_else(|| tag.year())
+            .or_else(|| tag.date_released().map(|date| date.year)),
+        genre: tag.genre_parsed().and_then(|genre| non_empty(&genre)),
+        video_id,
+        channel: tag
+            .extended_texts()
+            .find(|text| text.description == YOUTUBE_CHANNEL_DESCRIPTION)
+            .and_then(|text| non_empty(&text.value)),
+    }
+}
+
+fn read_symphonia_tags(path: &Path) -> Option<TrackTags> {
+    let mut probed = probe(path)?;
+    let mut tags = TrackTags::default();
+
+    // Container level metadata (e.g. Vorbis comments, MP4 atoms) wins over metadata found while probing
+    let mut revisions = Vec::new();
+    if let Some(revision) = probed.metadata.get().and_then(|m| m.current().cloned()) {
+        revisions.push(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current().cloned() {
+        revisions.push(revision);
+    }
+
+    for revision in revisions {
+        for tag in revision.tags() {
+            let value = tag.value.to_string();
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => tags.title = non_empty(&value),
+                Some(StandardTagKey::Artist) => tags.artist = non_empty(&value),
+                Some(StandardTagKey::Album) => tags.album = non_empty(&value),
+                Some(StandardTagKey::Genre) => tags.genre = non_empty(&value),
+                Some(StandardTagKey::TrackNumber) => {
+                    tags.track = value.split('/').next().and_then(|n| n.trim().parse().ok())
+                }
+                Some(StandardTagKey::Date) => {
+                    tags.year = value.get(0..4).and_then(|year| year.parse().ok())
+                }
+                _ if tag.key.to_uppercase().ends_with(YOUTUBE_ID_DESCRIPTION) => {
+                    tags.video_id = non_empty(&value)
+                }
+                _ if tag
+                    .key
+                    .to_uppercase()
+                    .ends_with(YOUTUBE_CHANNEL_DESCRIPTION) =>
+                {
+                    tags.channel = non_empty(&value)
+                }
+                Some(StandardTagKey::Comment) | Some(StandardTagKey::Url)
+                    if tags.video_id.is_none() =>
+                {
+                    tags.video_id = video_id_from_url(&value)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Some(tags)
+}
+
+/// Writes tags back to the file. MP3s are tagged in place through ID3v2,
+/// other formats are remuxed by ffmpeg without re-encoding.
+pub fn write_tags(path: &Path, tags: &TrackTags) -> Result<(), Box<dyn Error>> {
+    if is_mp3(path) {
+        write_id3_tags(path, tags)
+    } else {
+        write_ffmpeg_tags(path, tags)
+    }
+}
+
+fn write_id3_tags(path: &Path, tags: &TrackTags) -> Result<(), Box<dyn Error>> {
+    let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+
+    match &tags.title {
+        Some(title) => tag.set_title(title.as_str()),
+        None => tag.remove_title(),
+    }
+    match &tags.artist {
+        Some(artist) => tag.set_artist(artist.as_str()),
+        None => tag.remove_artist(),
+    }
+    match &tags.album {
+        Some(album) => tag.set_album(album.as_str()),
+        None => tag.remove_album(),
+    }
+    match tags.track {
+        Some(track) => tag.set_track(track),
+        None => tag.remove_track(),
+    }
+    // ID3v2.4 keeps the year in TDRC. Players fall back to the release date and the ID3v2.3
+    // TYER frame yt-dlp and ffmpeg leave behind, so none of them may keep an old year.
+    match tags.year {
+        Some(year) => {
+            if tag.date_recorded().map(|date| date.year) != Some(year) {
+                tag.set_date_recorded(Timestamp {
+                    year,
+                    month: None,
+                    day: None,
+                    hour: None,
+                    minute: None,
+                    second: None,
+                });
+            }
+            if tag.date_released().is_some_and(|date| date.year != year) {
+                tag.remove_date_released();
+            }
+        }
+        None => {
+            tag.remove_date_recorded();
+            tag.remove_date_released();
+        }
+    }
+    tag.remove_year();
+    match &tags.genre {
+        Some(genre) => tag.set_genre(genre.as_str()),
+        None => tag.remove_genre(),
+    }
+    tag.remove_extended_text(Some(YOUTUBE_ID_DESCRIPTION), None);
+    if let Some(video_id) = &tags.video_id {
+        tag.add_frame(ExtendedText {
+            description: YOUTUBE_ID_DESCRIPTION.to_string(),
+            value: video_id.clone(),
+        });
+    }
+    tag.remove_extended_text(Some(YOUTUBE_CHANNEL_DESCRIPTION), None);
+    if let Some(channel) = &tags.channel {
+        tag.add_frame(ExtendedText {
+            description: YOUTUBE_CHANNEL_DESCRIPTION.to_string(),
+            value: channel.clone(),
+        });
+    }
+
+    tag.write_to_path(path, Version::Id3v24)?;
+
+    Ok(())
+}
+
+fn write_ffmpeg_tags(path: &Path, tags: &TrackTags) -> Result<(), Box<dyn Error>> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::other("path has no file name"))?
+        .to_string_lossy();
+    // The leading dot keeps the library from picking up the half written copy
+    let temp_path = path.with_file_name(format!(".tagging.{}", file_name));
+
+    let fields = [
+        ("title", tags.title.clone()),
+        ("artist", tags.artist.clone()),
+        ("album", tags.album.clone()),
+        ("track", tags.track.map(|track| track.to_string())),
+        ("date", tags.year.map(|year| year.to_string())),
+        ("genre", tags.genre.clone()),
+        (YOUTUBE_ID_DESCRIPTION, tags.video_id.clone()),
+        (YOUTUBE_CHANNEL_DESCRIPTION, tags.channel.clone()),
+    ];
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .arg("-y")
+        .arg("-i")
+        .arg(path)
+        .args(["-map", "0", "-c", "copy"]);
+    for (key, value) in fields {
+        command
+            .arg("-metadata")
+            .arg(format!("{}={}", key, value.unwrap_or_default()));
+    }
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("m4a") || ext.eq_ignore_ascii_case("mp4"))
+    {
+        // Needed for custom keys like the video ID to survive in MP4 containers
+        command.args(["-movflags", "use_metadata_tags"]);
+    }
+
+    let status = command
+        .arg(&temp_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(Box::new(std::io::Error::other(
+            "ffmpeg failed to write tags",
+        )));
+    }
+
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+/// MIME type and file extension of an image, from its first bytes
+fn image_type(data: &[u8]) -> (&'static str, &'static str) {
+    if data.starts_with(b"\x89PNG") {
+        ("image/png", "png")
+    } else if data.get(8..12) == Some(b"WEBP") {
+        ("image/webp", "webp")
+    } else {
+        ("image/jpeg", "jpg")
+    }
+}
+
+/// Replaces the attached picture of an MP4 file with ffmpeg, which only takes JPEG and PNG
+fn write_mp4_cover(path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let (_, extension) = image_type(data);
+    let (data, extension) = if extension == "webp" {
+        let mut png = Cursor::new(Vec::new());
+        image::load_from_memory(data)?.write_to(&mut png, image::ImageFormat::Png)?;
+        (png.into_inner(), "png")
+    } else {
+        (data.to_vec(), extension)
+    };
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::other("path has no file name"))?
+        .to_string_lossy();
+    let temp_path = path.with_file_name(format!(".tagging.{}", file_name));
+    let cover_path = path.with_file_name(format!(".cover.{}.{}", file_name, extension));
+    fs::write(&cover_path, data)?;
+
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(path)
+        .arg("-i")
+        .arg(&cover_path)
+        .args(["-map", "0:a", "-map", "1", "-c", "copy"])
+        .args(["-disposition:v:0", "attached_pic"])
+        .args(["-movflags", "use_metadata_tags"])
+        .arg(&temp_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = fs::remove_file(&cover_path);
+
+    if !status?.success() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(Box::new(std::io::Error::other(
+            "ffmpeg failed to embed the cover",
+        )));
+    }
+
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+/// Replaces the cover art embedded in a song with the given image as its front cover
+pub fn write_cover(path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let (mime_type, _) = image_type(data);
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp3" => {
+            let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+            tag.remove_all_pictures();
+            tag.add_frame(Picture {
+                mime_type: mime_type.to_string(),
+                picture_type: PictureType::CoverFront,
+                description: String::new(),
+                data: data.to_vec(),
+            });
+            tag.write_to_path(path, Version::Id3v24)?;
+            Ok(())
+        }
+        "flac" => embed_flac_cover(path, mime_type, data),
+        "ogg" | "oga" | "opus" => embed_ogg_cover(path, mime_type, data),
+        "m4a" | "mp4" | "alac" => write_mp4_cover(path, data),
+        _ => Err(Box::new(std::io::Error::other(format!(
+            "cover art cannot be embedded in .{} files",
+            extension
+        )))),
+    }
+}
+
+/// Extracts tags from a file name using a pattern like `%artist% - %title%`.
+/// Placeholders are %title%, %artist%, %album%, %track%, %year%, %genre% and %ignore%;
+/// returns None when the file name does not fit the pattern.
+pub fn tags_from_file_name(path: &Path, pattern: &str) -> Option<TrackTags> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+
+    // Split the pattern into literal text and placeholder names
+    let mut parts: Vec<(String, Option<String>)> = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('%') {
+        let end = rest[start + 1..].find('%')? + start + 1;
+        parts.push((
+            rest[..start].to_string(),
+            Some(rest[start + 1..end].to_lowercase()),
+        ));
+        rest = &rest[end + 1..];
+    }
+    parts.push((rest.to_string(), None));
+
+    let mut tags = TrackTags::default();
+    let mut remaining = stem.as_str();
+
+    for (i, (literal, placeholder)) in parts.iter().enumerate() {
+        remaining = remaining.strip_prefix(literal.as_str())?;
+        let Some(placeholder) = placeholder else {
+            break;
+        };
+
+        // A placeholder runs up to the next literal, or to the end of the name
+        let next_literal = parts
+            .get(i + 1)
+            .map(|(next, _)| next.as_str())
+            .unwrap_or("");
+        let is_last = i + 2 == parts.len();
+        let length = if next_literal.is_empty() && is_last {
+            remaining.len()
+        } else if next_literal.is_empty() {
+            return None;
+        } else {
+            remaining.find(next_literal)?
+        };
+
+        let value = non_empty(&remaining[..length]);
+        remaining = &remaining[length..];
+
+        match placeholder.as_str() {
+            "title" => tags.title = value,
+            "artist" => tags.artist = value,
+            "album" => tags.album = value,
+            "genre" => tags.genre = value,
+            "track" => tags.track = Some(value?.parse().ok()?),
+            "year" => tags.year = Some(value?.parse().ok()?),
+            "ignore" => {}
+            _ => return None,
+        }
+    }
+
+    remaining.is_empty().then_some(tags)
+}
+
+/// Removes "(Official Video)", "[Lyrics]" and the like from a video title
+pub fn clean_video_title(title: &str) -> String {
+    let mut cleaned = String::new();
+    let mut rest = title;
+
+    while let Some(start) = rest.find(['(', '[']) {
+        let close = if rest[start..].starts_with('(') {
+            ')'
+        } else {
+            ']'
+        };
+        let Some(end) = rest[start..].find(close).map(|end| start + end) else {
+            break;
+        };
+
+        cleaned.push_str(&rest[..start]);
+        let inner = rest[start + 1..end].to_lowercase();
+        let is_noise = inner
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| TITLE_NOISE.iter().any(|noise| word.starts_with(noise)));
+        if !is_noise {
+            cleaned.push_str(&rest[start..=end]);
+        }
+        rest = &rest[end + 1..];
+    }
+    cleaned.push_str(rest);
+
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Artist and title pairs a song is probably known by, best guess first.
+/// The artist is None for the last resort of searching by title alone.
+pub fn track_guesses(song: &Song) -> Vec<(Option<String>, String)> {
+    let title = clean_video_title(song.tags.title.as_deref().unwrap_or(&song.title));
+    let mut guesses = Vec::new();
+
+    if let Some(artist) = &song.tags.artist {
+        let artist = artist
+            .trim_end_matches(TOPIC_CHANNEL_SUFFIX)
+            .trim_end_matches("VEVO");
+        guesses.push((non_empty(artist), title.clone()));
+    }
+
+    // Video titles are often "Artist - Title"
+    if let Some((artist, rest)) = title.split_once(" - ") {
+        guesses.push((non_empty(artist), rest.trim().to_string()));
+    }
+
+    guesses.push((None, title));
+    guesses.dedup();
+    guesses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+
+    fn temp_mp3(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "rusty_songs_tags_{}_{}.mp3",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, []).unwrap();
+        path
+    }
+
+    fn date(text: &str) -> Timestamp {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn changing_the_year_replaces_every_date_frame() {
+        let path = temp_mp3("year");
+        let mut tag = Tag::new();
+        tag.set_year(2001);
+        tag.set_date_recorded(date("2001-05-06"));
+        tag.set_date_released(date("2001-05-06T07:08:09"));
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+
+        let tags = TrackTags {
+            year: Some(2020),
+            ..read_tags(&path)
+        };
+        write_tags(&path, &tags).unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.date_recorded(), Some(date("2020")));
+        assert_eq!(tag.date_released(), None);
+        assert_eq!(tag.year(), None);
+        assert_eq!(read_tags(&path).year, Some(2020));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeping_the_year_keeps_the_full_date() {
+        let path = temp_mp3("same_year");
+        let mut tag = Tag::new();
+        tag.set_year(2001);
+        tag.set_date_recorded(date("2001-05-06"));
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+
+        write_tags(&path, &read_tags(&path)).unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.date_recorded(), Some(date("2001-05-06")));
+        assert_eq!(tag.year(), None);
+
+        let tags = TrackTags {
+            year: None,
+            ..read_tags(&path)
+        };
+        write_tags(&path, &tags).unwrap();
+        assert_eq!(read_tags(&path).year, None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn the_channel_outlives_a_new_artist() {
+        let path = temp_mp3("channel");
+        let snippet = Snippet {
+            title: "Song (Official Video)".to_string(),
+            description: String::new(),
+            channel_title: "Label Uploads".to_string(),
+            published_at: None,
+            live_broadcast_content: None,
+            thumbnails: None,
+        };
+        write_youtube_tags(&path, "abcdefghijk", &snippet, None).unwrap();
+
+        let tags = read_tags(&path);
+        assert_eq!(tags.artist.as_deref(), Some("Label Uploads"));
+        assert_eq!(tags.channel.as_deref(), Some("Label Uploads"));
+
+        write_tags(
+            &path,
+            &TrackTags {
+                artist: Some("Artist".to_string()),
+                ..tags
+            },
+        )
+        .unwrap();
+        let tags = read_tags(&path);
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.channel.as_deref(), Some("Label Uploads"));
+        assert_eq!(tags.video_id.as_deref(), Some("abcdefghijk"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn finds_video_ids_in_links() {
+        for text in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "Downloaded from https://youtu.be/dQw4w9WgXcQ, enjoy",
+        ] {
+            assert_eq!(
+                video_id_from_url(text).as_deref(),
+                Some("dQw4w9WgXcQ"),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(video_id_from_url("https://youtu.be/tooShort"), None);
+        assert_eq!(video_id_from_url("https://vimeo.com/123456789"), None);
+    }
+
+    #[test]
+    fn older_downloads_are_identified_by_their_link() {
+        let path = temp_mp3("link");
+        let mut tag = Tag::new();
+        tag.add_frame(ExtendedText {
+            description: "purl".to_string(),
+            value: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+        });
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+        assert_eq!(read_tags(&path).video_id.as_deref(), Some("dQw4w9WgXcQ"));
+
+        let mut tag = Tag::new();
+        tag.add_frame(Comment {
+            lang: "eng".to_string(),
+            description: String::new(),
+            text: "https://youtu.be/abcdefghijk".to_string(),
+        });
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+        assert_eq!(read_tags(&path).video_id.as_deref(), Some("abcdefghijk"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_tags_from_file_names() {
+        let tags = tags_from_file_name(
+            Path::new("music/03 - Daft Punk - One More Time.flac"),
+            "%track% - %artist% - %title%",
+        )
+        .unwrap();
+        assert_eq!(
+            tags,
+            TrackTags {
+                title: Some("One More Time".to_string()),
+                artist: Some("Daft Punk".to_string()),
+                track: Some(3),
+                ..TrackTags::default()
+            }
+        );
+
+        let tags = tags_from_file_name(
+            Path::new("[1999] Album (remastered).mp3"),
+            "[%year%] %album% (%ignore%)",
+        )
+        .unwrap();
+        assert_eq!(tags.year, Some(1999));
+        assert_eq!(tags.album.as_deref(), Some("Album"));
+        assert_eq!(tags.title, None);
+    }
+
+    #[test]
+    fn rejects_file_names_that_do_not_fit() {
+        let path = Path::new("Artist - Title.mp3");
+        assert_eq!(
+            tags_from_file_name(path, "%artist% - %title% - %album%"),
+            None
+        );
+        assert_eq!(tags_from_file_name(path, "%track% - %title%"), None);
+        assert_eq!(tags_from_file_name(path, "%artist%%title%"), None);
+        assert_eq!(tags_from_file_name(path, "%artist% - %label%"), None);
+        assert_eq!(tags_from_file_name(path, "%artist - %title%"), None);
+        assert_eq!(tags_from_file_name(path, "%title%.mp3"), None);
+    }
+}